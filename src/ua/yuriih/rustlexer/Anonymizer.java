@@ -0,0 +1,162 @@
+package ua.yuriih.rustlexer;
+
+import java.util.HashMap;
+import java.util.List;
+import java.util.TreeSet;
+
+/**
+ * Hides what source code is about while keeping how it lexes, for sharing files that reproduce lexer
+ * or parser bugs.
+ */
+public final class Anonymizer {
+    private Anonymizer() {
+    }
+
+    public static String anonymize(String source) {
+        return anonymize(source, false);
+    }
+
+    /**
+     * Replaces letters and digits in comments and in string literals (except for escapes) with x, or \u00e4 for
+     * non-ASCII ones, keeping delimiters, line breaks and the length of the text. Text of error tokens, and of
+     * string literals with errors in them, is kept as it is.
+     * <p>
+     * The result is lexed again and checked to have the same kinds of tokens as the source.
+     *
+     * @param renameIdentifiers also rename identifiers to ident_1, ident_2 and so on, and lifetimes and labels
+     *                          to 'lt_1, 'lt_2, the same name always getting the same replacement. Ones
+     *                          that touch an identifier char, which the renamed one could merge with, keep
+     *                          their names
+     * @throws IllegalStateException if the result doesn't lex to the same kinds of tokens, which is a bug
+     */
+    public static String anonymize(String source, boolean renameIdentifiers) {
+        LexerOptions options = LexerOptions.builder().emitTrivia(true).build();
+        Lexer.Tokenized tokenized = Lexer.tokenize(source, options);
+        List<Token> tokens = tokenized.tokens;
+        HashMap<String, String> identifiers = new HashMap<>();
+        HashMap<String, String> lifetimes = new HashMap<>();
+        TreeSet<Integer> errorOffsets = new TreeSet<>();
+        for (LexError error : tokenized.errors)
+            errorOffsets.add(error.offset);
+
+        StringBuilder anonymized = new StringBuilder(source.length());
+        int position = 0;
+        for (Token token : tokens) {
+            //errors inside a literal overlap it
+            if (token.offset < position)
+                continue;
+            anonymized.append(source, position, token.offset);
+            position = token.end();
+            String text = source.substring(token.offset, token.end());
+            switch (token.type) {
+                case STRING_LITERAL, BYTE_STRING_LITERAL, RAW_STRING_LITERAL, RAW_BYTE_STRING_LITERAL -> {
+                    if (errorOffsets.subSet(token.offset, token.end()).isEmpty())
+                        anonymizeString(anonymized, token, text);
+                    else
+                        anonymized.append(text);
+                }
+                case COMMENT, COMMENT_INNER_DOC, COMMENT_OUTER_DOC, SHEBANG ->
+                        hide(anonymized, text, 0, text.length());
+                case IDENTIFIER, RAW_IDENTIFIER -> {
+                    if (!renameIdentifiers || token.isContextualKeyword() || touches(anonymized, source, token)) {
+                        anonymized.append(text);
+                    } else {
+                        boolean raw = token.type == TokenType.RAW_IDENTIFIER;
+                        String name = raw ? text.substring(2) : text;
+                        anonymized.append(raw ? "r#" : "").append(identifiers.computeIfAbsent(name,
+                                key -> "ident_" + (identifiers.size() + 1)));
+                    }
+                }
+                case LIFETIME, LABEL, RAW_LIFETIME, RAW_LABEL -> {
+                    boolean raw = token.type == TokenType.RAW_LIFETIME || token.type == TokenType.RAW_LABEL;
+                    String name = raw ? text.substring(3) : text.substring(1);
+                    boolean label = name.endsWith(":");
+                    if (label)
+                        name = name.substring(0, name.length() - 1);
+                    if (!renameIdentifiers || name.equals("_") || touches(anonymized, source, token)) {
+                        anonymized.append(text);
+                    } else {
+                        anonymized.append(raw ? "'r#" : "'")
+                                .append(lifetimes.computeIfAbsent(name, key -> "lt_" + (lifetimes.size() + 1)));
+                        if (label)
+                            anonymized.append(':');
+                    }
+                }
+                default -> anonymized.append(text);
+            }
+        }
+        anonymized.append(source, position, source.length());
+
+        Lexer.Tokenized result = Lexer.tokenize(anonymized.toString(), options);
+        if (!sameKinds(tokenized.tokens, result.tokens) || !sameKinds(tokenized.errors, result.errors))
+            throw new IllegalStateException("Anonymized source doesn't lex to the same tokens");
+        return anonymized.toString();
+    }
+
+    /**
+     * Whether a renamed token could merge with the one before or after it. Identifier chars that the lexer
+     * doesn't take for a literal's suffix, like the \u00e9 in 1\u00e9, would be taken for one if they were
+     * replaced with ASCII ones, so tokens right after or before an identifier char keep their names.
+     */
+    private static boolean touches(StringBuilder anonymized, String source, Token token) {
+        if (anonymized.length() > 0 && Lexer.isIdentifierChar(anonymized.codePointBefore(anonymized.length())))
+            return true;
+        if (token.end() >= source.length())
+            return false;
+        int next = source.codePointAt(token.end());
+        return Lexer.isIdentifierChar(next) || next == '\'';
+    }
+
+    private static boolean sameKinds(List<? extends Token> expected, List<? extends Token> actual) {
+        if (expected.size() != actual.size())
+            return false;
+        for (int i = 0; i < expected.size(); i++) {
+            Token a = expected.get(i);
+            Token b = actual.get(i);
+            if (a.type != b.type || (a instanceof LexError && ((LexError) a).kind != ((LexError) b).kind))
+                return false;
+        }
+        return true;
+    }
+
+    private static void anonymizeString(StringBuilder anonymized, Token token, String text) {
+        if (token.type == TokenType.RAW_STRING_LITERAL || token.type == TokenType.RAW_BYTE_STRING_LITERAL) {
+            int open = text.indexOf('"');
+            int close = text.lastIndexOf('"');
+            anonymized.append(text, 0, open + 1);
+            hide(anonymized, text, open + 1, close);
+            anonymized.append(text, close, text.length());
+            return;
+        }
+        HashMap<Integer, Integer> escapes = new HashMap<>();
+        for (Literal.EscapeSequence escape : new Literal(token).escapes())
+            escapes.put(escape.start, escape.end);
+        int open = text.indexOf('"');
+        int close = text.lastIndexOf('"');
+        anonymized.append(text, 0, open + 1);
+        int i = open + 1;
+        while (i < close) {
+            if (text.charAt(i) == '\\') {
+                //after a malformed escape it's hard to tell what the lexer makes of the rest
+                int end = escapes.getOrDefault(i, close);
+                anonymized.append(text, i, end);
+                i = end;
+            } else {
+                hide(anonymized, text, i, i + 1);
+                i++;
+            }
+        }
+        anonymized.append(text, close, text.length());
+    }
+
+    private static void hide(StringBuilder anonymized, String text, int start, int end) {
+        for (int i = start; i < end; i++) {
+            char c = text.charAt(i);
+            if (c < 128)
+                anonymized.append(Character.isLetterOrDigit(c) ? 'x' : c);
+            else
+                //still not ASCII, for byte strings
+                anonymized.append(Character.isLetterOrDigit(c) || Character.isSurrogate(c) ? '\u00e4' : c);
+        }
+    }
+}
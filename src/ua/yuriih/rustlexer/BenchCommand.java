@@ -0,0 +1,292 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.PrintStream;
+import java.lang.management.ManagementFactory;
+import java.lang.ref.Reference;
+import java.nio.charset.StandardCharsets;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.Comparator;
+import java.util.LinkedHashMap;
+import java.util.List;
+import java.util.Locale;
+import java.util.Map;
+
+/**
+ * rlex bench: lexes a file or every .rs file under a directory several times and reports throughput.
+ * Files are read into memory first, so only lexing is timed. Times are medians over the measured iterations,
+ * after a few warm-up ones to let the JIT compile the lexer.
+ * <p>
+ * With --token-buffer, lexing into a {@code List<Token>} is compared with lexing into a {@link TokenBuffer}
+ * instead: tokens/s of each, and how much heap the tokens of all the files take up once lexed, per token.
+ */
+final class BenchCommand {
+    private static final int SLOWEST_FILES = 20;
+    //how much source the heap usage of tokens is measured on, at least
+    private static final long MEASURED_BYTES = 4_000_000;
+
+    private final PrintStream out;
+    private final LexerOptions options;
+    private final int warmup;
+    private final int iterations;
+    private final boolean json;
+    private final boolean tokenBuffer;
+
+    BenchCommand(PrintStream out, LexerOptions options, int warmup, int iterations, boolean json,
+                 boolean tokenBuffer) {
+        this.out = out;
+        this.options = options;
+        this.warmup = warmup;
+        this.iterations = iterations;
+        this.json = json;
+        this.tokenBuffer = tokenBuffer;
+    }
+
+    /**
+     * @return exit status: 0, or 1 if there were no files to lex
+     */
+    int run(Path path, List<String> excludes) throws IOException {
+        List<Path> paths = Files.isDirectory(path) ? SourceFiles.find(path, excludes) : List.of(path);
+        ArrayList<FileResult> files = new ArrayList<>();
+        for (Path file : paths) {
+            byte[] bytes = Files.readAllBytes(file);
+            files.add(new FileResult(file, bytes.length, new String(bytes, StandardCharsets.UTF_8)));
+        }
+        if (files.isEmpty()) {
+            System.err.println("No files to lex in " + path);
+            return 1;
+        }
+        if (tokenBuffer)
+            return compareStorage(files);
+
+        long[] totals = new long[iterations];
+        for (int i = 0; i < warmup + iterations; i++) {
+            long total = 0;
+            for (FileResult file : files) {
+                long[] tokens = {0};
+                long start = System.nanoTime();
+                Lexer.fromString(file.source, options).parse(token -> tokens[0]++);
+                long nanos = System.nanoTime() - start;
+                total += nanos;
+                file.tokens = tokens[0];
+                if (i >= warmup)
+                    file.nanos[i - warmup] = nanos;
+            }
+            if (i >= warmup)
+                totals[i - warmup] = total;
+        }
+
+        long bytes = 0;
+        long tokens = 0;
+        for (FileResult file : files) {
+            bytes += file.bytes;
+            tokens += file.tokens;
+        }
+        long median = median(totals);
+        files.sort(Comparator.comparingLong((FileResult file) -> median(file.nanos)).reversed());
+        long peakRss = peakRss();
+        if (json)
+            printJson(files, bytes, tokens, median, peakRss);
+        else
+            printText(files, bytes, tokens, median, peakRss);
+        return 0;
+    }
+
+    private void printText(List<FileResult> files, long bytes, long tokens, long nanos, long peakRss) {
+        out.printf(Locale.ROOT, "%d files, %d bytes, %d tokens, %d iterations (%d warm-up)%n",
+                files.size(), bytes, tokens, iterations, warmup);
+        out.printf(Locale.ROOT, "median %.3f ms, %.2f MB/s, %.0f tokens/s%n",
+                nanos / 1e6, megabytesPerSecond(bytes, nanos), perSecond(tokens, nanos));
+        if (peakRss >= 0)
+            out.printf(Locale.ROOT, "peak RSS %.1f MB%n", peakRss / 1e6);
+        if (files.size() > 1) {
+            out.println("slowest files:");
+            for (FileResult file : files.subList(0, Math.min(SLOWEST_FILES, files.size()))) {
+                long fileNanos = median(file.nanos);
+                out.printf(Locale.ROOT, "  %10.3f ms %8.2f MB/s %10d tokens  %s%n", fileNanos / 1e6,
+                        megabytesPerSecond(file.bytes, fileNanos), file.tokens, file.path);
+            }
+        }
+        out.flush();
+    }
+
+    private void printJson(List<FileResult> files, long bytes, long tokens, long nanos, long peakRss) {
+        LinkedHashMap<String, Object> result = new LinkedHashMap<>();
+        result.put("files", files.size());
+        result.put("bytes", bytes);
+        result.put("tokens", tokens);
+        result.put("iterations", iterations);
+        result.put("warmup", warmup);
+        result.put("medianNanos", nanos);
+        result.put("megabytesPerSecond", megabytesPerSecond(bytes, nanos));
+        result.put("tokensPerSecond", perSecond(tokens, nanos));
+        result.put("peakRssBytes", peakRss >= 0 ? peakRss : null);
+        ArrayList<Object> perFile = new ArrayList<>();
+        for (FileResult file : files) {
+            long fileNanos = median(file.nanos);
+            LinkedHashMap<String, Object> entry = new LinkedHashMap<>();
+            entry.put("path", file.path.toString().replace('\\', '/'));
+            entry.put("bytes", file.bytes);
+            entry.put("tokens", file.tokens);
+            entry.put("medianNanos", fileNanos);
+            entry.put("megabytesPerSecond", megabytesPerSecond(file.bytes, fileNanos));
+            perFile.add(entry);
+        }
+        result.put("perFile", perFile);
+        out.println(Json.write(result));
+        out.flush();
+    }
+
+    private int compareStorage(List<FileResult> files) throws IOException {
+        long[] listTotals = new long[iterations];
+        long[] bufferTotals = new long[iterations];
+        long tokens = 0;
+        for (int i = 0; i < warmup + iterations; i++) {
+            long listTotal = 0;
+            long bufferTotal = 0;
+            tokens = 0;
+            for (FileResult file : files) {
+                long start = System.nanoTime();
+                ArrayList<Token> list = lexToList(file.source);
+                listTotal += System.nanoTime() - start;
+                start = System.nanoTime();
+                TokenBuffer buffer = lexToBuffer(file.source);
+                bufferTotal += System.nanoTime() - start;
+                tokens += list.size();
+                Reference.reachabilityFence(buffer);
+            }
+            if (i >= warmup) {
+                listTotals[i - warmup] = listTotal;
+                bufferTotals[i - warmup] = bufferTotal;
+            }
+        }
+
+        long bytes = 0;
+        for (FileResult file : files)
+            bytes += file.bytes;
+        //small inputs are lexed several times over, so the tokens outweigh the noise in heap usage
+        int copies = (int) Math.max(1, MEASURED_BYTES / Math.max(bytes, 1));
+        long listBytes = retainedBytes(files, copies, false);
+        long bufferBytes = retainedBytes(files, copies, true);
+        long measuredTokens = tokens * copies;
+
+        long listNanos = median(listTotals);
+        long bufferNanos = median(bufferTotals);
+        if (json) {
+            LinkedHashMap<String, Object> result = new LinkedHashMap<>();
+            result.put("files", files.size());
+            result.put("bytes", bytes);
+            result.put("tokens", tokens);
+            result.put("iterations", iterations);
+            result.put("warmup", warmup);
+            result.put("list", storageObject(tokens, listNanos, listBytes, measuredTokens));
+            result.put("tokenBuffer", storageObject(tokens, bufferNanos, bufferBytes, measuredTokens));
+            out.println(Json.write(result));
+        } else {
+            out.printf(Locale.ROOT, "%d files, %d bytes, %d tokens, %d iterations (%d warm-up)%n",
+                    files.size(), bytes, tokens, iterations, warmup);
+            out.printf(Locale.ROOT, "List<Token>  median %.3f ms, %.0f tokens/s, %.1f bytes/token%n",
+                    listNanos / 1e6, perSecond(tokens, listNanos), perToken(listBytes, measuredTokens));
+            out.printf(Locale.ROOT, "TokenBuffer  median %.3f ms, %.0f tokens/s, %.1f bytes/token%n",
+                    bufferNanos / 1e6, perSecond(tokens, bufferNanos), perToken(bufferBytes, measuredTokens));
+        }
+        out.flush();
+        return 0;
+    }
+
+    private ArrayList<Token> lexToList(String source) throws IOException {
+        ArrayList<Token> list = new ArrayList<>();
+        Lexer.fromString(source, options).parse(list::add);
+        return list;
+    }
+
+    private TokenBuffer lexToBuffer(String source) throws IOException {
+        TokenBuffer buffer = new TokenBuffer();
+        Lexer.fromString(source, options).parse(buffer::add);
+        return buffer;
+    }
+
+    /**
+     * @return how much more heap is in use while the tokens of all the files, lexed the given number of times,
+     * are kept
+     */
+    private long retainedBytes(List<FileResult> files, int copies, boolean tokenBuffer) throws IOException {
+        ArrayList<Object> kept = new ArrayList<>(files.size() * copies);
+        long before = usedHeap();
+        for (int i = 0; i < copies; i++) {
+            for (FileResult file : files)
+                kept.add(tokenBuffer ? lexToBuffer(file.source) : lexToList(file.source));
+        }
+        long after = usedHeap();
+        Reference.reachabilityFence(kept);
+        return after - before;
+    }
+
+    private static Map<String, Object> storageObject(long tokens, long nanos, long bytes, long measuredTokens) {
+        LinkedHashMap<String, Object> object = new LinkedHashMap<>();
+        object.put("medianNanos", nanos);
+        object.put("tokensPerSecond", perSecond(tokens, nanos));
+        object.put("bytesPerToken", perToken(bytes, measuredTokens));
+        return object;
+    }
+
+    private static double perToken(long bytes, long tokens) {
+        return tokens == 0 ? 0 : (double) Math.max(bytes, 0) / tokens;
+    }
+
+    /**
+     * Heap in use after a few collections, so it's mostly what's reachable. Only approximate, the collector
+     * isn't required to collect everything.
+     */
+    private static long usedHeap() {
+        for (int i = 0; i < 3; i++)
+            System.gc();
+        return ManagementFactory.getMemoryMXBean().getHeapMemoryUsage().getUsed();
+    }
+
+    private static double megabytesPerSecond(long bytes, long nanos) {
+        return perSecond(bytes, nanos) / 1e6;
+    }
+
+    private static double perSecond(long count, long nanos) {
+        return nanos == 0 ? 0 : count * 1e9 / nanos;
+    }
+
+    private static long median(long[] values) {
+        long[] sorted = values.clone();
+        Arrays.sort(sorted);
+        return sorted[sorted.length / 2];
+    }
+
+    /**
+     * @return the process's peak resident set size from /proc, or -1 where there's no /proc
+     */
+    private static long peakRss() {
+        try {
+            for (String line : Files.readAllLines(Path.of("/proc/self/status"))) {
+                if (line.startsWith("VmHWM:"))
+                    return Long.parseLong(line.replaceAll("[^0-9]", "")) * 1024;
+            }
+        } catch (IOException | NumberFormatException e) {
+            return -1;
+        }
+        return -1;
+    }
+
+    private final class FileResult {
+        private final Path path;
+        private final long bytes;
+        private final String source;
+        private final long[] nanos = new long[iterations];
+        private long tokens;
+
+        private FileResult(Path path, long bytes, String source) {
+            this.path = path;
+            this.bytes = bytes;
+            this.source = source;
+        }
+    }
+}
@@ -0,0 +1,201 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Finds #[cfg(...)] and #![cfg(...)] attributes and guesses which tokens each one governs, without parsing.
+ * Tools can use it to approximate the tokens that are compiled under a configuration.
+ * <p>
+ * It's a heuristic, and it goes wrong where the item's syntax would be needed:
+ * <ul>
+ * <li>An outer attribute governs the tokens after it up to the end of the item: the first ; or , outside
+ * delimiters, or the first {} group, whichever comes first. Items that end with a ; but can have a {} group before
+ * it (let, const, static, type and use, after pub and pub(...)) go on to the ;. A const generic block in a type,
+ * like Foo&lt;{ N }&gt;, still ends the item too early, and so does a struct expression in a statement that doesn't
+ * start with let, or the first block of an if with an else.</li>
+ * <li>An item also ends before a closing delimiter of the group it's in, so the last field or match arm without
+ * a trailing comma is found. The comma after a field or arm is part of it.</li>
+ * <li>An inner attribute governs the rest of the group it's in, or of the file. The item that the group belongs
+ * to, like the mod m before its {, isn't included.</li>
+ * <li>Only cfg itself is recognized: cfg_attr, and cfg inside macro definitions and invocations, are treated
+ * like any other tokens. Groups are matched by delimiter only, like in {@link TokenTree}.</li>
+ * </ul>
+ */
+public final class CfgRegions {
+    private CfgRegions() {
+    }
+
+    /**
+     * @param tokens with or without trivia
+     * @return regions in the order of their attributes, regions of attributes inside other regions are included
+     */
+    public static List<Region> find(List<Token> tokens) {
+        ArrayList<Region> regions = new ArrayList<>();
+        for (int i = 0; i < tokens.size(); i++) {
+            if (tokens.get(i).type != TokenType.POUND)
+                continue;
+            int bang = next(tokens, i + 1);
+            boolean inner = bang < tokens.size() && tokens.get(bang).type == TokenType.NOT;
+            int square = inner ? next(tokens, bang + 1) : bang;
+            if (square >= tokens.size() || tokens.get(square).type != TokenType.SQUARE_L)
+                continue;
+            int cfg = next(tokens, square + 1);
+            if (cfg >= tokens.size() || tokens.get(cfg).type != TokenType.IDENTIFIER
+                    || !"cfg".equals(tokens.get(cfg).text()))
+                continue;
+            int paren = next(tokens, cfg + 1);
+            if (paren >= tokens.size() || tokens.get(paren).type != TokenType.PAREN_L)
+                continue;
+            int parenClose = groupEnd(tokens, paren);
+            int squareClose = next(tokens, parenClose + 1);
+            if (parenClose >= tokens.size() || squareClose >= tokens.size()
+                    || tokens.get(squareClose).type != TokenType.SQUARE_R)
+                continue;
+
+            ArrayList<Token> predicate = new ArrayList<>();
+            for (int j = paren + 1; j < parenClose; j++) {
+                if (!tokens.get(j).type.isTrivia())
+                    predicate.add(tokens.get(j));
+            }
+            int start = squareClose + 1;
+            int end = inner ? enclosingEnd(tokens, start) : itemEnd(tokens, start);
+            regions.add(new Region(i, start, end, inner, predicate));
+        }
+        return Collections.unmodifiableList(regions);
+    }
+
+    /**
+     * @return index of the first token from the index that isn't trivia, or the size
+     */
+    private static int next(List<Token> tokens, int from) {
+        int i = from;
+        while (i < tokens.size() && tokens.get(i).type.isTrivia())
+            i++;
+        return i;
+    }
+
+    /**
+     * @return index of the delimiter that closes the group opened at the index, or the size
+     */
+    private static int groupEnd(List<Token> tokens, int open) {
+        int depth = 0;
+        for (int i = open; i < tokens.size(); i++) {
+            TokenType type = tokens.get(i).type;
+            if (TokenTree.isOpening(type))
+                depth++;
+            else if (TokenTree.isClosing(type) && --depth == 0)
+                return i;
+        }
+        return tokens.size();
+    }
+
+    /**
+     * @return index of the closing delimiter of the group the index is in, or the size
+     */
+    private static int enclosingEnd(List<Token> tokens, int from) {
+        int depth = 0;
+        for (int i = from; i < tokens.size(); i++) {
+            TokenType type = tokens.get(i).type;
+            if (TokenTree.isOpening(type))
+                depth++;
+            else if (TokenTree.isClosing(type) && depth-- == 0)
+                return i;
+        }
+        return tokens.size();
+    }
+
+    /**
+     * @return end index, exclusive, of the item that starts at the index, other attributes included
+     */
+    private static int itemEnd(List<Token> tokens, int from) {
+        boolean untilSemicolon = false;
+        boolean sawKeyword = false;
+        boolean attribute = false;
+        int depth = 0;
+        for (int i = from; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            TokenType type = token.type;
+            if (type.isTrivia() || type == TokenType.COMMENT_OUTER_DOC)
+                continue;
+            if (depth == 0 && !sawKeyword && type != TokenType.POUND && type != TokenType.NOT
+                    && !(type == TokenType.SQUARE_L && attribute)) {
+                //the first token after the attributes, or after pub(...)
+                if (type == TokenType.PUB) {
+                    int paren = next(tokens, i + 1);
+                    if (paren < tokens.size() && tokens.get(paren).type == TokenType.PAREN_L)
+                        i = groupEnd(tokens, paren);
+                    continue;
+                }
+                sawKeyword = true;
+                untilSemicolon = switch (type) {
+                    case LET, STATIC, TYPE, USE -> true;
+                    //not const fn or a const block
+                    case CONST -> {
+                        int name = next(tokens, i + 1);
+                        yield name < tokens.size() && (tokens.get(name).type == TokenType.IDENTIFIER
+                                || tokens.get(name).type == TokenType.UNDERSCORE);
+                    }
+                    default -> false;
+                };
+            }
+            attribute = depth == 0 && (type == TokenType.POUND || (type == TokenType.NOT && attribute));
+
+            if (TokenTree.isOpening(type)) {
+                depth++;
+            } else if (TokenTree.isClosing(type)) {
+                if (depth == 0)
+                    return i;
+                depth--;
+                if (depth == 0 && type == TokenType.CURLY_R && !untilSemicolon)
+                    return i + 1;
+            } else if (depth == 0 && (type == TokenType.SEMICOLON || (type == TokenType.COMMA && !untilSemicolon))) {
+                return i + 1;
+            }
+        }
+        return tokens.size();
+    }
+
+    public static final class Region {
+        /** Index of the # of the attribute */
+        public final int attributeStart;
+        /** Index of the first token the attribute governs, the one after its ] */
+        public final int start;
+        /** Index after the last token the attribute governs */
+        public final int end;
+        /** True for #![cfg(...)] */
+        public final boolean inner;
+        /** Tokens between the parentheses, without trivia */
+        public final List<Token> predicate;
+
+        private Region(int attributeStart, int start, int end, boolean inner, List<Token> predicate) {
+            this.attributeStart = attributeStart;
+            this.start = start;
+            this.end = end;
+            this.inner = inner;
+            this.predicate = Collections.unmodifiableList(predicate);
+        }
+
+        /**
+         * The predicate written out the way rustfmt would, e.g. all(unix, feature = "serde").
+         */
+        public String predicateText() {
+            StringBuilder text = new StringBuilder();
+            for (Token token : predicate) {
+                if (token.type == TokenType.EQ)
+                    text.append(" = ");
+                else if (token.type == TokenType.COMMA)
+                    text.append(", ");
+                else
+                    text.append(token.text());
+            }
+            return text.toString();
+        }
+
+        @Override
+        public String toString() {
+            return (inner ? "#![cfg(" : "#[cfg(") + predicateText() + ")] " + start + ".." + end;
+        }
+    }
+}
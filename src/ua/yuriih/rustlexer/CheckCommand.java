@@ -0,0 +1,120 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.PrintStream;
+import java.nio.file.Files;
+import java.nio.file.NoSuchFileException;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.Comparator;
+import java.util.List;
+
+/**
+ * rlex check: lexes files and prints their errors and warnings, for pre-commit hooks. The exit status is
+ * {@link #CLEAN}, {@link #WARNINGS} or {@link #ERRORS}.
+ */
+final class CheckCommand {
+    static final int CLEAN = 0;
+    static final int WARNINGS = 1;
+    /** Also for files that can't be read, aren't valid UTF-8 or end inside a string or block comment */
+    static final int ERRORS = 2;
+
+    private final PrintStream out;
+    private final boolean quiet;
+    private final boolean denyWarnings;
+    private int fileCount;
+    private int errorCount;
+    private int warningCount;
+
+    /**
+     * @param quiet        print nothing, only set the exit status
+     * @param denyWarnings exit with {@link #ERRORS} if there are warnings
+     */
+    CheckCommand(PrintStream out, boolean quiet, boolean denyWarnings) {
+        this.out = out;
+        this.quiet = quiet;
+        this.denyWarnings = denyWarnings;
+    }
+
+    /**
+     * @param paths files, and directories to check every .rs file under
+     */
+    int run(List<Path> paths) {
+        for (Path path : paths) {
+            if (Files.isDirectory(path)) {
+                List<Path> files;
+                try {
+                    files = SourceFiles.find(path);
+                } catch (IOException e) {
+                    fail(path, e.getMessage());
+                    continue;
+                }
+                for (Path file : files)
+                    check(file);
+            } else {
+                check(path);
+            }
+        }
+        if (!quiet)
+            out.printf("%d files, %d errors, %d warnings%n", fileCount, errorCount, warningCount);
+        if (errorCount > 0 || (denyWarnings && warningCount > 0))
+            return ERRORS;
+        return warningCount > 0 ? WARNINGS : CLEAN;
+    }
+
+    private void check(Path file) {
+        fileCount++;
+        ArrayList<LexWarning> warnings = new ArrayList<>();
+        LexerOptions options = LexerOptions.builder().warningSink(warnings::add).build();
+        List<Token> tokens;
+        try {
+            tokens = Lexer.fromBytes(Files.readAllBytes(file), Lexer.Utf8Policy.ERROR, options).parse();
+        } catch (NoSuchFileException e) {
+            fail(file, "No such file");
+            return;
+        } catch (IOException e) {
+            fail(file, e.getMessage());
+            return;
+        }
+
+        ArrayList<Diagnostic> diagnostics = new ArrayList<>();
+        for (Token token : tokens) {
+            if (token instanceof LexError) {
+                LexError error = (LexError) token;
+                diagnostics.add(new Diagnostic(error.line, error.column, error.offset,
+                        "error[" + error.kind.code + "]: " + error.value));
+            }
+        }
+        errorCount += diagnostics.size();
+        warningCount += warnings.size();
+        if (quiet)
+            return;
+        for (LexWarning warning : warnings) {
+            diagnostics.add(new Diagnostic(warning.line, warning.column, warning.offset,
+                    "warning[" + warning.kind.code + "]: " + warning.message));
+        }
+        diagnostics.sort(Comparator.comparingInt(diagnostic -> diagnostic.offset));
+        for (Diagnostic diagnostic : diagnostics)
+            out.printf("%s:%d:%d: %s%n", file, diagnostic.line + 1, diagnostic.column + 1, diagnostic.message);
+    }
+
+    private void fail(Path path, String message) {
+        errorCount++;
+        if (!quiet)
+            out.println(path + ": error: " + message);
+    }
+
+    private static final class Diagnostic {
+        final int line;
+        final int column;
+        final int offset;
+        final String message;
+
+        Diagnostic(int line, int column, int offset, String message) {
+            this.line = line;
+            this.column = column;
+            this.offset = offset;
+            this.message = message;
+        }
+    }
+}
@@ -0,0 +1,65 @@
+package ua.yuriih.rustlexer;
+
+import java.io.Reader;
+import java.util.Iterator;
+
+/**
+ * Reads a text that's split into chunks, like the leaves of a rope, without joining them.
+ */
+final class ChunkReader extends Reader {
+    private final Iterator<? extends CharSequence> chunks;
+    private CharSequence chunk = "";
+    private int position = 0;
+
+    ChunkReader(Iterable<? extends CharSequence> chunks) {
+        this.chunks = chunks.iterator();
+    }
+
+    // moves to the next non-empty chunk, false at the end
+    private boolean nextChunk() {
+        while (position >= chunk.length()) {
+            if (!chunks.hasNext())
+                return false;
+            chunk = chunks.next();
+            position = 0;
+        }
+        return true;
+    }
+
+    @Override
+    public int read() {
+        if (!nextChunk())
+            return -1;
+        return chunk.charAt(position++);
+    }
+
+    @Override
+    public int read(char[] buffer, int offset, int length) {
+        if (length == 0)
+            return 0;
+        int read = 0;
+        while (read < length && nextChunk()) {
+            int count = Math.min(length - read, chunk.length() - position);
+            for (int i = 0; i < count; i++)
+                buffer[offset + read + i] = chunk.charAt(position + i);
+            position += count;
+            read += count;
+        }
+        return read == 0 ? -1 : read;
+    }
+
+    @Override
+    public long skip(long count) {
+        long skipped = 0;
+        while (skipped < count && nextChunk()) {
+            int step = (int) Math.min(count - skipped, chunk.length() - position);
+            position += step;
+            skipped += step;
+        }
+        return skipped;
+    }
+
+    @Override
+    public void close() {
+    }
+}
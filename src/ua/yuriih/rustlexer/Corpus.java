@@ -0,0 +1,129 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.InputStream;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.Comparator;
+import java.util.EnumMap;
+import java.util.List;
+import java.util.Map;
+import java.util.logging.Level;
+import java.util.logging.Logger;
+
+/**
+ * Lexes every Rust file under a directory and aggregates the results, see {@link #run}.
+ */
+public final class Corpus {
+    private static final Logger LOG = Logger.getLogger(Corpus.class.getName());
+    private static final int SLOWEST_FILES = 10;
+
+    private Corpus() {
+    }
+
+    /**
+     * Files are found with {@link SourceFiles#find(Path)}; tokens aren't kept, so memory use
+     * doesn't grow with the size of the tree.
+     */
+    public static Report run(Path root, LexerOptions options) throws IOException {
+        Report report = new Report();
+        for (Path file : SourceFiles.find(root)) {
+            long start = System.nanoTime();
+            long[] tokenCount = {0};
+            try (InputStream in = Files.newInputStream(file)) {
+                Lexer lexer = Lexer.withOptions(in, options);
+                lexer.parse(token -> {
+                    tokenCount[0]++;
+                    if (token instanceof LexError)
+                        report.errors.merge(((LexError) token).kind, 1, Integer::sum);
+                });
+                if (options.profile)
+                    report.profile = report.profile == null ? lexer.profile() : report.profile.plus(lexer.profile());
+            } catch (IOException e) {
+                report.unreadable.add(file);
+                continue;
+            }
+            long nanos = System.nanoTime() - start;
+            if (options.log)
+                LOG.log(Level.FINE, "{0}: {1,number,#} tokens in {2,number,#} us",
+                        new Object[] {file, tokenCount[0], nanos / 1000});
+
+            report.files++;
+            report.tokens += tokenCount[0];
+            report.slowest.add(new FileTime(file, nanos));
+            report.slowest.sort(Comparator.comparingLong((FileTime time) -> time.nanos).reversed());
+            if (report.slowest.size() > SLOWEST_FILES)
+                report.slowest.remove(SLOWEST_FILES);
+        }
+        return report;
+    }
+
+    public static final class Report {
+        private int files = 0;
+        private long tokens = 0;
+        private final EnumMap<LexError.Kind, Integer> errors = new EnumMap<>(LexError.Kind.class);
+        private final ArrayList<FileTime> slowest = new ArrayList<>();
+        private final ArrayList<Path> unreadable = new ArrayList<>();
+        private Lexer.Profile profile;
+
+        private Report() {
+        }
+
+        public int files() {
+            return files;
+        }
+
+        public long tokens() {
+            return tokens;
+        }
+
+        public int errorCount() {
+            int count = 0;
+            for (int n : errors.values())
+                count += n;
+            return count;
+        }
+
+        public Map<LexError.Kind, Integer> errorsByKind() {
+            return Collections.unmodifiableMap(errors);
+        }
+
+        /**
+         * @return up to 10 files that took the longest to lex, slowest first
+         */
+        public List<FileTime> slowestFiles() {
+            return Collections.unmodifiableList(slowest);
+        }
+
+        /**
+         * @return files that couldn't be read, they are not counted in {@link #files()}
+         */
+        public List<Path> unreadableFiles() {
+            return Collections.unmodifiableList(unreadable);
+        }
+
+        /**
+         * @return time spent in each phase over all files, null unless {@link LexerOptions.Builder#profile profiling}
+         */
+        public Lexer.Profile profile() {
+            return profile;
+        }
+    }
+
+    public static final class FileTime {
+        public final Path path;
+        public final long nanos;
+
+        private FileTime(Path path, long nanos) {
+            this.path = path;
+            this.nanos = nanos;
+        }
+
+        @Override
+        public String toString() {
+            return path + " " + nanos / 1_000_000 + "ms";
+        }
+    }
+}
@@ -0,0 +1,29 @@
+package ua.yuriih.rustlexer;
+
+public class CustomToken extends Token {
+    public final String kind;
+
+    public CustomToken(int line, int column, int offset, String kind, String value) {
+        this(line, column, offset, kind, value, 0);
+    }
+
+    CustomToken(int line, int column, int offset, String kind, String value, int flags) {
+        super(line, column, offset, value.length(), TokenType.CUSTOM, value, flags);
+        this.kind = kind;
+    }
+
+    @Override
+    Token copy(int line, int column, int offset, int flags) {
+        return new CustomToken(line, column, offset, kind, value, flags);
+    }
+
+    @Override
+    public boolean equals(Object o) {
+        return super.equals(o) && kind.equals(((CustomToken) o).kind);
+    }
+
+    @Override
+    public int hashCode() {
+        return 31 * super.hashCode() + kind.hashCode();
+    }
+}
@@ -0,0 +1,45 @@
+package ua.yuriih.rustlexer;
+
+/**
+ * Lets embedders lex their own tokens, e.g. a `{{ name }}` template marker. Hooks are asked about every char
+ * that starts a token with {@link #start(char, CharSequence)} before the lexer's own rules, and about chars
+ * the lexer would otherwise report as errors with {@link #start(char)}. When no hook claims a char, it's lexed
+ * as usual.
+ */
+public interface CustomTokenHook {
+    /**
+     * How many chars after the first one {@link #start(char, CharSequence)} can see
+     */
+    int LOOKAHEAD = 16;
+
+    /**
+     * Kind name given to tokens produced by this hook.
+     */
+    String kind();
+
+    /**
+     * Called before the lexer's own rules, so a hook can claim chars that would start a Rust token,
+     * like the { of {{ name }}.
+     * @param ahead the next {@link #LOOKAHEAD} chars after c, fewer near the end of the input; only valid during
+     *              the call
+     * @return true to start a custom token with this character
+     */
+    default boolean start(char c, CharSequence ahead) {
+        return false;
+    }
+
+    /**
+     * Called for characters that aren't part of Rust's syntax and would be an error otherwise.
+     * @return true to start a custom token with this character
+     */
+    default boolean start(char c) {
+        return false;
+    }
+
+    /**
+     * Called for each character after the start of the token.
+     * @param text token text collected so far
+     * @return false if c is not part of the token anymore, it will then be lexed as usual
+     */
+    boolean accept(CharSequence text, char c);
+}
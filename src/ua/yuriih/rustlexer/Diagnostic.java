@@ -0,0 +1,130 @@
+package ua.yuriih.rustlexer;
+
+import java.util.Locale;
+
+/**
+ * A lexical error or warning with what's needed to show it, rendered the way miette and rustc draw their reports:
+ * the message, then the source lines around the span with the span underlined.
+ */
+public final class Diagnostic {
+    private static final int CONTEXT_LINES = 1;
+    private static final int TAB_WIDTH = 4;
+
+    public enum Severity {
+        ERROR,
+        WARNING
+    }
+
+    public final Severity severity;
+    /** {@link LexError.Kind#code} or {@link LexWarning.Kind#code} */
+    public final String code;
+    public final String message;
+    /** What to do about it, or null */
+    public final String help;
+    public final int line;
+    public final int column;
+    public final int offset;
+    public final int length;
+
+    private Diagnostic(Severity severity, String code, String message, String help, int line, int column, int offset,
+                       int length) {
+        this.severity = severity;
+        this.code = code;
+        this.message = message;
+        this.help = help;
+        this.line = line;
+        this.column = column;
+        this.offset = offset;
+        this.length = length;
+    }
+
+    public static Diagnostic of(LexError error) {
+        return new Diagnostic(Severity.ERROR, error.kind.code, error.value, null, error.line, error.column,
+                error.offset, error.length);
+    }
+
+    /**
+     * The warning's suggestion, if it has one, becomes the help.
+     */
+    public static Diagnostic of(LexWarning warning) {
+        String help = null;
+        if (warning.suggestion != null)
+            help = warning.suggestion.isEmpty() ? "remove this" : "replace with " + warning.suggestion;
+        return new Diagnostic(Severity.WARNING, warning.kind.code, warning.message, help, warning.line,
+                warning.column, warning.offset, warning.length);
+    }
+
+    /**
+     * Draws the report with box-drawing characters, the way miette's graphical handler does: the code, the message,
+     * then the lines around the span in a frame with line numbers and the span underlined. Spans that go on past
+     * their first line are underlined to the end of it, and the help comes last.
+     *
+     * @param source   the text that was lexed, with columns counted in chars
+     * @param fileName shown in the header
+     */
+    public String render(String source, String fileName) {
+        String[] lines = source.split("\n", -1);
+        int first = Math.max(line - CONTEXT_LINES, 0);
+        int last = Math.min(line + CONTEXT_LINES, lines.length - 1);
+        int gutter = String.valueOf(last + 1).length();
+        String blank = " ".repeat(gutter + 2);
+
+        StringBuilder report = new StringBuilder();
+        report.append(code).append("\n\n");
+        report.append("  ").append(severity == Severity.ERROR ? '\u00d7' : '\u26a0').append(' ')
+                .append(message).append('\n');
+        report.append(blank).append("\u256d\u2500[").append(fileName).append(':').append(line + 1).append(':')
+                .append(column + 1).append("]\n");
+        for (int i = first; i <= last && i < lines.length; i++) {
+            String text = stripCarriageReturn(lines[i]);
+            report.append(' ').append(String.format("%" + gutter + "d", i + 1)).append(" \u2502 ")
+                    .append(expandTabs(text, text.length()).stripTrailing()).append('\n');
+            if (i != line)
+                continue;
+            int start = Math.min(column, text.length());
+            int end = Math.min(column + Math.max(length, 1), Math.max(text.length(), start + 1));
+            int underlineStart = width(text, start);
+            int underlineWidth = Math.max(width(text, end) - underlineStart, 1);
+            int label = (underlineWidth - 1) / 2;
+            String indent = blank + "\u00b7 " + " ".repeat(underlineStart);
+            report.append(indent).append("\u2500".repeat(label)).append('\u252c')
+                    .append("\u2500".repeat(underlineWidth - label - 1)).append('\n');
+            report.append(indent).append(" ".repeat(label)).append("\u2570\u2500\u2500 here\n");
+        }
+        report.append(blank).append("\u2570\u2500\u2500\u2500\u2500\n");
+        if (help != null)
+            report.append("  help: ").append(help).append('\n');
+        return report.toString();
+    }
+
+    private static String stripCarriageReturn(String line) {
+        return line.endsWith("\r") ? line.substring(0, line.length() - 1) : line;
+    }
+
+    /**
+     * Display width of the line's first chars, with tabs expanded.
+     */
+    private static int width(String text, int end) {
+        String expanded = expandTabs(text, Math.min(end, text.length()));
+        return DisplayColumns.width(expanded, 0, expanded.length(), LexerOptions.ColumnMode.WIDTH)
+                + Math.max(end - text.length(), 0);
+    }
+
+    private static String expandTabs(String text, int end) {
+        StringBuilder expanded = new StringBuilder(end);
+        for (int i = 0; i < end; i++) {
+            char c = text.charAt(i);
+            if (c == '\t')
+                expanded.append(" ".repeat(TAB_WIDTH - expanded.length() % TAB_WIDTH));
+            else
+                expanded.append(c);
+        }
+        return expanded.toString();
+    }
+
+    @Override
+    public String toString() {
+        return (line + 1) + ":" + (column + 1) + ": " + severity.name().toLowerCase(Locale.ROOT)
+                + "[" + code + "]: " + message;
+    }
+}
@@ -0,0 +1,166 @@
+package ua.yuriih.rustlexer;
+
+/**
+ * Columns the way terminals and editors show them, for {@link LexerOptions.ColumnMode}. Grapheme clusters follow
+ * Unicode's extended grapheme cluster rules closely enough for source code: combining marks, joiners, Hangul
+ * syllables, emoji sequences and flags each take one column. Prepended concatenation marks, found in a few
+ * scripts, start a new cluster here.
+ */
+public final class DisplayColumns {
+    private static final int ZWJ = 0x200D;
+
+    //East_Asian_Width Wide and Fullwidth, start and end of each range
+    private static final int[] WIDE = {
+            0x1100, 0x115F, 0x231A, 0x231B, 0x2329, 0x232A, 0x23E9, 0x23EC, 0x23F0, 0x23F0, 0x23F3, 0x23F3,
+            0x25FD, 0x25FE, 0x2614, 0x2615, 0x2648, 0x2653, 0x267F, 0x267F, 0x2693, 0x2693, 0x26A1, 0x26A1,
+            0x26AA, 0x26AB, 0x26BD, 0x26BE, 0x26C4, 0x26C5, 0x26CE, 0x26CE, 0x26D4, 0x26D4, 0x26EA, 0x26EA,
+            0x26F2, 0x26F3, 0x26F5, 0x26F5, 0x26FA, 0x26FA, 0x26FD, 0x26FD, 0x2705, 0x2705, 0x270A, 0x270B,
+            0x2728, 0x2728, 0x274C, 0x274C, 0x274E, 0x274E, 0x2753, 0x2755, 0x2757, 0x2757, 0x2795, 0x2797,
+            0x27B0, 0x27B0, 0x27BF, 0x27BF, 0x2B1B, 0x2B1C, 0x2B50, 0x2B50, 0x2B55, 0x2B55, 0x2E80, 0x303E,
+            0x3041, 0x33FF, 0x3400, 0x4DBF, 0x4E00, 0x9FFF, 0xA000, 0xA4CF, 0xA960, 0xA97F, 0xAC00, 0xD7A3,
+            0xF900, 0xFAFF, 0xFE10, 0xFE19, 0xFE30, 0xFE6F, 0xFF00, 0xFF60, 0xFFE0, 0xFFE6, 0x16FE0, 0x16FE4,
+            0x17000, 0x18AFF, 0x1B000, 0x1B2FF, 0x1F004, 0x1F004, 0x1F0CF, 0x1F0CF, 0x1F18E, 0x1F18E,
+            0x1F191, 0x1F19A, 0x1F200, 0x1F202, 0x1F210, 0x1F23B, 0x1F240, 0x1F248, 0x1F250, 0x1F251,
+            0x1F260, 0x1F265, 0x1F300, 0x1F320, 0x1F32D, 0x1F335, 0x1F337, 0x1F37C, 0x1F37E, 0x1F393,
+            0x1F3A0, 0x1F3CA, 0x1F3CF, 0x1F3D3, 0x1F3E0, 0x1F3F0, 0x1F3F4, 0x1F3F4, 0x1F3F8, 0x1F43E,
+            0x1F440, 0x1F440, 0x1F442, 0x1F4FC, 0x1F4FF, 0x1F53D, 0x1F54B, 0x1F54E, 0x1F550, 0x1F567,
+            0x1F57A, 0x1F57A, 0x1F595, 0x1F596, 0x1F5A4, 0x1F5A4, 0x1F5FB, 0x1F64F, 0x1F680, 0x1F6C5,
+            0x1F6CC, 0x1F6CC, 0x1F6D0, 0x1F6D2, 0x1F6D5, 0x1F6D7, 0x1F6EB, 0x1F6EC, 0x1F6F4, 0x1F6FC,
+            0x1F7E0, 0x1F7EB, 0x1F90C, 0x1F93A, 0x1F93C, 0x1F945, 0x1F947, 0x1F9FF, 0x1FA70, 0x1FAFF,
+            0x20000, 0x2FFFD, 0x30000, 0x3FFFD,
+    };
+
+    private DisplayColumns() {
+    }
+
+    /**
+     * @param text        a line, or its start
+     * @param charColumn  column in chars, as the lexer counts them by default
+     * @return the column counted the given way, chars past the end of the text count as one column each
+     */
+    public static int column(CharSequence text, int charColumn, LexerOptions.ColumnMode mode) {
+        return width(text, 0, charColumn, mode);
+    }
+
+    /**
+     * Columns taken by the chars from start to end, the ones past the end of the text count as one each.
+     */
+    static int width(CharSequence text, int start, int end, LexerOptions.ColumnMode mode) {
+        if (mode == LexerOptions.ColumnMode.CHARS)
+            return end - start;
+        int textEnd = Math.min(end, text.length());
+        int width = 0;
+        for (int i = start; i < textEnd; i++) {
+            if (isGraphemeStart(text, i))
+                width += mode == LexerOptions.ColumnMode.WIDTH && isWide(Character.codePointAt(text, i)) ? 2 : 1;
+        }
+        return width + end - Math.max(start, textEnd);
+    }
+
+    /**
+     * Whether a grapheme cluster starts at the index, looking back at the chars before it where needed.
+     */
+    public static boolean isGraphemeStart(CharSequence text, int index) {
+        if (index == 0)
+            return true;
+        char c = text.charAt(index);
+        char previous = text.charAt(index - 1);
+        if (Character.isLowSurrogate(c) && Character.isHighSurrogate(previous))
+            return false;
+        if (previous == '\r' && c == '\n')
+            return false;
+        if (isControl(previous) || isControl(c))
+            return true;
+        int codePoint = Character.codePointAt(text, index);
+        int previousCodePoint = Character.codePointBefore(text, index);
+        if (isExtend(codePoint) || codePoint == ZWJ
+                || Character.getType(codePoint) == Character.COMBINING_SPACING_MARK)
+            return false;
+        if (isHangulSyllable(previousCodePoint, codePoint))
+            return false;
+        if (previousCodePoint == ZWJ && isPictographic(codePoint)) {
+            //emoji ZWJ sequence if there's a pictograph before the joiner, maybe with marks in between
+            int i = index - 1;
+            while (i > 0) {
+                int before = Character.codePointBefore(text, i);
+                if (isPictographic(before))
+                    return false;
+                if (!isExtend(before))
+                    break;
+                i -= Character.charCount(before);
+            }
+        }
+        if (isRegionalIndicator(codePoint) && isRegionalIndicator(previousCodePoint)) {
+            //flags are pairs of regional indicators
+            int count = 0;
+            for (int i = index; i > 0 && isRegionalIndicator(Character.codePointBefore(text, i)); i -= 2)
+                count++;
+            return count % 2 == 0;
+        }
+        return true;
+    }
+
+    /**
+     * Whether the code point is East Asian Wide or Fullwidth, so it takes two columns in a terminal.
+     */
+    public static boolean isWide(int codePoint) {
+        if (codePoint < WIDE[0])
+            return false;
+        for (int i = 0; i < WIDE.length; i += 2) {
+            if (codePoint < WIDE[i])
+                return false;
+            if (codePoint <= WIDE[i + 1])
+                return true;
+        }
+        return false;
+    }
+
+    private static boolean isControl(char c) {
+        int type = Character.getType(c);
+        return type == Character.CONTROL || (type == Character.FORMAT && c != ZWJ && c != 0x200C);
+    }
+
+    private static boolean isExtend(int codePoint) {
+        int type = Character.getType(codePoint);
+        return type == Character.NON_SPACING_MARK || type == Character.ENCLOSING_MARK
+                || (codePoint >= 0x1F3FB && codePoint <= 0x1F3FF) || (codePoint >= 0xE0020 && codePoint <= 0xE007F)
+                || codePoint == 0x200C;
+    }
+
+    private static boolean isHangulSyllable(int previous, int codePoint) {
+        char kind = hangulKind(codePoint);
+        return switch (hangulKind(previous)) {
+            case 'L' -> kind != 0 && kind != 'T';
+            case 'V', 'S' -> kind == 'V' || kind == 'T';
+            case 'T', 'X' -> kind == 'T';
+            default -> false;
+        };
+    }
+
+    /**
+     * @return L, V or T for leading, vowel and trailing jamo, S for syllables without a trailing consonant,
+     * X for ones with it, 0 otherwise
+     */
+    private static char hangulKind(int codePoint) {
+        if ((codePoint >= 0x1100 && codePoint <= 0x115F) || (codePoint >= 0xA960 && codePoint <= 0xA97C))
+            return 'L';
+        if ((codePoint >= 0x1160 && codePoint <= 0x11A7) || (codePoint >= 0xD7B0 && codePoint <= 0xD7C6))
+            return 'V';
+        if ((codePoint >= 0x11A8 && codePoint <= 0x11FF) || (codePoint >= 0xD7CB && codePoint <= 0xD7FB))
+            return 'T';
+        if (codePoint >= 0xAC00 && codePoint <= 0xD7A3)
+            return (codePoint - 0xAC00) % 28 == 0 ? 'S' : 'X';
+        return 0;
+    }
+
+    private static boolean isPictographic(int codePoint) {
+        return (codePoint >= 0x1F000 && codePoint <= 0x1FAFF) || (codePoint >= 0x2600 && codePoint <= 0x27BF)
+                || (codePoint >= 0x2300 && codePoint <= 0x23FF) || (codePoint >= 0x2B00 && codePoint <= 0x2BFF)
+                || codePoint == 0x00A9 || codePoint == 0x00AE || codePoint == 0x203C || codePoint == 0x2049
+                || codePoint == 0x2122 || codePoint == 0x2139;
+    }
+
+    private static boolean isRegionalIndicator(int codePoint) {
+        return codePoint >= 0x1F1E6 && codePoint <= 0x1F1FF;
+    }
+}
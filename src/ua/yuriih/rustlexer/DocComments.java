@@ -0,0 +1,124 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayDeque;
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Guesses what doc comments document without parsing: outer doc comments go with the item that follows them,
+ * inner ones with the block they're in.
+ */
+public final class DocComments {
+    private DocComments() {
+    }
+
+    /**
+     * Groups doc comments into runs and finds the target of each run. A run is consecutive doc comments of the same
+     * kind, plain comments between them don't break it.
+     * <ul>
+     * <li>An outer run (/// or /**) documents the next token, skipping attributes like #[derive(Debug)],
+     * so that's usually the first keyword of the item.</li>
+     * <li>An inner run (//! or /*!) documents the { of the block it's in, or the file itself, then the target
+     * is null.</li>
+     * </ul>
+     *
+     * @return runs in source order
+     */
+    public static List<Association> associate(List<Token> tokens) {
+        ArrayList<Association> associations = new ArrayList<>();
+        ArrayDeque<Token> blocks = new ArrayDeque<>();
+        int i = 0;
+        while (i < tokens.size()) {
+            Token token = tokens.get(i);
+            if (token.type == TokenType.CURLY_L) {
+                blocks.push(token);
+            } else if (token.type == TokenType.CURLY_R) {
+                blocks.poll();
+            } else if (token.type == TokenType.COMMENT_OUTER_DOC || token.type == TokenType.COMMENT_INNER_DOC) {
+                ArrayList<Token> run = new ArrayList<>();
+                int end = i;
+                for (int j = i; j < tokens.size(); j++) {
+                    TokenType type = tokens.get(j).type;
+                    if (type == token.type) {
+                        run.add(tokens.get(j));
+                        end = j + 1;
+                    } else if (type != TokenType.COMMENT && type != TokenType.WHITESPACE) {
+                        break;
+                    }
+                }
+                Token target = token.type == TokenType.COMMENT_INNER_DOC ? blocks.peek() : itemAfter(tokens, end);
+                associations.add(new Association(run, target));
+                i = end;
+                continue;
+            }
+            i++;
+        }
+        return Collections.unmodifiableList(associations);
+    }
+
+    /**
+     * @return the first token from the index that isn't trivia or part of an outer attribute, or null
+     */
+    private static Token itemAfter(List<Token> tokens, int from) {
+        int depth = 0;
+        for (int i = from; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            switch (token.type) {
+                case WHITESPACE, COMMENT, COMMENT_OUTER_DOC -> {
+                    continue;
+                }
+                case POUND -> {
+                    if (depth == 0 && i + 1 < tokens.size() && tokens.get(i + 1).type == TokenType.SQUARE_L)
+                        continue;
+                }
+                case SQUARE_L -> {
+                    if (depth > 0 || tokens.get(i - 1).type == TokenType.POUND) {
+                        depth++;
+                        continue;
+                    }
+                }
+                case SQUARE_R -> {
+                    if (depth > 0) {
+                        depth--;
+                        continue;
+                    }
+                }
+            }
+            if (depth == 0)
+                return token;
+        }
+        return null;
+    }
+
+    public static final class Association {
+        /** Doc comments of the run, at least one */
+        public final List<Token> comments;
+        /** What they document, null for inner doc comments of the file or outer ones at the end of it */
+        public final Token target;
+
+        private Association(List<Token> comments, Token target) {
+            this.comments = Collections.unmodifiableList(comments);
+            this.target = target;
+        }
+
+        public boolean isInner() {
+            return comments.get(0).type == TokenType.COMMENT_INNER_DOC;
+        }
+
+        /** Start offset of the first comment */
+        public int start() {
+            return comments.get(0).offset;
+        }
+
+        /** End offset of the last comment */
+        public int end() {
+            return comments.get(comments.size() - 1).end();
+        }
+
+        @Override
+        public String toString() {
+            return start() + ".." + end() + " -> " + target;
+        }
+    }
+}
@@ -0,0 +1,227 @@
+package ua.yuriih.rustlexer;
+
+import java.io.ByteArrayInputStream;
+import java.io.IOException;
+import java.nio.charset.StandardCharsets;
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+import java.util.Set;
+
+/**
+ * Finds fenced code blocks in doc comments and lexes the Rust ones,
+ * mapping the resulting tokens back to their positions in the original file.
+ */
+public final class DocTests {
+    // rustdoc still treats blocks with these tags as Rust code
+    private static final Set<String> RUST_TAGS = Set.of(
+            "rust", "ignore", "should_panic", "no_run", "compile_fail", "test_harness", "standalone_crate",
+            "edition2015", "edition2018", "edition2021", "edition2024"
+    );
+
+    private DocTests() {
+    }
+
+    public static List<CodeBlock> lexCodeBlocks(Token docComment) throws IOException {
+        return lexCodeBlocks(List.of(docComment));
+    }
+
+    public static List<CodeBlock> lexCodeBlocks(List<Token> docComments) throws IOException {
+        return lexCodeBlocks(docComments, false);
+    }
+
+    /**
+     * @param docComments a run of consecutive line doc comments, or a single block doc comment
+     * @param stripHidden lex the code the way rustdoc compiles it: "# " is cut from the start of hidden lines
+     *                    and "##" turns into "#", tokens still point at the doc comments
+     */
+    public static List<CodeBlock> lexCodeBlocks(List<Token> docComments, boolean stripHidden) throws IOException {
+        ArrayList<ContentLine> lines = new ArrayList<>();
+        for (Token token : docComments) {
+            if (token.type != TokenType.COMMENT_OUTER_DOC && token.type != TokenType.COMMENT_INNER_DOC)
+                throw new IllegalArgumentException("Not a doc comment: " + token.type);
+            addContentLines(token, lines);
+        }
+        unindent(lines);
+
+        ArrayList<CodeBlock> blocks = new ArrayList<>();
+        int i = 0;
+        while (i < lines.size()) {
+            String trimmed = lines.get(i).text.strip();
+            int fenceLength = fenceLength(trimmed);
+            if (fenceLength == 0) {
+                i++;
+                continue;
+            }
+
+            char fenceChar = trimmed.charAt(0);
+            String info = trimmed.substring(fenceLength).strip();
+            int start = ++i;
+            while (i < lines.size() && !isClosingFence(lines.get(i).text.strip(), fenceChar, fenceLength))
+                i++;
+            List<ContentLine> code = lines.subList(start, i);
+            i++; //skip closing fence
+
+            if (!isRust(info))
+                continue;
+            ArrayList<Integer> hiddenLines = new ArrayList<>();
+            if (stripHidden) {
+                code = new ArrayList<>(code);
+                for (int j = 0; j < code.size(); j++) {
+                    ContentLine line = code.get(j);
+                    ContentLine stripped = stripHidden(line);
+                    if (stripped != line) {
+                        code.set(j, stripped);
+                        if (!line.text.strip().startsWith("##"))
+                            hiddenLines.add(line.line);
+                    }
+                }
+            }
+            blocks.add(new CodeBlock(info, lexMapped(code), hiddenLines));
+        }
+        return blocks;
+    }
+
+    private static void addContentLines(Token token, List<ContentLine> lines) {
+        String value = token.value;
+        if (value.startsWith("//")) {
+            lines.add(new ContentLine(value.substring(3), token.line, token.column + 3, token.offset + 3));
+            return;
+        }
+
+        // block comment: cut "/**" or "/*!" and "*/"
+        String[] parts = value.substring(3, value.length() - 2).split("\n", -1);
+        int offset = token.offset + 3;
+        for (int i = 0; i < parts.length; i++) {
+            String text = parts[i];
+            int lineOffset = offset;
+            offset += text.length() + 1;
+            int column = i == 0 ? token.column + 3 : 0;
+            if (i > 0) {
+                int j = 0;
+                while (j < text.length() && (text.charAt(j) == ' ' || text.charAt(j) == '\t'))
+                    j++;
+                if (j < text.length() && text.charAt(j) == '*') {
+                    j++;
+                    if (j < text.length() && text.charAt(j) == ' ')
+                        j++;
+                    text = text.substring(j);
+                    column = j;
+                    lineOffset += j;
+                }
+            }
+            lines.add(new ContentLine(text, token.line + i, column, lineOffset));
+        }
+    }
+
+    private static void unindent(List<ContentLine> lines) {
+        int indent = Integer.MAX_VALUE;
+        for (ContentLine line : lines) {
+            if (line.text.isBlank())
+                continue;
+            int j = 0;
+            while (j < line.text.length() && line.text.charAt(j) == ' ')
+                j++;
+            indent = Math.min(indent, j);
+        }
+        if (indent == Integer.MAX_VALUE || indent == 0)
+            return;
+
+        for (int i = 0; i < lines.size(); i++) {
+            ContentLine line = lines.get(i);
+            int cut = Math.min(indent, line.text.length());
+            lines.set(i, new ContentLine(line.text.substring(cut), line.line, line.column + cut, line.offset + cut));
+        }
+    }
+
+    // same rules as rustdoc, the line is returned as is if it's not hidden
+    private static ContentLine stripHidden(ContentLine line) {
+        String trimmed = line.text.strip();
+        int cut;
+        if (trimmed.startsWith("##"))
+            cut = line.text.indexOf('#') + 1;
+        else if (trimmed.startsWith("# ") || trimmed.startsWith("#\t"))
+            cut = line.text.indexOf('#') + 2;
+        else if (trimmed.equals("#"))
+            cut = line.text.length();
+        else
+            return line;
+        return new ContentLine(line.text.substring(cut), line.line, line.column + cut, line.offset + cut);
+    }
+
+    private static int fenceLength(String line) {
+        if (!line.startsWith("```") && !line.startsWith("~~~"))
+            return 0;
+        int length = 0;
+        while (length < line.length() && line.charAt(length) == line.charAt(0))
+            length++;
+        return length;
+    }
+
+    private static boolean isClosingFence(String line, char fenceChar, int openingLength) {
+        if (line.isEmpty() || line.charAt(0) != fenceChar)
+            return false;
+        int length = fenceLength(line);
+        return length >= openingLength && length == line.length();
+    }
+
+    static boolean isRust(String info) {
+        if (info.isEmpty())
+            return true;
+        for (String tag : info.split("[\\s,]+")) {
+            if (!tag.isEmpty() && !RUST_TAGS.contains(tag))
+                return false;
+        }
+        return true;
+    }
+
+    private static List<Token> lexMapped(List<ContentLine> code) throws IOException {
+        StringBuilder source = new StringBuilder();
+        int[] lineStarts = new int[code.size()];
+        for (int i = 0; i < code.size(); i++) {
+            lineStarts[i] = source.length();
+            source.append(code.get(i).text).append('\n');
+        }
+
+        Lexer lexer = new Lexer(new ByteArrayInputStream(source.toString().getBytes(StandardCharsets.UTF_8)));
+        ArrayList<Token> tokens = new ArrayList<>();
+        for (Token token : lexer.parse()) {
+            ContentLine line = code.get(token.line);
+            int column = token.offset - lineStarts[token.line];
+            tokens.add(token.withPosition(line.line, line.column + column, line.offset + column));
+        }
+        return tokens;
+    }
+
+    private static final class ContentLine {
+        final String text;
+        final int line;
+        final int column;
+        final int offset;
+
+        ContentLine(String text, int line, int column, int offset) {
+            this.text = text;
+            this.line = line;
+            this.column = column;
+            this.offset = offset;
+        }
+    }
+
+    public static final class CodeBlock {
+        /**
+         * Info string after the opening fence, e.g. "should_panic"
+         */
+        public final String info;
+        public final List<Token> tokens;
+        /**
+         * Lines of the file that are hidden in the docs, only filled in when lexing with hidden lines stripped
+         */
+        public final List<Integer> hiddenLines;
+
+        CodeBlock(String info, List<Token> tokens, List<Integer> hiddenLines) {
+            this.info = info;
+            this.tokens = tokens;
+            this.hiddenLines = Collections.unmodifiableList(hiddenLines);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * Graphviz DOT output of a {@link TokenTree}: every group is a node labeled with its delimiters and span,
+ * and runs of tokens between groups are summarized in one node. Trivia is left out.
+ */
+public final class Dot {
+    private static final int MAX_SUMMARY_TOKENS = 8;
+    private static final int MAX_TEXT_LENGTH = 20;
+
+    private Dot() {
+    }
+
+    public static String toDot(List<Token> tokens) {
+        return toDot(TokenTree.build(tokens));
+    }
+
+    public static String toDot(TokenTree tree) {
+        StringBuilder dot = new StringBuilder();
+        dot.append("digraph tokens {\n");
+        dot.append("    node [shape=box, fontname=\"monospace\"];\n");
+        int[] nextId = {0};
+        writeGroup(dot, tree.root, nextId);
+        for (Token token : tree.unmatched) {
+            int id = nextId[0]++;
+            dot.append("    n").append(id).append(" [label=\"")
+                    .append(escape("unmatched " + token.type.text + "\n" + position(token)))
+                    .append("\", color=red];\n");
+        }
+        dot.append("}\n");
+        return dot.toString();
+    }
+
+    // returns the node's id
+    private static int writeGroup(StringBuilder dot, TokenTree.Group group, int[] nextId) {
+        int id = nextId[0]++;
+        String label;
+        if (group.open == null) {
+            label = "file";
+        } else {
+            label = group.delimiters() + "\n" + position(group.open) + " - "
+                    + (group.close() != null ? position(group.close()) : "unclosed");
+        }
+        dot.append("    n").append(id).append(" [label=\"").append(escape(label)).append('"')
+                .append(group.open != null && group.close() == null ? ", color=red" : "")
+                .append(group.open != null ? ", shape=ellipse" : "").append("];\n");
+
+        ArrayList<Token> run = new ArrayList<>();
+        for (TokenTree.Node child : group.children()) {
+            if (child instanceof TokenTree.Group) {
+                writeRun(dot, id, run, nextId);
+                int childId = writeGroup(dot, (TokenTree.Group) child, nextId);
+                dot.append("    n").append(id).append(" -> n").append(childId).append(";\n");
+            } else {
+                Token token = ((TokenTree.Leaf) child).token;
+                if (!token.type.isTrivia())
+                    run.add(token);
+            }
+        }
+        writeRun(dot, id, run, nextId);
+        return id;
+    }
+
+    private static void writeRun(StringBuilder dot, int parentId, ArrayList<Token> run, int[] nextId) {
+        if (run.isEmpty())
+            return;
+        StringBuilder summary = new StringBuilder();
+        for (int i = 0; i < run.size() && i < MAX_SUMMARY_TOKENS; i++) {
+            if (i > 0)
+                summary.append(' ');
+            String text = run.get(i).text();
+            if (text == null)
+                text = run.get(i).type.name();
+            summary.append(text.length() > MAX_TEXT_LENGTH ? text.substring(0, MAX_TEXT_LENGTH) + "..." : text);
+        }
+        if (run.size() > MAX_SUMMARY_TOKENS)
+            summary.append(" ... (").append(run.size()).append(" tokens)");
+
+        int id = nextId[0]++;
+        dot.append("    n").append(id).append(" [label=\"").append(escape(summary.toString()))
+                .append("\", shape=plaintext];\n");
+        dot.append("    n").append(parentId).append(" -> n").append(id).append(";\n");
+        run.clear();
+    }
+
+    private static String position(Token token) {
+        return (token.line + 1) + ":" + (token.column + 1);
+    }
+
+    private static String escape(String label) {
+        StringBuilder escaped = new StringBuilder();
+        for (int i = 0; i < label.length(); i++) {
+            char c = label.charAt(i);
+            switch (c) {
+                case '"' -> escaped.append("\\\"");
+                case '\\' -> escaped.append("\\\\");
+                case '\n' -> escaped.append("\\n");
+                case '\r', '\t' -> escaped.append(' ');
+                default -> escaped.append(c);
+            }
+        }
+        return escaped.toString();
+    }
+}
@@ -0,0 +1,108 @@
+package ua.yuriih.rustlexer;
+
+/**
+ * The inverse of {@link Literal#escapes()}: renders text as Rust literal source code.
+ */
+public final class Escape {
+    private Escape() {
+    }
+
+    /**
+     * Quoted string literal. Printable characters are kept as they are, the rest is escaped.
+     *
+     * @throws IllegalArgumentException if the string has unpaired surrogates, which Rust strings can't contain
+     */
+    public static String toStringLiteral(String s) {
+        StringBuilder sb = new StringBuilder(s.length() + 2);
+        sb.append('"');
+        for (int i = 0; i < s.length(); ) {
+            int c = s.codePointAt(i);
+            if (Character.getType(c) == Character.SURROGATE)
+                throw new IllegalArgumentException("Unpaired surrogate at index " + i);
+            switch (c) {
+                case '"' -> sb.append("\\\"");
+                case '\\' -> sb.append("\\\\");
+                case '\n' -> sb.append("\\n");
+                case '\r' -> sb.append("\\r");
+                case '\t' -> sb.append("\\t");
+                case 0 -> sb.append("\\0");
+                default -> {
+                    if (isPrintable(c))
+                        sb.appendCodePoint(c);
+                    else
+                        sb.append("\\u{").append(Integer.toHexString(c)).append('}');
+                }
+            }
+            i += Character.charCount(c);
+        }
+        return sb.append('"').toString();
+    }
+
+    /**
+     * Byte string literal, b"...". Bytes outside of printable ASCII are written as \xNN.
+     */
+    public static String toByteStringLiteral(byte[] bytes) {
+        StringBuilder sb = new StringBuilder(bytes.length + 3);
+        sb.append("b\"");
+        for (byte b : bytes) {
+            int c = b & 0xFF;
+            switch (c) {
+                case '"' -> sb.append("\\\"");
+                case '\\' -> sb.append("\\\\");
+                case '\n' -> sb.append("\\n");
+                case '\r' -> sb.append("\\r");
+                case '\t' -> sb.append("\\t");
+                case 0 -> sb.append("\\0");
+                default -> {
+                    if (c >= 0x20 && c < 0x7F)
+                        sb.append((char) c);
+                    else
+                        sb.append(String.format("\\x%02X", c));
+                }
+            }
+        }
+        return sb.append('"').toString();
+    }
+
+    /**
+     * Raw string literal with the fewest # needed, e.g. r"a" or r##"## "#"##.
+     *
+     * @throws IllegalArgumentException if the string has a carriage return that isn't part of CRLF
+     * or an unpaired surrogate, neither can appear in a raw string
+     */
+    public static String toRawStringLiteral(String s) {
+        int hashCount = 0;
+        for (int i = 0; i < s.length(); i++) {
+            char c = s.charAt(i);
+            if (c == '\r' && (i + 1 == s.length() || s.charAt(i + 1) != '\n'))
+                throw new IllegalArgumentException("Bare carriage return at index " + i);
+            if (Character.isSurrogate(c)) {
+                if (Character.isHighSurrogate(c) && i + 1 < s.length() && Character.isLowSurrogate(s.charAt(i + 1)))
+                    i++;
+                else
+                    throw new IllegalArgumentException("Unpaired surrogate at index " + i);
+                continue;
+            }
+            if (c == '"') {
+                //the literal would end at a quote followed by as many # as we use
+                int hashes = 0;
+                while (i + 1 + hashes < s.length() && s.charAt(i + 1 + hashes) == '#')
+                    hashes++;
+                hashCount = Math.max(hashCount, hashes + 1);
+            }
+        }
+        String hashes = "#".repeat(hashCount);
+        return "r" + hashes + '"' + s + '"' + hashes;
+    }
+
+    private static boolean isPrintable(int c) {
+        if (c == ' ')
+            return true;
+        return switch (Character.getType(c)) {
+            case Character.CONTROL, Character.FORMAT, Character.UNASSIGNED, Character.PRIVATE_USE,
+                    Character.SURROGATE, Character.LINE_SEPARATOR, Character.PARAGRAPH_SEPARATOR,
+                    Character.SPACE_SEPARATOR -> false;
+            default -> true;
+        };
+    }
+}
@@ -0,0 +1,147 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.PrintStream;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * rlex explain: prints everything the lexer knows about the token at a position, for debugging the lexer and code
+ * that uses it.
+ */
+final class ExplainCommand {
+    private final PrintStream out;
+
+    ExplainCommand(PrintStream out) {
+        this.out = out;
+    }
+
+    /**
+     * @param location path:line:column, 1-based, the column in chars
+     * @return exit status: 0 if there's a token there, 1 if there isn't, 2 if the location is malformed
+     */
+    int run(String location) throws IOException {
+        int columnColon = location.lastIndexOf(':');
+        int lineColon = columnColon > 0 ? location.lastIndexOf(':', columnColon - 1) : -1;
+        int line = -1;
+        int column = -1;
+        if (lineColon > 0) {
+            try {
+                line = Integer.parseInt(location.substring(lineColon + 1, columnColon)) - 1;
+                column = Integer.parseInt(location.substring(columnColon + 1)) - 1;
+            } catch (NumberFormatException e) {
+                line = -1;
+            }
+        }
+        if (line < 0 || column < 0) {
+            out.println("Expected path:line:column, got " + location);
+            return 2;
+        }
+        String path = location.substring(0, lineColon);
+
+        String source = Files.readString(Path.of(path));
+        List<Token> tokens = Lexer.fromString(source, LexerOptions.builder().emitTrivia(true).build()).parse();
+        int offset = offset(source, line, column);
+        ArrayList<Token> found = new ArrayList<>();
+        for (Token token : tokens) {
+            if (!token.isSynthetic() && token.offset <= offset && offset < token.end())
+                found.add(token);
+        }
+        if (offset < 0 || found.isEmpty()) {
+            out.println("No token at " + location);
+            return 1;
+        }
+
+        Trivia trivia = Trivia.attach(tokens);
+        for (Token token : found) {
+            if (token instanceof LexError) {
+                LexError error = (LexError) token;
+                out.printf("%s:%d:%d: error[%s]: %s%n", path, token.line + 1, token.column + 1, error.kind.code,
+                        error.value);
+                continue;
+            }
+            out.printf("%s:%d:%d: %s%s%n", path, token.line + 1, token.column + 1, token.type,
+                    token.isContextualKeyword() ? " (contextual keyword)" : "");
+            out.printf("  span:     %d:%d-%s (offsets %d..%d)%n", token.line + 1, token.column + 1,
+                    end(token), token.offset, token.end());
+            out.println("  text:     " + Json.quote(token.text()));
+            if (isLiteral(token.type))
+                printLiteral(new Literal(token));
+            for (Trivia.Attached attached : trivia.tokens) {
+                if (attached.token == token) {
+                    printTrivia("leading", attached.leading());
+                    printTrivia("trailing", attached.trailing());
+                }
+            }
+        }
+        return 0;
+    }
+
+    private void printLiteral(Literal literal) {
+        out.println("  literal:  " + literal.kind());
+        try {
+            if (literal.kind() == Literal.Kind.INT)
+                out.println("  value:    " + literal.parseInt());
+            else if (literal.kind() == Literal.Kind.FLOAT)
+                out.println("  value:    " + literal.parseFloat());
+            else
+                out.println("  cooked:   " + Json.quote(literal.cooked()));
+        } catch (LiteralParseException e) {
+            out.println("  value:    invalid, " + e.getMessage());
+        }
+        if (!literal.suffix().isEmpty())
+            out.println("  suffix:   " + literal.suffix());
+        if (literal.kind().isRaw())
+            out.println("  hashes:   " + literal.hashCount());
+    }
+
+    private void printTrivia(String name, List<Token> trivia) {
+        for (Token token : trivia)
+            out.printf("  %-9s %s %s%n", name + ":", token.type, Json.quote(token.text()));
+    }
+
+    private static boolean isLiteral(TokenType type) {
+        return switch (type) {
+            case CHAR_LITERAL, BYTE_LITERAL, STRING_LITERAL, BYTE_STRING_LITERAL, RAW_STRING_LITERAL,
+                    RAW_BYTE_STRING_LITERAL, INT_LITERAL_DEC, INT_LITERAL_HEX, INT_LITERAL_OCTAL, INT_LITERAL_BIN,
+                    FLOAT_LITERAL -> true;
+            default -> false;
+        };
+    }
+
+    /**
+     * @return offset of the 0-based line and column, or -1 if the line is shorter or there's no such line
+     */
+    private static int offset(String source, int line, int column) {
+        int lineStart = 0;
+        for (int i = 0; i < line; i++) {
+            lineStart = source.indexOf('\n', lineStart) + 1;
+            if (lineStart == 0)
+                return -1;
+        }
+        int lineEnd = source.indexOf('\n', lineStart);
+        if (lineEnd < 0)
+            lineEnd = source.length();
+        return column <= lineEnd - lineStart ? lineStart + column : -1;
+    }
+
+    /**
+     * @return 1-based line:column just after the token
+     */
+    private static String end(Token token) {
+        String text = token.text();
+        int line = token.line;
+        int column = token.column;
+        for (int i = 0; i < text.length(); i++) {
+            if (text.charAt(i) == '\n') {
+                line++;
+                column = 0;
+            } else {
+                column++;
+            }
+        }
+        return (line + 1) + ":" + (column + 1);
+    }
+}
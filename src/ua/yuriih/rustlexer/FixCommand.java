@@ -0,0 +1,119 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.PrintStream;
+import java.nio.charset.CharacterCodingException;
+import java.nio.file.Files;
+import java.nio.file.NoSuchFileException;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * rlex fix: applies {@link Fixer} to files in place, or prints what it would change as a unified diff.
+ */
+final class FixCommand {
+    private static final int CONTEXT_LINES = 3;
+
+    private final PrintStream out;
+    private final boolean dryRun;
+
+    FixCommand(PrintStream out, boolean dryRun) {
+        this.out = out;
+        this.dryRun = dryRun;
+    }
+
+    /**
+     * @param paths files, and directories to fix every .rs file under
+     * @return exit status: 0 if every file was fixed or didn't need it, 1 if some couldn't be read or written
+     * or had lex errors
+     */
+    int run(List<Path> paths) {
+        ArrayList<Path> files = new ArrayList<>();
+        boolean failed = false;
+        for (Path path : paths) {
+            try {
+                if (Files.isDirectory(path))
+                    files.addAll(SourceFiles.find(path));
+                else
+                    files.add(path);
+            } catch (IOException e) {
+                out.println(path + ": FAILED (" + e.getMessage() + ")");
+                failed = true;
+            }
+        }
+
+        int fixedFiles = 0;
+        for (Path file : files) {
+            try {
+                String source = Files.readString(file);
+                String fixed = Fixer.fix(source);
+                if (fixed.equals(source))
+                    continue;
+                fixedFiles++;
+                if (dryRun) {
+                    printDiff(file, source, fixed);
+                } else {
+                    Files.writeString(file, fixed);
+                    out.println(file + ": fixed");
+                }
+            } catch (NoSuchFileException e) {
+                out.println(file + ": FAILED (no such file)");
+                failed = true;
+            } catch (CharacterCodingException e) {
+                out.println(file + ": FAILED (not valid UTF-8)");
+                failed = true;
+            } catch (IllegalArgumentException e) {
+                out.println(file + ": FAILED (has lex errors)");
+                failed = true;
+            } catch (IOException e) {
+                out.println(file + ": FAILED (" + e.getMessage() + ")");
+                failed = true;
+            }
+        }
+        out.printf("%d files, %d %s%n", files.size(), fixedFiles, dryRun ? "would be fixed" : "fixed");
+        return failed ? 1 : 0;
+    }
+
+    /**
+     * Fixes never add or remove line breaks, so the lines of the source and the fixed text pair up one to one.
+     */
+    private void printDiff(Path file, String source, String fixed) {
+        String[] before = source.split("\n", -1);
+        String[] after = fixed.split("\n", -1);
+        if (before.length != after.length) {
+            before = new String[] {source};
+            after = new String[] {fixed};
+        }
+        //the empty string after the last line break isn't a line
+        int lines = before[before.length - 1].isEmpty() && after[after.length - 1].isEmpty()
+                ? before.length - 1 : before.length;
+        out.println("--- " + file);
+        out.println("+++ " + file);
+        int i = 0;
+        while (i < lines) {
+            if (before[i].equals(after[i])) {
+                i++;
+                continue;
+            }
+            //a hunk goes on while changed lines are close enough for their contexts to touch
+            int start = Math.max(i - CONTEXT_LINES, 0);
+            int end = i + 1;
+            for (int j = end; j < lines && j < end + 2 * CONTEXT_LINES; j++) {
+                if (!before[j].equals(after[j]))
+                    end = j + 1;
+            }
+            int hunkEnd = Math.min(end + CONTEXT_LINES, lines);
+            out.printf("@@ -%d,%d +%d,%d @@%n", start + 1, hunkEnd - start, start + 1, hunkEnd - start);
+            for (int j = start; j < hunkEnd; j++) {
+                if (before[j].equals(after[j])) {
+                    out.println(" " + before[j]);
+                } else {
+                    out.println("-" + before[j]);
+                    out.println("+" + after[j]);
+                }
+            }
+            i = hunkEnd;
+        }
+    }
+}
@@ -0,0 +1,108 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Comparator;
+import java.util.List;
+
+/**
+ * Cleanups that don't change what the source means: they're lexical, so they never touch the text of string
+ * literals.
+ */
+public final class Fixer {
+    private Fixer() {
+    }
+
+    public static String fix(String source) {
+        return fix(source, LexerOptions.DEFAULT);
+    }
+
+    /**
+     * Strips the byte order mark, turns \r\n line endings into \n, applies the suggestions of warnings the options
+     * don't allow and removes spaces and tabs at the ends of lines, except in string literals.
+     * <p>
+     * The result is lexed again and checked to have the same tokens as the source. Sources with lex errors aren't
+     * fixed, since what comes after an error can lex differently once whitespace or line endings change.
+     *
+     * @throws IllegalArgumentException if the source has lex errors
+     * @throws IllegalStateException    if the result doesn't lex the same, which is a bug
+     */
+    public static String fix(String source, LexerOptions options) {
+        LexerOptions lexing = options.toBuilder().emitTrivia(false).warningSink(null).build();
+        Lexer.Tokenized before = Lexer.tokenize(source, lexing);
+        if (!before.errors.isEmpty())
+            throw new IllegalArgumentException("Source has lex errors");
+
+        String fixed = source.startsWith("\uFEFF") ? source.substring(1) : source;
+        //rustc reads \r\n as \n everywhere, even in raw strings
+        fixed = fixed.replace("\r\n", "\n");
+        fixed = applySuggestions(fixed, options);
+        fixed = removeTrailingWhitespace(fixed, options);
+
+        Lexer.Tokenized after = Lexer.tokenize(fixed, lexing);
+        if (!after.errors.isEmpty() || !sameTypes(before.tokens, after.tokens))
+            throw new IllegalStateException("Fixed source doesn't lex to the same tokens");
+        return fixed;
+    }
+
+    private static String applySuggestions(String source, LexerOptions options) {
+        ArrayList<LexWarning> warnings = new ArrayList<>();
+        Lexer.tokenize(source, options.toBuilder().warningSink(warnings::add).build());
+        warnings.removeIf(warning -> warning.suggestion == null);
+        if (warnings.isEmpty())
+            return source;
+        warnings.sort(Comparator.comparingInt(warning -> warning.offset));
+
+        StringBuilder fixed = new StringBuilder(source.length());
+        int position = 0;
+        for (LexWarning warning : warnings) {
+            //overlapping suggestions can't both be applied
+            if (warning.offset < position)
+                continue;
+            fixed.append(source, position, warning.offset).append(warning.suggestion);
+            position = warning.offset + warning.length;
+        }
+        return fixed.append(source, position, source.length()).toString();
+    }
+
+    private static String removeTrailingWhitespace(String source, LexerOptions options) {
+        List<Token> tokens = Lexer.tokenize(source, options.toBuilder().emitTrivia(false).build()).tokens;
+        StringBuilder fixed = new StringBuilder(source.length());
+        int tokenIndex = 0;
+        int lineStart = 0;
+        while (lineStart <= source.length()) {
+            int lineEnd = source.indexOf('\n', lineStart);
+            if (lineEnd < 0)
+                lineEnd = source.length();
+            int end = lineEnd;
+            while (end > lineStart && (source.charAt(end - 1) == ' ' || source.charAt(end - 1) == '\t'))
+                end--;
+            if (end < lineEnd) {
+                while (tokenIndex < tokens.size() && tokens.get(tokenIndex).end() <= end)
+                    tokenIndex++;
+                if (tokenIndex < tokens.size() && isString(tokens.get(tokenIndex).type)
+                        && tokens.get(tokenIndex).offset < end)
+                    end = lineEnd;
+            }
+            fixed.append(source, lineStart, end);
+            if (lineEnd < source.length())
+                fixed.append('\n');
+            lineStart = lineEnd + 1;
+        }
+        return fixed.toString();
+    }
+
+    private static boolean isString(TokenType type) {
+        return type == TokenType.STRING_LITERAL || type == TokenType.BYTE_STRING_LITERAL
+                || type == TokenType.RAW_STRING_LITERAL || type == TokenType.RAW_BYTE_STRING_LITERAL;
+    }
+
+    private static boolean sameTypes(List<Token> expected, List<Token> actual) {
+        if (expected.size() != actual.size())
+            return false;
+        for (int i = 0; i < expected.size(); i++) {
+            if (expected.get(i).type != actual.get(i).type)
+                return false;
+        }
+        return true;
+    }
+}
@@ -0,0 +1,295 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+import java.util.Random;
+
+/**
+ * Random Rust source that lexes without errors, along with the tokens it should lex to, for property tests and
+ * fuzzing of parsers built on the lexer. The source has the hard parts: raw strings with quotes and hashes inside,
+ * nested comments, escapes, line continuations and non-ASCII text.
+ */
+public final class Generator {
+    /** The expected tokens are the ones the lexer gives with these options */
+    public static final LexerOptions OPTIONS = LexerOptions.builder().emitTrivia(true).build();
+
+    private static final String[] WORDS = {
+            "x", "foo", "bar_baz", "_tmp", "i", "Vec", "MAX", "a1", "__", "\u03bb", "donn\u00e9es", "\u65e5\u672c",
+            "\u0416", "e\u0301t"
+    };
+    private static final String[] CHARS = {
+            "a", "Z", "0", " ", "\"", "\u03bb", "\u65e5", "\\n", "\\r", "\\t", "\\\\", "\\'", "\\\"", "\\0", "\\x41",
+            "\\x7f", "\\u{1F600}", "\\u{10_FFFF}", "\\u{e9}"
+    };
+    private static final String[] BYTES = {"a", "Z", "0", " ", "\"", "\\n", "\\'", "\\\\", "\\0", "\\x00", "\\xff"};
+    private static final String[] STRING_PARTS = {
+            "a", "text", " ", "\t", "\n", "'", "#", "\\\"", "\\\\", "\\n", "\\0", "\\x41", "\\u{1F600}",
+            "\\\n    ", "\u00e9", "\u65e5\u672c\u8a9e", "\ud83d\ude00", "/*", "//"
+    };
+    private static final String[] BYTE_STRING_PARTS = {
+            "a", "text", " ", "\t", "\n", "'", "#", "\\\"", "\\\\", "\\n", "\\0", "\\xff", "\\\n    ", "/*"
+    };
+    private static final String[] RAW_STRING_PARTS = {"a", " ", "\\", "\n", "'", "#", "\\u{0}", "/*", "*/"};
+    private static final String[] COMMENT_PARTS = {"x", " ", "\n", "\u00e9", "\ud83d\ude00", "* ", "/ ", "\"", "'"};
+    private static final String[] WHITESPACE = {" ", " ", "\t", "\n", "  ", "\n    ", "\n\n"};
+    private static final String[] INT_SUFFIXES = {"u8", "i32", "u64", "usize", "i128"};
+    private static final String[] FLOAT_SUFFIXES = {"f32", "f64"};
+
+    private static final List<TokenType> KEYWORDS = new ArrayList<>();
+    private static final List<TokenType> PUNCTUATION = new ArrayList<>();
+
+    static {
+        for (TokenType type : TokenType.values()) {
+            if (type.text == null || type == TokenType.UNION || type == TokenType.STATIC_LIFETIME)
+                continue;
+            if (type.isKeyword()) {
+                if (OPTIONS.reserves(type))
+                    KEYWORDS.add(type);
+            } else {
+                PUNCTUATION.add(type);
+            }
+        }
+    }
+
+    private final Random random;
+    private final StringBuilder source = new StringBuilder();
+    private final ArrayList<Token> tokens = new ArrayList<>();
+    private int line;
+    private int column;
+
+    public Generator(long seed) {
+        this(new Random(seed));
+    }
+
+    public Generator(Random random) {
+        this.random = random;
+    }
+
+    /**
+     * @param count how many tokens to generate, not counting whitespace and comments between them
+     * @throws IllegalStateException if the source doesn't lex to the expected tokens, which is a bug
+     */
+    public Generated generate(int count) {
+        source.setLength(0);
+        tokens.clear();
+        line = 0;
+        column = 0;
+        for (int i = 0; i < count; i++) {
+            if (i > 0)
+                separator();
+            token();
+        }
+        add(TokenType.WHITESPACE, "\n");
+
+        String text = source.toString();
+        List<Token> lexed = Lexer.tokenize(text, OPTIONS).tokens;
+        if (!lexed.equals(tokens)) {
+            int i = 0;
+            while (i < Math.min(lexed.size(), tokens.size()) && lexed.get(i).equals(tokens.get(i)))
+                i++;
+            throw new IllegalStateException("Generated source doesn't lex to the expected tokens, expected "
+                    + (i < tokens.size() ? tokens.get(i) : "end") + ", got " + (i < lexed.size() ? lexed.get(i) : "end"));
+        }
+        return new Generated(text, new ArrayList<>(tokens));
+    }
+
+    private void token() {
+        switch (random.nextInt(14)) {
+            case 0, 1 -> add(pick(KEYWORDS));
+            case 2, 3, 4 -> add(pick(PUNCTUATION));
+            case 5, 6 -> add(TokenType.IDENTIFIER, identifier());
+            case 7 -> add(TokenType.RAW_IDENTIFIER, "r#" + (random.nextBoolean() ? identifier() : rawKeyword()));
+            case 8 -> lifetime();
+            case 9 -> {
+                if (random.nextBoolean())
+                    add(TokenType.CHAR_LITERAL, "'" + pick(CHARS) + "'");
+                else
+                    add(TokenType.BYTE_LITERAL, "b'" + pick(BYTES) + "'");
+            }
+            case 10 -> {
+                if (random.nextBoolean())
+                    add(TokenType.STRING_LITERAL, "\"" + parts(STRING_PARTS) + "\"");
+                else
+                    add(TokenType.BYTE_STRING_LITERAL, "b\"" + parts(BYTE_STRING_PARTS) + "\"");
+            }
+            case 11 -> rawString();
+            case 12 -> integer();
+            default -> floatLiteral();
+        }
+    }
+
+    private String identifier() {
+        while (true) {
+            String word = pick(WORDS);
+            if (random.nextInt(3) == 0)
+                word += "_" + pick(WORDS);
+            if (!Lexer.KEYWORDS.containsKey(word) && !Lexer.CONTEXTUAL_KEYWORDS.contains(word) && !word.equals("_"))
+                return word;
+        }
+    }
+
+    private String rawKeyword() {
+        while (true) {
+            String keyword = pick(KEYWORDS).text;
+            if (!Lexer.RAW_DISALLOWED.contains(keyword))
+                return keyword;
+        }
+    }
+
+    private void lifetime() {
+        String word = random.nextInt(8) == 0 ? "static" : identifier();
+        switch (random.nextInt(4)) {
+            case 0 -> add(TokenType.LABEL, "'" + word + ":");
+            case 1 -> add(TokenType.RAW_LIFETIME, "'r#" + (word.equals("static") ? "x" : word));
+            default -> {
+                if (word.equals("static"))
+                    add(TokenType.STATIC_LIFETIME);
+                else
+                    add(TokenType.LIFETIME, "'" + word);
+            }
+        }
+    }
+
+    /**
+     * Raw strings with quotes and fewer hashes than the delimiter inside, like r##"a"#b"##.
+     */
+    private void rawString() {
+        boolean bytes = random.nextBoolean();
+        int hashes = random.nextInt(4);
+        String end = "\"" + "#".repeat(hashes);
+        StringBuilder content = new StringBuilder();
+        for (int i = random.nextInt(6); i > 0; i--) {
+            String part = hashes > 0 && random.nextInt(3) == 0 ? "\"" + "#".repeat(random.nextInt(hashes))
+                    : pick(RAW_STRING_PARTS);
+            if (!(content + part).contains(end) && !(bytes && part.chars().anyMatch(c -> c >= 128)))
+                content.append(part);
+        }
+        //a quote at the end would close the string early
+        if (hashes > 0 && content.length() > 0 && (content + end).indexOf(end) < content.length())
+            content.setLength(0);
+        String hash = "#".repeat(hashes);
+        add(bytes ? TokenType.RAW_BYTE_STRING_LITERAL : TokenType.RAW_STRING_LITERAL,
+                (bytes ? "br" : "r") + hash + "\"" + content + end);
+    }
+
+    private void integer() {
+        String suffix = random.nextBoolean() ? pick(INT_SUFFIXES) : "";
+        switch (random.nextInt(4)) {
+            case 0 -> add(TokenType.INT_LITERAL_HEX, "0x" + digits("0123456789abcdefABCDEF") + suffix);
+            case 1 -> add(TokenType.INT_LITERAL_OCTAL, "0o" + digits("01234567") + suffix);
+            case 2 -> add(TokenType.INT_LITERAL_BIN, "0b" + digits("01") + suffix);
+            default -> add(TokenType.INT_LITERAL_DEC, digits("0123456789") + suffix);
+        }
+    }
+
+    private void floatLiteral() {
+        String text = digits("0123456789");
+        boolean fraction = random.nextBoolean();
+        if (fraction)
+            text += "." + digits("0123456789");
+        if (!fraction || random.nextBoolean())
+            text += (random.nextBoolean() ? "e" : "E") + pick(new String[] {"", "+", "-"}) + digits("0123456789");
+        if (random.nextBoolean())
+            text += pick(FLOAT_SUFFIXES);
+        add(TokenType.FLOAT_LITERAL, text);
+    }
+
+    /**
+     * Digits with underscores between some of them.
+     */
+    private String digits(String alphabet) {
+        StringBuilder digits = new StringBuilder();
+        for (int i = random.nextInt(6); i >= 0; i--) {
+            digits.append(alphabet.charAt(random.nextInt(alphabet.length())));
+            if (i > 0 && random.nextInt(4) == 0)
+                digits.append('_');
+        }
+        return digits.toString();
+    }
+
+    /**
+     * Whitespace, maybe with a comment, possibly nested, or a doc comment in it.
+     */
+    private void separator() {
+        add(TokenType.WHITESPACE, pick(WHITESPACE));
+        if (random.nextInt(4) != 0)
+            return;
+        switch (random.nextInt(6)) {
+            case 0 -> add(TokenType.COMMENT, "// " + parts(COMMENT_PARTS).replace("\n", ""));
+            case 1 -> add(TokenType.COMMENT_OUTER_DOC, "/// " + parts(COMMENT_PARTS).replace("\n", ""));
+            case 2 -> add(TokenType.COMMENT_INNER_DOC, "//! " + parts(COMMENT_PARTS).replace("\n", ""));
+            case 3 -> add(TokenType.COMMENT_OUTER_DOC, "/** " + blockComment(2) + " */");
+            case 4 -> add(TokenType.COMMENT_INNER_DOC, "/*! " + blockComment(2) + " */");
+            default -> add(TokenType.COMMENT, "/* " + blockComment(3) + " */");
+        }
+        //line comments end at the line break
+        add(TokenType.WHITESPACE, "\n");
+    }
+
+    private String blockComment(int depth) {
+        StringBuilder comment = new StringBuilder();
+        for (int i = random.nextInt(5); i >= 0; i--) {
+            if (depth > 0 && random.nextInt(4) == 0)
+                comment.append("/*").append(blockComment(depth - 1)).append("*/");
+            else
+                comment.append(pick(COMMENT_PARTS));
+        }
+        return comment.toString();
+    }
+
+    private String parts(String[] parts) {
+        StringBuilder text = new StringBuilder();
+        for (int i = random.nextInt(6); i > 0; i--)
+            text.append(pick(parts));
+        return text.toString();
+    }
+
+    private <T> T pick(T[] values) {
+        return values[random.nextInt(values.length)];
+    }
+
+    private <T> T pick(List<T> values) {
+        return values.get(random.nextInt(values.size()));
+    }
+
+    private void add(TokenType type) {
+        add(type, type.text);
+    }
+
+    private void add(TokenType type, String text) {
+        tokens.add(new Token(line, column, source.length(), text.length(), type, type.text != null ? null : text));
+        source.append(text);
+        for (int i = 0; i < text.length(); i++) {
+            if (text.charAt(i) == '\n') {
+                line++;
+                column = 0;
+            } else {
+                column++;
+            }
+        }
+    }
+
+    public static final class Generated {
+        public final String source;
+        /** What the source lexes to with {@link #OPTIONS}, including whitespace and comments */
+        public final List<Token> tokens;
+
+        private Generated(String source, List<Token> tokens) {
+            this.source = source;
+            this.tokens = Collections.unmodifiableList(tokens);
+        }
+
+        /**
+         * The tokens a lexer with default options gives: no whitespace or plain comments.
+         */
+        public List<Token> withoutTrivia() {
+            ArrayList<Token> significant = new ArrayList<>(tokens.size());
+            for (Token token : tokens) {
+                if (token.type != TokenType.WHITESPACE && token.type != TokenType.COMMENT)
+                    significant.add(token);
+            }
+            return Collections.unmodifiableList(significant);
+        }
+    }
+}
@@ -3,16 +3,42 @@ package ua.yuriih.rustlexer;
 import org.fusesource.jansi.Ansi;
 import org.fusesource.jansi.AnsiPrintStream;
 
+import java.io.BufferedReader;
 import java.io.IOException;
 import java.io.InputStream;
+import java.io.InputStreamReader;
+import java.io.Reader;
+import java.io.UncheckedIOException;
+import java.nio.charset.Charset;
+import java.nio.charset.StandardCharsets;
 import java.util.ArrayList;
+import java.util.Collections;
+import java.util.Iterator;
 import java.util.List;
+import java.util.Locale;
+import java.util.NoSuchElementException;
 import java.util.stream.Stream;
 
 import static org.fusesource.jansi.Ansi.ansi;
 
 public class Highlighter {
+    private final Theme theme;
+
+    public Highlighter() {
+        this(Theme.TERMINAL);
+    }
+
+    public Highlighter(Theme theme) {
+        this.theme = theme;
+    }
+
     public void printHighlighted(AnsiPrintStream printStream, InputStream source, ArrayList<Token> tokens) throws IOException {
+        printHighlighted(printStream, source, StandardCharsets.UTF_8, tokens);
+    }
+
+    public void printHighlighted(AnsiPrintStream printStream, InputStream sourceStream, Charset charset,
+                                 ArrayList<Token> tokens) throws IOException {
+        Reader source = new BufferedReader(new InputStreamReader(sourceStream, charset));
         int row = 0;
         int column = 0;
 
@@ -25,153 +51,18 @@ public class Highlighter {
                 return;
             c = (char) read;
 
-            boolean highlightError = false;
             while (tokenNum < tokens.size()) {
                 Token token = tokens.get(tokenNum);
 
                 if (token != null && token.line == row && token.column == column) {
                     printStream.print(ansi().reset());
-                    switch (token.type) {
-                        case AS,
-                                BREAK,
-                                CONST,
-                                CONTINUE,
-                                CRATE,
-                                ELSE,
-                                ENUM,
-                                EXTERN,
-                                FALSE,
-                                FN,
-                                FOR,
-                                IF,
-                                IMPL,
-                                IN,
-                                LET,
-                                LOOP,
-                                MATCH,
-                                MOD,
-                                MOVE,
-                                MUT,
-                                PUB,
-                                REF,
-                                RETURN,
-                                SELF_VALUE,
-                                SELF_TYPE,
-                                STATIC,
-                                STRUCT,
-                                SUPER,
-                                TRAIT,
-                                TRUE,
-                                TYPE,
-                                UNSAFE,
-                                USE,
-                                WHERE,
-                                WHILE,
-                                ASYNC,
-                                AWAIT,
-                                DYN,
-                                ABSTRACT,
-                                BECOME,
-                                BOX,
-                                DO,
-                                FINAL,
-                                MACRO,
-                                OVERRIDE,
-                                PRIV,
-                                TYPEOF,
-                                UNSIZED,
-                                VIRTUAL,
-                                YIELD,
-                                TRY,
-                                UNION,
-                                STATIC_LIFETIME -> printStream.print(ansi().fgMagenta());
-
-                        case IDENTIFIER,
-                                RAW_IDENTIFIER -> printStream.print(ansi().fgCyan());
-
-                        case COMMENT,
-                                COMMENT_INNER_DOC,
-                                COMMENT_OUTER_DOC -> printStream.print(ansi().a(Ansi.Attribute.ITALIC).fgBrightBlack());
-
-                        case LABEL,
-                                LIFETIME -> printStream.print(ansi().fgYellow());
-
-                        case CHAR_LITERAL,
-                                BYTE_LITERAL -> printStream.print(ansi().a(Ansi.Attribute.ITALIC).fgBlue());
-                        case STRING_LITERAL,
-                                RAW_STRING_LITERAL,
-                                BYTE_STRING_LITERAL,
-                                RAW_BYTE_STRING_LITERAL -> printStream.print(ansi().fgGreen());
-                        case INT_LITERAL_DEC,
-                                INT_LITERAL_HEX,
-                                INT_LITERAL_OCTAL,
-                                INT_LITERAL_BIN,
-                                FLOAT_LITERAL -> printStream.print(ansi().fgBlue());
-
-                        case PLUS,
-                                MINUS,
-                                STAR,
-                                SLASH,
-                                PERCENT,
-                                CARET,
-                                NOT,
-                                AND,
-                                OR,
-                                AND_AND,
-                                OR_OR,
-                                SHL,
-                                SHR,
-                                PLUS_EQ,
-                                MINUS_EQ,
-                                STAR_EQ,
-                                SLASH_EQ,
-                                PERCENT_EQ,
-                                CARET_EQ,
-                                AND_EQ,
-                                OR_EQ,
-                                SHL_EQ,
-                                SHR_EQ,
-                                EQ,
-                                EQ_EQ,
-                                NE,
-                                GT,
-                                LT,
-                                GE,
-                                LE,
-                                AT,
-                                UNDERSCORE,
-                                DOT,
-                                DOT_DOT,
-                                DOT_DOT_DOT,
-                                DOT_DOT_EQ,
-                                COMMA,
-                                SEMICOLON,
-                                COLON,
-                                PATH_SEPARATOR,
-                                R_ARROW,
-                                FAT_ARROW,
-                                POUND,
-                                DOLLAR,
-                                QUESTION,
-
-                                CURLY_L,
-                                CURLY_R,
-                                SQUARE_L,
-                                SQUARE_R,
-                                PAREN_L,
-                                PAREN_R -> { }
-
-                        case ERROR -> highlightError = true;
-                    }
+                    printStream.print(toAnsi(theme.style(token.type)));
                     tokenNum++;
                 } else {
                     break;
                 }
             }
 
-            if (highlightError)
-                printStream.print(ansi().bgRed());
-
             printStream.print(c);
 
             if (c == '\n') {
@@ -182,4 +73,243 @@ public class Highlighter {
             }
         }
     }
+
+    /**
+     * @return the source as a {@code <pre>} block, with inline styles so it doesn't need a stylesheet
+     */
+    public String toHtml(String source, List<Token> tokens) {
+        StringBuilder html = new StringBuilder("<pre class=\"rust\"");
+        if (theme.foreground != null || theme.background != null) {
+            html.append(" style=\"");
+            if (theme.foreground != null)
+                html.append("color:").append(cssColor(theme.foreground)).append(';');
+            if (theme.background != null)
+                html.append("background:").append(cssColor(theme.background)).append(';');
+            html.append('"');
+        }
+        html.append('>');
+
+        int position = 0;
+        for (Token token : tokens) {
+            if (token.isSynthetic() || token.offset < position)
+                continue;
+            int end = Math.min(token.end(), source.length());
+            escapeHtml(html, source, position, token.offset);
+            Theme.Style style = theme.style(token.type);
+            if (style.isPlain()) {
+                escapeHtml(html, source, token.offset, end);
+            } else {
+                html.append("<span style=\"").append(css(style)).append("\">");
+                escapeHtml(html, source, token.offset, end);
+                html.append("</span>");
+            }
+            position = end;
+        }
+        escapeHtml(html, source, position, source.length());
+        return html.append("</pre>\n").toString();
+    }
+
+    /**
+     * Lexes the source and highlights lines [fromLine, toLine), 0-based, for pagers and editors that draw
+     * line by line. Tokens spanning several lines, like block comments, are split between them.
+     */
+    public Iterator<StyledLine> highlightLines(String source, int fromLine, int toLine) {
+        List<Token> tokens;
+        try {
+            tokens = Lexer.fromString(source, LexerOptions.builder().emitTrivia(true).build()).parse();
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+        return highlightLines(source, tokens, fromLine, toLine);
+    }
+
+    /**
+     * Same as {@link #highlightLines(String, int, int)}, with tokens that were already lexed from the source.
+     */
+    public Iterator<StyledLine> highlightLines(String source, List<Token> tokens, int fromLine, int toLine) {
+        int start = 0;
+        for (int line = 0; line < fromLine && start >= 0; line++) {
+            start = source.indexOf('\n', start);
+            if (start >= 0)
+                start++;
+        }
+        int firstLineStart = start;
+
+        return new Iterator<>() {
+            int line = fromLine;
+            int lineStart = firstLineStart;
+            int tokenIndex = 0;
+
+            @Override
+            public boolean hasNext() {
+                return line < toLine && lineStart >= 0 && (lineStart < source.length() || line == 0);
+            }
+
+            @Override
+            public StyledLine next() {
+                if (!hasNext())
+                    throw new NoSuchElementException();
+                int lineEnd = source.indexOf('\n', lineStart);
+                int nextLineStart = lineEnd < 0 ? -1 : lineEnd + 1;
+                if (lineEnd < 0)
+                    lineEnd = source.length();
+                if (lineEnd > lineStart && source.charAt(lineEnd - 1) == '\r')
+                    lineEnd--;
+
+                ArrayList<Segment> segments = new ArrayList<>();
+                int position = lineStart;
+                //tokens that ended before this line won't be needed again
+                while (tokenIndex < tokens.size() && tokenEnd(tokens.get(tokenIndex)) <= lineStart)
+                    tokenIndex++;
+                for (int i = tokenIndex; i < tokens.size() && position < lineEnd; i++) {
+                    Token token = tokens.get(i);
+                    if (token.isSynthetic() || tokenEnd(token) <= position)
+                        continue;
+                    if (token.offset >= lineEnd)
+                        break;
+                    if (token.offset > position)
+                        addSegment(segments, Theme.Style.PLAIN, source.substring(position, token.offset));
+                    int end = Math.min(tokenEnd(token), lineEnd);
+                    addSegment(segments, theme.style(token.type),
+                            source.substring(Math.max(position, token.offset), end));
+                    position = end;
+                }
+                if (position < lineEnd)
+                    addSegment(segments, Theme.Style.PLAIN, source.substring(position, lineEnd));
+
+                StyledLine result = new StyledLine(line, segments);
+                line++;
+                lineStart = nextLineStart;
+                return result;
+            }
+
+            private int tokenEnd(Token token) {
+                return Math.min(token.end(), source.length());
+            }
+        };
+    }
+
+    private static void addSegment(ArrayList<Segment> segments, Theme.Style style, String text) {
+        if (text.isEmpty())
+            return;
+        Segment last = segments.isEmpty() ? null : segments.get(segments.size() - 1);
+        if (last != null && last.style == style)
+            segments.set(segments.size() - 1, new Segment(style, last.text + text));
+        else
+            segments.add(new Segment(style, text));
+    }
+
+    private static Ansi toAnsi(Theme.Style style) {
+        Ansi ansi = ansi();
+        if (style.foreground != null) {
+            if (style.foreground.startsWith("#"))
+                ansi.fgRgb(Integer.parseInt(style.foreground.substring(1), 16));
+            else if (style.foreground.startsWith("bright-"))
+                ansi.fgBright(ansiColor(style.foreground.substring("bright-".length())));
+            else
+                ansi.fg(ansiColor(style.foreground));
+        }
+        if (style.background != null) {
+            if (style.background.startsWith("#"))
+                ansi.bgRgb(Integer.parseInt(style.background.substring(1), 16));
+            else if (style.background.startsWith("bright-"))
+                ansi.bgBright(ansiColor(style.background.substring("bright-".length())));
+            else
+                ansi.bg(ansiColor(style.background));
+        }
+        if (style.bold)
+            ansi.a(Ansi.Attribute.INTENSITY_BOLD);
+        if (style.italic)
+            ansi.a(Ansi.Attribute.ITALIC);
+        if (style.underline)
+            ansi.a(Ansi.Attribute.UNDERLINE);
+        return ansi;
+    }
+
+    private static Ansi.Color ansiColor(String name) {
+        return Ansi.Color.valueOf(name.toUpperCase(Locale.ROOT));
+    }
+
+    static String css(Theme.Style style) {
+        StringBuilder css = new StringBuilder();
+        if (style.foreground != null)
+            css.append("color:").append(cssColor(style.foreground)).append(';');
+        if (style.background != null)
+            css.append("background:").append(cssColor(style.background)).append(';');
+        if (style.bold)
+            css.append("font-weight:bold;");
+        if (style.italic)
+            css.append("font-style:italic;");
+        if (style.underline)
+            css.append("text-decoration:underline;");
+        return css.toString();
+    }
+
+    // terminal colors in HTML, as in the xterm palette
+    static String cssColor(String color) {
+        return switch (color) {
+            case "black" -> "#000000";
+            case "red" -> "#cd0000";
+            case "green" -> "#00cd00";
+            case "yellow" -> "#cdcd00";
+            case "blue" -> "#0000ee";
+            case "magenta" -> "#cd00cd";
+            case "cyan" -> "#00cdcd";
+            case "white" -> "#e5e5e5";
+            case "bright-black" -> "#7f7f7f";
+            case "bright-red" -> "#ff0000";
+            case "bright-green" -> "#00ff00";
+            case "bright-yellow" -> "#ffff00";
+            case "bright-blue" -> "#5c5cff";
+            case "bright-magenta" -> "#ff00ff";
+            case "bright-cyan" -> "#00ffff";
+            case "bright-white" -> "#ffffff";
+            default -> color;
+        };
+    }
+
+    static void escapeHtml(StringBuilder html, String source, int from, int to) {
+        for (int i = from; i < to; i++) {
+            char c = source.charAt(i);
+            switch (c) {
+                case '<' -> html.append("&lt;");
+                case '>' -> html.append("&gt;");
+                case '&' -> html.append("&amp;");
+                case '"' -> html.append("&quot;");
+                default -> html.append(c);
+            }
+        }
+    }
+
+    /**
+     * One line of highlighted source, without the line break.
+     */
+    public static final class StyledLine {
+        /** 0-based */
+        public final int line;
+        public final List<Segment> segments;
+
+        private StyledLine(int line, List<Segment> segments) {
+            this.line = line;
+            this.segments = Collections.unmodifiableList(segments);
+        }
+
+        public String text() {
+            StringBuilder text = new StringBuilder();
+            for (Segment segment : segments)
+                text.append(segment.text);
+            return text.toString();
+        }
+    }
+
+    public static final class Segment {
+        public final Theme.Style style;
+        public final String text;
+
+        private Segment(Theme.Style style, String text) {
+            this.style = style;
+            this.text = text;
+        }
+    }
 }
@@ -0,0 +1,130 @@
+package ua.yuriih.rustlexer;
+
+import java.util.EnumSet;
+import java.util.List;
+
+/**
+ * A standalone HTML page with two versions of a file side by side, for reviewing changes to generated code.
+ * Changed tokens are highlighted, and whitespace and comments that changed between tokens that didn't are dimmed,
+ * so reformatting doesn't drown out real changes.
+ */
+public final class HtmlDiff {
+    private static final String SCRIPT = String.join("\n",
+            "var panes = document.querySelectorAll('.pane');",
+            "panes.forEach(function (pane) {",
+            "  pane.addEventListener('scroll', function () {",
+            "    panes.forEach(function (other) {",
+            "      if (other === pane) return;",
+            "      other.scrollTop = pane.scrollTop;",
+            "      other.scrollLeft = pane.scrollLeft;",
+            "    });",
+            "  });",
+            "});"
+    );
+
+    private static final String STYLE = String.join("\n",
+            "body { margin: 0; display: flex; flex-direction: column; height: 100vh; font-family: sans-serif; }",
+            "#summary { padding: 4px 8px; border-bottom: 1px solid #ccc; font-size: 13px; }",
+            "#panes { flex: 1; display: flex; min-height: 0; }",
+            ".pane { flex: 1; overflow: auto; margin: 0; padding: 8px; }",
+            ".pane + .pane { border-left: 1px solid #ccc; }",
+            ".ln { display: inline-block; width: 4em; color: #999; user-select: none; }",
+            ".ln::before { content: attr(data-n); }",
+            ".del { background: #ffd7d5; }",
+            ".ins { background: #ccffd8; }",
+            ".trivia { background: #eee; opacity: 0.6; }"
+    );
+
+    private HtmlDiff() {
+    }
+
+    public static String toHtml(String title, String before, String after, TokenDiff diff) {
+        return toHtml(title, before, after, diff, Theme.LIGHT);
+    }
+
+    /**
+     * @param title  page title, usually the file name
+     * @param before source of the old version
+     * @param after  source of the new version
+     * @param diff   of the tokens lexed from them, preferably with trivia
+     * @param theme  colors of the source
+     */
+    public static String toHtml(String title, String before, String after, TokenDiff diff, Theme theme) {
+        EnumSet<TokenType> types = EnumSet.noneOf(TokenType.class);
+        for (Token token : diff.before)
+            types.add(token.type);
+        for (Token token : diff.after)
+            types.add(token.type);
+
+        StringBuilder html = new StringBuilder();
+        html.append("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+        Highlighter.escapeHtml(html, title, 0, title.length());
+        html.append("</title>\n<style>\n").append(STYLE).append('\n');
+        for (TokenType type : types) {
+            Theme.Style style = theme.style(type);
+            if (!style.isPlain())
+                html.append(".k-").append(type.name()).append(" { ").append(Highlighter.css(style)).append(" }\n");
+        }
+        html.append("</style>\n</head>\n<body>\n<div id=\"summary\">");
+        int edits = diff.edits().size();
+        if (edits == 0)
+            html.append("Only whitespace and comments changed");
+        else
+            html.append(edits).append(edits == 1 ? " change" : " changes");
+        html.append("</div>\n<div id=\"panes\">\n");
+        writePane(html, before, diff.before, true, diff, theme);
+        writePane(html, after, diff.after, false, diff, theme);
+        html.append("</div>\n<script>\n").append(SCRIPT).append("\n</script>\n</body>\n</html>\n");
+        return html.toString();
+    }
+
+    private static void writePane(StringBuilder html, String source, List<Token> tokens, boolean isBefore,
+                                  TokenDiff diff, Theme theme) {
+        html.append("<pre class=\"pane\"");
+        if (theme.foreground != null || theme.background != null) {
+            html.append(" style=\"");
+            if (theme.foreground != null)
+                html.append("color:").append(Highlighter.cssColor(theme.foreground)).append(';');
+            if (theme.background != null)
+                html.append("background:").append(Highlighter.cssColor(theme.background)).append(';');
+            html.append('"');
+        }
+        html.append("><span class=\"ln\" data-n=\"1\"></span>");
+        int[] line = {1};
+        int position = 0;
+        for (int i = 0; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (token.isSynthetic() || token.offset < position)
+                continue;
+            int end = Math.min(token.end(), source.length());
+            escape(html, source, position, token.offset, line);
+            html.append("<span class=\"k-").append(token.type.name());
+            TokenDiff.Status status = isBefore ? diff.before(i) : diff.after(i);
+            if (status == TokenDiff.Status.CHANGED)
+                html.append(isBefore ? " del" : " ins");
+            else if (status == TokenDiff.Status.TRIVIA_CHANGED)
+                html.append(" trivia");
+            html.append("\">");
+            escape(html, source, token.offset, end, line);
+            html.append("</span>");
+            position = end;
+        }
+        escape(html, source, position, source.length(), line);
+        html.append("</pre>\n");
+    }
+
+    /**
+     * Escapes the text and numbers the lines that start in it.
+     */
+    private static void escape(StringBuilder html, String source, int from, int to, int[] line) {
+        int start = from;
+        for (int i = from; i < to; i++) {
+            if (source.charAt(i) == '\n' && i + 1 < source.length()) {
+                Highlighter.escapeHtml(html, source, start, i + 1);
+                html.append("<span class=\"ln\" data-n=\"").append(++line[0]).append("\"></span>");
+                start = i + 1;
+            }
+        }
+        Highlighter.escapeHtml(html, source, start, to);
+    }
+}
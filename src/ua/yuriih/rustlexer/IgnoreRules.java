@@ -0,0 +1,134 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.List;
+import java.util.regex.Pattern;
+
+/**
+ * Patterns from a single .gitignore or .ignore file.
+ * Supports comments, negation, directory-only patterns, anchoring and the usual wildcards including **.
+ */
+final class IgnoreRules {
+    private final List<Rule> rules = new ArrayList<>();
+
+    static IgnoreRules load(Path file) throws IOException {
+        IgnoreRules ignoreRules = new IgnoreRules();
+        for (String line : Files.readAllLines(file))
+            ignoreRules.add(line);
+        return ignoreRules;
+    }
+
+    boolean isEmpty() {
+        return rules.isEmpty();
+    }
+
+    private void add(String line) {
+        line = stripTrailingSpaces(line);
+        if (line.isEmpty() || line.startsWith("#"))
+            return;
+
+        boolean negated = false;
+        if (line.startsWith("!")) {
+            negated = true;
+            line = line.substring(1);
+        } else if (line.startsWith("\\!") || line.startsWith("\\#")) {
+            line = line.substring(1);
+        }
+
+        boolean directoryOnly = false;
+        if (line.endsWith("/")) {
+            directoryOnly = true;
+            line = line.substring(0, line.length() - 1);
+        }
+        if (line.isEmpty())
+            return;
+
+        // a slash anywhere except at the end anchors the pattern to this directory
+        boolean anchored = line.contains("/");
+        if (line.startsWith("/"))
+            line = line.substring(1);
+
+        String regex = globToRegex(line);
+        if (!anchored)
+            regex = "(?:.*/)?" + regex;
+        rules.add(new Rule(Pattern.compile(regex), negated, directoryOnly));
+    }
+
+    /**
+     * @param relativePath path relative to the directory of the ignore file, with / separators
+     * @return true if ignored, false if explicitly re-included, null if no rule matches
+     */
+    Boolean match(String relativePath, boolean isDirectory) {
+        Boolean result = null;
+        for (Rule rule : rules) {
+            if (rule.directoryOnly && !isDirectory)
+                continue;
+            if (rule.pattern.matcher(relativePath).matches())
+                result = !rule.negated;
+        }
+        return result;
+    }
+
+    private static String stripTrailingSpaces(String line) {
+        int end = line.length();
+        while (end > 0 && line.charAt(end - 1) == ' ' && !(end > 1 && line.charAt(end - 2) == '\\'))
+            end--;
+        return line.substring(0, end);
+    }
+
+    static String globToRegex(String glob) {
+        StringBuilder regex = new StringBuilder();
+        for (int i = 0; i < glob.length(); i++) {
+            char c = glob.charAt(i);
+            switch (c) {
+                case '*' -> {
+                    if (i + 1 < glob.length() && glob.charAt(i + 1) == '*') {
+                        i++;
+                        if (i + 1 < glob.length() && glob.charAt(i + 1) == '/') {
+                            i++;
+                            regex.append("(?:.*/)?"); // "**/" matches zero or more directories
+                        } else {
+                            regex.append(".*");
+                        }
+                    } else {
+                        regex.append("[^/]*");
+                    }
+                }
+                case '?' -> regex.append("[^/]");
+                case '[' -> {
+                    int close = glob.indexOf(']', i + 1);
+                    if (close < 0) {
+                        regex.append("\\[");
+                    } else {
+                        String set = glob.substring(i + 1, close);
+                        if (set.startsWith("!"))
+                            set = "^" + set.substring(1);
+                        regex.append('[').append(set.replace("\\", "\\\\")).append(']');
+                        i = close;
+                    }
+                }
+                case '\\' -> {
+                    if (i + 1 < glob.length())
+                        regex.append(Pattern.quote(String.valueOf(glob.charAt(++i))));
+                }
+                default -> regex.append(Pattern.quote(String.valueOf(c)));
+            }
+        }
+        return regex.toString();
+    }
+
+    private static final class Rule {
+        final Pattern pattern;
+        final boolean negated;
+        final boolean directoryOnly;
+
+        Rule(Pattern pattern, boolean negated, boolean directoryOnly) {
+            this.pattern = pattern;
+            this.negated = negated;
+            this.directoryOnly = directoryOnly;
+        }
+    }
+}
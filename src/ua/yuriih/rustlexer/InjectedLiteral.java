@@ -0,0 +1,109 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * A string literal whose contents were lexed by an {@link InjectionHook}. The nested tokens are
+ * {@link CustomToken}s positioned in the source like any other token, their value is their source text, escapes
+ * included, and their kind is the hook's language and the span's kind, like "regex.group".
+ */
+public final class InjectedLiteral extends Token {
+    public final String language;
+    /** In order, inside the literal */
+    public final List<CustomToken> nested;
+
+    InjectedLiteral(Token literal, String language, List<CustomToken> nested) {
+        super(literal.line, literal.column, literal.offset, literal.length, literal.type, literal.value,
+                literal.flags());
+        this.language = language;
+        this.nested = List.copyOf(nested);
+    }
+
+    /**
+     * Lexes the contents of a string literal with the hook and positions its spans in the literal.
+     */
+    static InjectedLiteral inject(Token literal, InjectionHook hook) {
+        Literal parsed = new Literal(literal);
+        String text = literal.text();
+        String contents = parsed.unescape();
+        //where each char of the contents starts and ends in the token text
+        int[] starts = new int[contents.length()];
+        int[] ends = new int[contents.length()];
+        int position = text.indexOf('"') + 1;
+        int c = 0;
+        if (!parsed.kind().isRaw()) {
+            for (Literal.EscapeSequence escape : parsed.escapes()) {
+                while (position < escape.start && c < contents.length()) {
+                    starts[c] = position;
+                    ends[c++] = ++position;
+                }
+                if (escape.kind != Literal.EscapeSequence.Kind.LINE_CONTINUATION) {
+                    for (int i = Character.charCount(escape.value); i > 0 && c < contents.length(); i--) {
+                        starts[c] = escape.start;
+                        ends[c++] = escape.end;
+                    }
+                }
+                position = escape.end;
+            }
+        }
+        while (c < contents.length()) {
+            starts[c] = position;
+            ends[c++] = ++position;
+        }
+
+        String kindPrefix = hook.language() + ".";
+        ArrayList<CustomToken> nested = new ArrayList<>();
+        int line = literal.line;
+        int column = literal.column;
+        int walked = 0;
+        for (InjectionHook.Span span : hook.lex(contents)) {
+            if (span.end > contents.length())
+                throw new IllegalArgumentException("Span " + span + " is past the end of " + contents.length()
+                        + " chars of contents");
+            if (span.start == span.end)
+                continue;
+            int start = starts[span.start];
+            if (start < walked)
+                throw new IllegalArgumentException("Span " + span + " overlaps the one before it");
+            for (; walked < start; walked++) {
+                if (text.charAt(walked) == '\n') {
+                    line++;
+                    column = 0;
+                } else {
+                    column++;
+                }
+            }
+            nested.add(new CustomToken(line, column, literal.offset + start, kindPrefix + span.kind,
+                    text.substring(start, ends[span.end - 1])));
+        }
+        return new InjectedLiteral(literal, hook.language(), nested);
+    }
+
+    /**
+     * Nested tokens move along, their columns only change on the literal's first line, and they get the literal's
+     * provenance.
+     */
+    @Override
+    Token copy(int line, int column, int offset, int flags) {
+        ArrayList<CustomToken> moved = new ArrayList<>(nested.size());
+        for (CustomToken token : nested) {
+            int tokenColumn = token.line == this.line ? token.column - this.column + column : token.column;
+            moved.add((CustomToken) token.copy(token.line - this.line + line, tokenColumn,
+                    token.offset - this.offset + offset,
+                    (token.flags() & ~PROVENANCE_FLAGS) | (flags & PROVENANCE_FLAGS)));
+        }
+        return new InjectedLiteral(new Token(line, column, offset, length, type, value, flags), language, moved);
+    }
+
+    @Override
+    public boolean equals(Object o) {
+        return super.equals(o) && language.equals(((InjectedLiteral) o).language)
+                && nested.equals(((InjectedLiteral) o).nested);
+    }
+
+    @Override
+    public int hashCode() {
+        return 31 * super.hashCode() + nested.hashCode();
+    }
+}
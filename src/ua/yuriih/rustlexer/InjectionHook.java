@@ -0,0 +1,54 @@
+package ua.yuriih.rustlexer;
+
+import java.util.List;
+
+/**
+ * Lexes another language embedded in string literals, like SQL, a regex or a format string, so highlighters can
+ * color it too. Matching literals come out of the lexer as {@link InjectedLiteral}s with the hook's tokens nested
+ * in them; code that doesn't care still sees an ordinary string literal.
+ */
+public interface InjectionHook {
+    /**
+     * Name of the embedded language, e.g. "sql". Nested tokens get kinds like "sql.keyword".
+     */
+    String language();
+
+    /**
+     * Called for string, raw string, byte string and raw byte string literals.
+     *
+     * @param preceding up to {@link Lexer#INJECTION_CONTEXT} tokens before the literal, oldest first (fewer at the
+     *                  start), without whitespace; comments are there if the lexer {@link LexerOptions.Builder#emitTrivia
+     *                  emits trivia}, so a literal can be marked with one, like <code>/*sql*&#47; "SELECT 1"</code>
+     * @return true to lex the literal's contents with this hook
+     */
+    boolean matches(Token literal, List<Token> preceding);
+
+    /**
+     * @param contents the literal's value with escapes replaced, see {@link Literal#unescape()}
+     * @return sub-tokens, in order and not overlapping; parts of the contents can be left out
+     */
+    List<Span> lex(String contents);
+
+    /**
+     * A sub-token, as a range of the contents that were passed to {@link #lex}.
+     */
+    final class Span {
+        public final int start;
+        /** Exclusive */
+        public final int end;
+        public final String kind;
+
+        public Span(int start, int end, String kind) {
+            if (start < 0 || end < start)
+                throw new IllegalArgumentException("Invalid span: " + start + ".." + end);
+            this.start = start;
+            this.end = end;
+            this.kind = kind;
+        }
+
+        @Override
+        public String toString() {
+            return start + ".." + end + " " + kind;
+        }
+    }
+}
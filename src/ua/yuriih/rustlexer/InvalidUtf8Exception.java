@@ -0,0 +1,21 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+
+/**
+ * Thrown by {@link Lexer#fromBytes} with {@link Lexer.Utf8Policy#ERROR} when the input isn't valid UTF-8.
+ */
+public class InvalidUtf8Exception extends IOException {
+    private static final long serialVersionUID = 1L;
+
+    /** Where the invalid sequence starts, in bytes */
+    public final int byteOffset;
+    /** Length of the invalid sequence, in bytes */
+    public final int length;
+
+    public InvalidUtf8Exception(int byteOffset, int length) {
+        super("Invalid UTF-8 at byte " + byteOffset + " (" + length + (length == 1 ? " byte)" : " bytes)"));
+        this.byteOffset = byteOffset;
+        this.length = length;
+    }
+}
@@ -0,0 +1,255 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.LinkedHashMap;
+import java.util.List;
+import java.util.Locale;
+import java.util.Map;
+
+/**
+ * Just enough JSON for the CLI output formats and the language server.
+ */
+final class Json {
+    private final String text;
+    private int position = 0;
+
+    private Json(String text) {
+        this.text = text;
+    }
+
+    /**
+     * @return a Map, List, String, Long (for integers), Double, Boolean or null
+     * @throws IllegalArgumentException if the text isn't valid JSON
+     */
+    static Object parse(String text) {
+        Json parser = new Json(text);
+        Object value = parser.value();
+        parser.skipWhitespace();
+        if (parser.position != text.length())
+            throw parser.error("Unexpected text after the value");
+        return value;
+    }
+
+    /**
+     * Writes a value made of the same types as {@link #parse} returns, any Number is allowed.
+     */
+    static String write(Object value) {
+        StringBuilder sb = new StringBuilder();
+        write(sb, value);
+        return sb.toString();
+    }
+
+    private static void write(StringBuilder sb, Object value) {
+        if (value == null || value instanceof Boolean || value instanceof Number) {
+            sb.append(value);
+        } else if (value instanceof String) {
+            sb.append(quote((String) value));
+        } else if (value instanceof Map) {
+            sb.append('{');
+            boolean first = true;
+            for (Map.Entry<?, ?> entry : ((Map<?, ?>) value).entrySet()) {
+                if (!first)
+                    sb.append(',');
+                first = false;
+                sb.append(quote(entry.getKey().toString())).append(':');
+                write(sb, entry.getValue());
+            }
+            sb.append('}');
+        } else if (value instanceof List) {
+            sb.append('[');
+            boolean first = true;
+            for (Object element : (List<?>) value) {
+                if (!first)
+                    sb.append(',');
+                first = false;
+                write(sb, element);
+            }
+            sb.append(']');
+        } else {
+            throw new IllegalArgumentException("Can't write " + value.getClass() + " as JSON");
+        }
+    }
+
+    private Object value() {
+        skipWhitespace();
+        if (position >= text.length())
+            throw error("Unexpected end");
+        char c = text.charAt(position);
+        switch (c) {
+            case '{' -> {
+                position++;
+                LinkedHashMap<String, Object> object = new LinkedHashMap<>();
+                skipWhitespace();
+                if (consume('}'))
+                    return object;
+                do {
+                    skipWhitespace();
+                    if (position >= text.length() || text.charAt(position) != '"')
+                        throw error("Expected a key");
+                    String key = string();
+                    skipWhitespace();
+                    if (!consume(':'))
+                        throw error("Expected ':'");
+                    object.put(key, value());
+                    skipWhitespace();
+                } while (consume(','));
+                if (!consume('}'))
+                    throw error("Expected '}'");
+                return object;
+            }
+            case '[' -> {
+                position++;
+                ArrayList<Object> array = new ArrayList<>();
+                skipWhitespace();
+                if (consume(']'))
+                    return array;
+                do {
+                    array.add(value());
+                    skipWhitespace();
+                } while (consume(','));
+                if (!consume(']'))
+                    throw error("Expected ']'");
+                return array;
+            }
+            case '"' -> {
+                return string();
+            }
+            default -> {
+                if (text.startsWith("true", position)) {
+                    position += 4;
+                    return true;
+                } else if (text.startsWith("false", position)) {
+                    position += 5;
+                    return false;
+                } else if (text.startsWith("null", position)) {
+                    position += 4;
+                    return null;
+                }
+                return number();
+            }
+        }
+    }
+
+    private String string() {
+        position++;
+        StringBuilder sb = new StringBuilder();
+        while (true) {
+            if (position >= text.length())
+                throw error("Unclosed string");
+            char c = text.charAt(position++);
+            if (c == '"')
+                return sb.toString();
+            if (c != '\\') {
+                sb.append(c);
+                continue;
+            }
+            if (position >= text.length())
+                throw error("Unclosed string");
+            char escaped = text.charAt(position++);
+            switch (escaped) {
+                case '"', '\\', '/' -> sb.append(escaped);
+                case 'n' -> sb.append('\n');
+                case 'r' -> sb.append('\r');
+                case 't' -> sb.append('\t');
+                case 'b' -> sb.append('\b');
+                case 'f' -> sb.append('\f');
+                case 'u' -> {
+                    if (position + 4 > text.length())
+                        throw error("Invalid escape");
+                    try {
+                        sb.append((char) Integer.parseInt(text.substring(position, position + 4), 16));
+                    } catch (NumberFormatException e) {
+                        throw error("Invalid escape");
+                    }
+                    position += 4;
+                }
+                default -> throw error("Invalid escape");
+            }
+        }
+    }
+
+    private Number number() {
+        int start = position;
+        while (position < text.length() && "+-0123456789.eE".indexOf(text.charAt(position)) >= 0)
+            position++;
+        String number = text.substring(start, position);
+        try {
+            if (number.indexOf('.') < 0 && number.indexOf('e') < 0 && number.indexOf('E') < 0)
+                return Long.parseLong(number);
+            return Double.parseDouble(number);
+        } catch (NumberFormatException e) {
+            position = start;
+            throw error("Unexpected " + (number.isEmpty() ? "character" : "number"));
+        }
+    }
+
+    private boolean consume(char c) {
+        if (position < text.length() && text.charAt(position) == c) {
+            position++;
+            return true;
+        }
+        return false;
+    }
+
+    private void skipWhitespace() {
+        while (position < text.length() && " \t\r\n".indexOf(text.charAt(position)) >= 0)
+            position++;
+    }
+
+    private IllegalArgumentException error(String message) {
+        return new IllegalArgumentException(message + " at " + position);
+    }
+
+    static String quote(String s) {
+        if (s == null)
+            return "null";
+        StringBuilder sb = new StringBuilder(s.length() + 2);
+        sb.append('"');
+        for (int i = 0; i < s.length(); i++) {
+            char c = s.charAt(i);
+            switch (c) {
+                case '"' -> sb.append("\\\"");
+                case '\\' -> sb.append("\\\\");
+                case '\n' -> sb.append("\\n");
+                case '\r' -> sb.append("\\r");
+                case '\t' -> sb.append("\\t");
+                case '\b' -> sb.append("\\b");
+                case '\f' -> sb.append("\\f");
+                default -> {
+                    if (c < 0x20)
+                        sb.append(String.format("\\u%04x", (int) c));
+                    else
+                        sb.append(c);
+                }
+            }
+        }
+        sb.append('"');
+        return sb.toString();
+    }
+
+    /**
+     * One-line object with the token's fields, positions are 0-based like in {@link Token}.
+     */
+    static String token(Token token) {
+        return write(tokenObject(token));
+    }
+
+    /**
+     * The object {@link #token} writes, to put it inside other values.
+     */
+    static Map<String, Object> tokenObject(Token token) {
+        LinkedHashMap<String, Object> object = new LinkedHashMap<>();
+        object.put("line", token.line);
+        object.put("column", token.column);
+        object.put("offset", token.offset);
+        object.put("length", token.length);
+        object.put("type", token.type.name());
+        if (token instanceof CustomToken)
+            object.put("kind", ((CustomToken) token).kind);
+        if (token.value != null)
+            object.put("value", token.value);
+        if (token.provenance() != Token.Provenance.SOURCE)
+            object.put("provenance", token.provenance().name().toLowerCase(Locale.ROOT));
+        return object;
+    }
+}
@@ -0,0 +1,84 @@
+package ua.yuriih.rustlexer;
+
+/**
+ * {@link TokenType#ERROR} token produced by the lexer, the message is in {@link #value}.
+ */
+public class LexError extends Token {
+    public enum Kind {
+        UNEXPECTED_SYMBOL("RL0001"),
+        EMPTY_CHAR_LITERAL("RL0002"),
+        INVALID_QUOTE("RL0003"),
+        NON_ASCII_BYTE("RL0004"),
+        LINE_CONTINUATION_OUTSIDE_STRING("RL0005"),
+        UNICODE_ESCAPE_IN_BYTES("RL0006"),
+        ASCII_ESCAPE_OUT_OF_RANGE("RL0007"),
+        INVALID_HEX_ESCAPE("RL0008"),
+        UNICODE_ESCAPE_NO_BRACE("RL0009"),
+        UNICODE_ESCAPE_TOO_LONG("RL0010"),
+        INVALID_UNICODE_ESCAPE("RL0011"),
+        CHAR_LITERAL_TOO_LONG("RL0012"),
+        BYTE_LITERAL_TOO_LONG("RL0013"),
+        INVALID_RAW_STRING_START("RL0014"),
+        NO_DIGITS("RL0015"),
+        /** Not produced anymore, the sign is optional */
+        EXPONENT_NO_SIGN("RL0016"),
+        /** The error covers the exponent, e.g. "e+" in 1e+ */
+        EXPONENT_NO_DIGITS("RL0017"),
+        TOKEN_LIMIT("RL0018"),
+        /** A {@link LexWarning} with {@link LexWarning.Severity#DENY} severity */
+        DENIED_WARNING("RL0019"),
+        INVALID_RAW_IDENTIFIER("RL0020"),
+        INVALID_DIGIT("RL0021"),
+        /** A backslash followed by a char that doesn't start an escape, e.g. \q */
+        UNKNOWN_ESCAPE("RL0022"),
+        /** Unicode escape above 10FFFF */
+        UNICODE_ESCAPE_OUT_OF_RANGE("RL0023"),
+        /** Unicode escape from D800 to DFFF, code points that are only used in UTF-16 */
+        UNICODE_ESCAPE_SURROGATE("RL0024"),
+        /** A string, char or byte literal or a block comment that's still open at the end of the input */
+        UNTERMINATED("RL0025");
+
+        /** Stays the same across versions, used as the rule ID in SARIF output */
+        public final String code;
+
+        Kind(String code) {
+            this.code = code;
+        }
+    }
+
+    public final Kind kind;
+    /**
+     * Base of the number literal for {@link Kind#NO_DIGITS} and {@link Kind#INVALID_DIGIT}, e.g. 2 for 0b2,
+     * otherwise 0
+     */
+    public final int radix;
+
+    public LexError(int line, int column, int offset, int length, Kind kind, String message) {
+        this(line, column, offset, length, kind, message, 0);
+    }
+
+    public LexError(int line, int column, int offset, int length, Kind kind, String message, int radix) {
+        this(line, column, offset, length, kind, message, radix, 0);
+    }
+
+    LexError(int line, int column, int offset, int length, Kind kind, String message, int radix, int flags) {
+        super(line, column, offset, length, TokenType.ERROR, message, flags);
+        this.kind = kind;
+        this.radix = radix;
+    }
+
+    @Override
+    Token copy(int line, int column, int offset, int flags) {
+        return new LexError(line, column, offset, length, kind, value, radix, flags);
+    }
+
+    @Override
+    public boolean equals(Object o) {
+        return super.equals(o) && kind == ((LexError) o).kind && radix == ((LexError) o).radix;
+    }
+
+    @Override
+    public int hashCode() {
+        return 31 * (31 * super.hashCode() + kind.hashCode()) + radix;
+    }
+}
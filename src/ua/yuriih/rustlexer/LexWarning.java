@@ -0,0 +1,92 @@
+package ua.yuriih.rustlexer;
+
+/**
+ * Non-fatal finding, passed to the {@link LexerOptions.Builder#warningSink warning sink} instead of the token output.
+ */
+public final class LexWarning {
+    public enum Kind {
+        /** The file starts with a byte order mark */
+        BOM("RW0001"),
+        /** Identifier mixes letters from scripts that look alike, e.g. Latin and Cyrillic */
+        CONFUSABLE_IDENTIFIER("RW0002"),
+        /** Escape that doesn't mean what it looks like, e.g. "\01" is \0 followed by 1, not an octal escape */
+        UNUSUAL_ESCAPE("RW0003"),
+        /** Identifier isn't in Unicode Normalization Form C, the suggestion is the normalized form */
+        NON_NFC_IDENTIFIER("RW0004"),
+        /**
+         * Underscore in a number that doesn't separate digits, e.g. 0x_1, 1_ or 1__000. The suggestion is the
+         * underscores to keep: none, or one for a repeated underscore
+         */
+        MISPLACED_UNDERSCORE("RW0005"),
+        /** Escaped quote that doesn't need it, like \' in a string or \" in a char, the suggestion is the quote */
+        UNNECESSARY_ESCAPE("RW0006"),
+        /**
+         * Hex or Unicode escape for a printable ASCII char, like \x41 for A, the suggestion is the char.
+         * Allowed by default, some code spells chars out on purpose.
+         */
+        ESCAPED_PRINTABLE("RW0007", Severity.ALLOW),
+        /**
+         * Spaces or tabs between a backslash and the end of the line in a string, so the backslash doesn't continue
+         * the line. The suggestion is to remove them
+         */
+        SPACE_AFTER_BACKSLASH("RW0008"),
+        /**
+         * Identifier that is a keyword in a later edition, like async in 2015, for getting ready to migrate.
+         * The suggestion is the raw identifier, e.g. r#async. Allowed by default
+         */
+        FUTURE_KEYWORD("RW0009", Severity.ALLOW);
+
+        public final String code;
+        /** Severity unless {@link LexerOptions.Builder#severity} says otherwise */
+        public final Severity defaultSeverity;
+
+        Kind(String code) {
+            this(code, Severity.WARN);
+        }
+
+        Kind(String code, Severity defaultSeverity) {
+            this.code = code;
+            this.defaultSeverity = defaultSeverity;
+        }
+    }
+
+    /**
+     * What to do with a kind of warning, see {@link LexerOptions.Builder#severity}.
+     */
+    public enum Severity {
+        /** Ignore it */
+        ALLOW,
+        /** Pass it to the warning sink */
+        WARN,
+        /** Report it as an error ({@link LexError.Kind#DENIED_WARNING}) */
+        DENY
+    }
+
+    public final int line;
+    public final int column;
+    public final int offset;
+    public final int length;
+    public final Kind kind;
+    public final String message;
+    /** Replacement text for the warning's span, or null */
+    public final String suggestion;
+
+    public LexWarning(int line, int column, int offset, int length, Kind kind, String message) {
+        this(line, column, offset, length, kind, message, null);
+    }
+
+    public LexWarning(int line, int column, int offset, int length, Kind kind, String message, String suggestion) {
+        this.line = line;
+        this.column = column;
+        this.offset = offset;
+        this.length = length;
+        this.kind = kind;
+        this.message = message;
+        this.suggestion = suggestion;
+    }
+
+    @Override
+    public String toString() {
+        return line + ":" + column + "\t" + kind + "\t" + message;
+    }
+}
@@ -2,13 +2,72 @@ package ua.yuriih.rustlexer;
 
 import java.io.IOException;
 import java.io.InputStream;
+import java.io.InputStreamReader;
+import java.io.Serializable;
+import java.io.Reader;
+import java.io.StringReader;
+import java.io.UncheckedIOException;
+import java.nio.ByteBuffer;
+import java.nio.CharBuffer;
+import java.nio.charset.CharsetDecoder;
+import java.nio.charset.CoderResult;
+import java.nio.charset.CodingErrorAction;
+import java.nio.charset.StandardCharsets;
+import java.text.Normalizer;
+import java.time.Duration;
+import java.util.ArrayDeque;
 import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.Collections;
+import java.util.Comparator;
+import java.util.EnumSet;
 import java.util.HashMap;
+import java.util.List;
+import java.util.Locale;
+import java.util.Map;
+import java.util.Set;
+import java.util.function.Consumer;
+import java.util.logging.Level;
+import java.util.logging.Logger;
 
 public final class Lexer {
-    private final InputStream in;
+    private static final Logger LOG = Logger.getLogger(Lexer.class.getName());
+    /**
+     * How many tokens before a string literal {@link InjectionHook#matches} gets to see
+     */
+    public static final int INJECTION_CONTEXT = 4;
+    //how far to look past "#!" for a "[", comments included
+    private static final int SHEBANG_LOOKAHEAD = 1 << 16;
+    private static final int CHUNK_SIZE = 8192;
+    //chars between checking the clock in lexBounded
+    private static final int BOUNDED_STEP = 4096;
+
+    private static final byte ASCII_IDENTIFIER = 1;
+    private static final byte ASCII_WHITESPACE = 2;
+    private static final byte[] ASCII_CLASSES = new byte[128];
+
+    static {
+        for (char c = 0; c < 128; c++) {
+            if ((c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || (c >= '0' && c <= '9') || c == '_')
+                ASCII_CLASSES[c] |= ASCII_IDENTIFIER;
+            if (isWhitespace(c))
+                ASCII_CLASSES[c] |= ASCII_WHITESPACE;
+        }
+    }
+
+    private final Reader in;
+    //read ahead, so that runs of chars inside strings and comments can be scanned in one go
+    private char[] chunk = new char[CHUNK_SIZE];
+    private int chunkPosition = 0;
+    private int chunkLength = 0;
+    //only replaced by the deprecated addHook
+    private LexerOptions options;
+    private final Map<String, TokenType> keywords;
+    private final boolean warningsEnabled;
 
-    private final ArrayList<Token> tokens = new ArrayList<>();
+    private Consumer<? super Token> sink;
+    private final ArrayList<LexError> collectedErrors = new ArrayList<>();
+    private int tokenCount = 0;
 
     private State state = State.INITIAL;
     private State.StringEscape stringEscapeState = State.StringEscape.NONE;
@@ -16,15 +75,52 @@ public final class Lexer {
     private int rawStringEndHashCount = 0;
     private int nestedCommentDepth = 0; //block comments can be nested
     private State outerCommentState; //block comments can be nested
+    private int nestedCommentEnd = -1; //buffer length after the */ of a nested comment, its slash can't start another
+    private CustomTokenHook activeHook;
+    private final ArrayDeque<Token> injectionContext = new ArrayDeque<>(); //tokens before the next string literal
+    private TokenType suffixedLiteralType;
 
     private StringBuilder buffer;
     private int bufferStartLine;
     private int bufferStartColumn;
+    private int bufferStartOffset;
 
     private int line = 0;
     private int column = 0;
-
-    private static final HashMap<String, TokenType> KEYWORDS = new HashMap<>();
+    private int offset = 0;
+    private int errorCount = 0;
+    //slow paths, only reported in the log
+    private int rawStringCount = 0;
+    private int nestedCommentCount = 0;
+    private int refillCount = 0;
+    private long bulkChars = 0;
+    //errors that dropped the unfinished token and went back to the initial state
+    private int recoveryCount = 0;
+    private boolean addedFinalNewline = false;
+    private char lastChar = 0;
+    //code point starting at the char being lexed, from both surrogates when it's the high one of a pair
+    private int codePoint;
+    private boolean finished = false;
+    //lexBounded stops once tokenCount gets here
+    private long tokenBudgetEnd = Long.MAX_VALUE;
+    //null unless profiling or logging
+    private final long[] profileNanos;
+    private final long[] profileChars;
+    private Profile.Phase currentPhase;
+    private long phaseStart;
+    //null unless columns aren't counted in chars: text of the lines from the one the unfinished token started on
+    private final ArrayList<StringBuilder> lineTexts;
+    private int firstLineText;
+    //last converted column, so tokens on a long line don't count it from the start every time
+    private int cachedLine = -1;
+    private int cachedCharColumn;
+    private int cachedColumn;
+    //false when lexing a part of a file that comes after its start, where there's no shebang or BOM
+    private boolean startsFile = true;
+    //null unless made with fromBytes: byte offset of every char of the decoded input, and of its end
+    private int[] byteOffsets;
+
+    static final HashMap<String, TokenType> KEYWORDS = new HashMap<>();
 
     static {
         KEYWORDS.put("as", TokenType.AS);
@@ -78,101 +174,752 @@ public final class Lexer {
         KEYWORDS.put("virtual", TokenType.VIRTUAL);
         KEYWORDS.put("yield", TokenType.YIELD);
         KEYWORDS.put("try", TokenType.TRY);
-        //weak keywords are handled separately
-//        KEYWORDS.put("union", TokenType.UNION);
-//        KEYWORDS.put("'static", TokenType.STATIC_LIFETIME);
+        KEYWORDS.put("gen", TokenType.GEN);
+        //'static is handled separately
     }
 
+    /**
+     * Words that are only keywords in some contexts, they're lexed as identifiers with
+     * {@link Token#isContextualKeyword()} set, and a parser can decide.
+     */
+    static final Set<String> CONTEXTUAL_KEYWORDS = Set.of("union", "raw", "auto", "macro_rules");
+
+    //chars initialState starts an operator or punctuation token with
+    private static final String PUNCTUATION_START = "/+-*%^!&|<>=.:@,;#$?()[]{}";
+
+    /**
+     * Words that can't be made raw, as in r#crate or 'r#self
+     */
+    static final Set<String> RAW_DISALLOWED = Set.of("_", "crate", "self", "super", "Self");
+
 
     public Lexer(InputStream in) {
+        this(in, LexerOptions.DEFAULT);
+    }
+
+    /**
+     * @deprecated use {@link #withOptions}
+     */
+    @Deprecated
+    public Lexer(InputStream in, LexerOptions options) {
+        this(new InputStreamReader(in, options.charset), options);
+    }
+
+    private Lexer(Reader in, LexerOptions options) {
         this.in = in;
+        this.options = options;
+        this.keywords = options.keywords;
+        this.warningsEnabled = options.warningSink != null
+                || options.severities.containsValue(LexWarning.Severity.DENY);
+        boolean profiling = options.profile || options.log;
+        this.profileNanos = profiling ? new long[Profile.Phase.values().length] : null;
+        this.profileChars = profiling ? new long[Profile.Phase.values().length] : null;
+        this.lineTexts = options.columnMode != LexerOptions.ColumnMode.CHARS ? new ArrayList<>() : null;
     }
 
-    public ArrayList<Token> parse() throws IOException {
-        char c = 0;
+    public static Lexer withOptions(InputStream in, LexerOptions options) {
+        return new Lexer(new InputStreamReader(in, options.charset), options);
+    }
+
+    /**
+     * Hooks added here are asked about unknown characters after the ones in the options.
+     *
+     * @deprecated use {@link LexerOptions.Builder#addHook}
+     */
+    @Deprecated
+    public void addHook(CustomTokenHook hook) {
+        options = options.toBuilder().addHook(hook).build();
+    }
+
+    /**
+     * Lexes a string that's already in memory, {@link LexerOptions.Builder#charset} is ignored.
+     */
+    public static Lexer fromString(String source, LexerOptions options) {
+        return new Lexer(new StringReader(source), options);
+    }
+
+    /**
+     * Lexes the source from the start of a line, as if everything before it was lexed and no token was left
+     * unfinished. Positions still count from the start of the source.
+     */
+    static Lexer fromLineStart(String source, int offset, int line, LexerOptions options) {
+        Lexer lexer = fromString(source, options);
+        try {
+            lexer.in.skip(offset);
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+        lexer.offset = offset;
+        lexer.line = line;
+        lexer.lastChar = '\n';
+        return lexer;
+    }
+
+    /**
+     * Lexes a part of a source. Positions count from the start of the part.
+     *
+     * @param startsFile whether the part is at the start of the source, otherwise a #! in it isn't taken for
+     *                   a shebang
+     */
+    static Lexer fromRegion(String region, boolean startsFile, LexerOptions options) {
+        Lexer lexer = fromString(region, options);
+        lexer.startsFile = startsFile;
+        return lexer;
+    }
+
+    /**
+     * Lexes a text made of chunks, e.g. the leaves of an editor's rope, without copying it into one string.
+     * Token offsets count from the start of the first chunk. The chunks must not change while lexing.
+     */
+    public static Lexer fromChunks(Iterable<? extends CharSequence> chunks, LexerOptions options) {
+        return new Lexer(new ChunkReader(chunks), options);
+    }
+
+    /**
+     * Lexes UTF-8 bytes, {@link LexerOptions.Builder#charset} is ignored. Token offsets are in chars of the
+     * decoded text, {@link #byteOffset} turns them into offsets in the bytes.
+     *
+     * @param policy what to do with bytes that aren't valid UTF-8
+     * @throws InvalidUtf8Exception if the policy is {@link Utf8Policy#ERROR} and there's an invalid sequence
+     */
+    public static Lexer fromBytes(byte[] bytes, Utf8Policy policy, LexerOptions options) throws InvalidUtf8Exception {
+        CharsetDecoder decoder = StandardCharsets.UTF_8.newDecoder()
+                .onMalformedInput(CodingErrorAction.REPORT)
+                .onUnmappableCharacter(CodingErrorAction.REPORT);
+        ByteBuffer in = ByteBuffer.wrap(bytes);
+        //UTF-8 never takes fewer bytes than chars, and replacements take one char per byte
+        CharBuffer out = CharBuffer.allocate(bytes.length);
+        int[] byteOffsets = new int[bytes.length + 1];
         while (true) {
-            int read = in.read();
+            int charStart = out.position();
+            int byteOffset = in.position();
+            CoderResult result = decoder.decode(in, out, true);
+            for (int i = charStart; i < out.position(); i++) {
+                byteOffsets[i] = byteOffset;
+                char c = out.get(i);
+                //a surrogate pair takes 4 bytes, 2 are counted for each half
+                byteOffset += c < 0x80 ? 1 : c < 0x800 ? 2 : Character.isSurrogate(c) ? 2 : 3;
+            }
+            if (result.isUnderflow())
+                break;
+            switch (policy) {
+                case ERROR -> throw new InvalidUtf8Exception(in.position(), result.length());
+                case LOSSY -> {
+                    for (int i = 0; i < result.length(); i++) {
+                        byteOffsets[out.position()] = in.position() + i;
+                        out.put('\uFFFD');
+                    }
+                }
+                case SKIP -> {
+                }
+            }
+            in.position(in.position() + result.length());
+        }
+        decoder.flush(out);
+        byteOffsets[out.position()] = bytes.length;
+        Lexer lexer = fromString(out.flip().toString(), options);
+        lexer.byteOffsets = Arrays.copyOf(byteOffsets, out.limit() + 1);
+        return lexer;
+    }
+
+    /**
+     * The offset in the input bytes of a lexer made with {@link #fromBytes}, for an offset in chars like
+     * {@link Token#offset} or {@link Token#end()}. Skipped bytes are counted, so a token's byte span is
+     * byteOffset(token.offset) to byteOffset(token.end()).
+     *
+     * @param charOffset from 0 to the length of the decoded text, both included
+     * @throws IllegalStateException if the lexer wasn't made with {@link #fromBytes}
+     */
+    public int byteOffset(int charOffset) {
+        if (byteOffsets == null)
+            throw new IllegalStateException("Not lexing bytes");
+        if (charOffset < 0 || charOffset >= byteOffsets.length)
+            throw new IndexOutOfBoundsException("Offset " + charOffset + " out of 0.." + (byteOffsets.length - 1));
+        return byteOffsets[charOffset];
+    }
+
+    /**
+     * Lexes the whole string with error recovery, errors are returned separately from the other tokens.
+     */
+    public static Tokenized tokenize(String source) {
+        return tokenize(source, LexerOptions.DEFAULT);
+    }
+
+    /**
+     * Same as {@link #tokenize(String)}, but with the given options. Recovery is always on and there's no token limit.
+     */
+    public static Tokenized tokenize(String source, LexerOptions options) {
+        LexerOptions collecting = options.toBuilder().collectErrors(true).maxTokens(0).build();
+        Lexer lexer = fromString(source, collecting);
+        ArrayList<Token> tokens;
+        try {
+            tokens = lexer.parse();
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+        return new Tokenized(tokens, lexer.errors());
+    }
+
+    /**
+     * Continues lexing from a checkpoint, possibly in another process. The input must be the same
+     * as the one the checkpoint was made from, starting from the beginning; already lexed chars are skipped.
+     * Options must also be the same, including custom token hooks.
+     */
+    public static Lexer resume(InputStream in, LexerOptions options, Checkpoint checkpoint) throws IOException {
+        Lexer lexer = new Lexer(in, options);
+        checkpoint.restore(lexer);
+        long toSkip = checkpoint.addedFinalNewline ? checkpoint.offset - 1 : checkpoint.offset;
+        while (toSkip > 0) {
+            long skipped = lexer.in.skip(toSkip);
+            if (skipped <= 0)
+                throw new IOException("Input is shorter than the checkpoint's position");
+            toSkip -= skipped;
+        }
+        return lexer;
+    }
+
+    /**
+     * Snapshot of the lexer between two {@link #advance} calls. Tokens passed to the consumer so far
+     * are not part of it.
+     */
+    public Checkpoint checkpoint() {
+        return new Checkpoint(this);
+    }
+
+    /**
+     * Whether the input ended in the middle of a token, like an unclosed string or block comment.
+     */
+    boolean endedInsideToken() {
+        return state != State.INITIAL && state != State.WHITESPACE;
+    }
+
+    /**
+     * Whether the next char starts a new token, with nothing pending, not even whitespace.
+     */
+    boolean betweenTokens() {
+        return state == State.INITIAL;
+    }
+
+    /**
+     * How many chars were read so far
+     */
+    int offset() {
+        return offset;
+    }
+
+    /**
+     * Errors found so far in {@link LexerOptions.Builder#collectErrors collect errors} mode, sorted by position.
+     * A string or block comment that's still open is only reported once the input ends.
+     */
+    public List<LexError> errors() {
+        ArrayList<LexError> errors = new ArrayList<>(collectedErrors);
+        errors.sort(Comparator.comparingInt((LexError error) -> error.offset).thenComparingInt(error -> error.length));
+        return Collections.unmodifiableList(errors);
+    }
+
+    /**
+     * Time and chars spent in each phase so far, including time spent in the token consumer.
+     *
+     * @throws IllegalStateException if {@link LexerOptions.Builder#profile profiling} is off
+     */
+    public Profile profile() {
+        if (!options.profile)
+            throw new IllegalStateException("Profiling is off");
+        return new Profile(profileNanos.clone(), profileChars.clone());
+    }
+
+    public ArrayList<Token> parse() throws IOException {
+        ArrayList<Token> tokens = new ArrayList<>();
+        parse(tokens::add);
+        return tokens;
+    }
+
+    /**
+     * Passes each token to the consumer as soon as it's complete, without keeping them in memory.
+     */
+    public void parse(Consumer<? super Token> consumer) throws IOException {
+        sink = consumer;
+        if (!options.log || !LOG.isLoggable(Level.FINE)) {
+            lex();
+            return;
+        }
+        long start = System.nanoTime();
+        lex();
+        LOG.log(Level.FINE, "Lexed {0,number,#} chars in {1,number,#} us: {2,number,#} tokens, {3,number,#} errors, "
+                + "{4,number,#} recoveries",
+                new Object[] {offset, (System.nanoTime() - start) / 1000, tokenCount, errorCount, recoveryCount});
+        LOG.log(Level.FINE, "Slow paths: {0,number,#} of {1,number,#} chars char by char, {2,number,#} buffer refills, "
+                + "{3,number,#} raw strings, {4,number,#} nested comments",
+                new Object[] {offset - bulkChars, offset, refillCount, rawStringCount, nestedCommentCount});
+        LOG.log(Level.FINE, "Phases:\n{0}", new Profile(profileNanos.clone(), profileChars.clone()));
+    }
+
+    /**
+     * Passes tokens and errors to the sink as soon as they're complete, then calls {@link TokenSink#end()}.
+     */
+    public void lexInto(TokenSink tokenSink) throws IOException {
+        parse(token -> {
+            if (token instanceof LexError)
+                tokenSink.error((LexError) token);
+            else
+                tokenSink.token(token);
+        });
+        tokenSink.end();
+    }
+
+    /**
+     * Lexes a string that's already in memory into the sink, without building a token list.
+     */
+    public static void lexInto(String source, LexerOptions options, TokenSink tokenSink) {
+        try {
+            fromString(source, options).lexInto(tokenSink);
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+    }
+
+    /**
+     * Lexes at most maxChars chars of the input, so the lexer can be {@link #checkpoint() suspended} in between.
+     *
+     * @return false once the whole input is lexed
+     */
+    public boolean advance(Consumer<? super Token> consumer, int maxChars) throws IOException {
+        sink = consumer;
+        lex(maxChars);
+        return !finished;
+    }
+
+    /**
+     * Lexes until the input ends or the budget runs out, so a UI can stay responsive on huge or pathological input.
+     * Call it again, e.g. when the UI is idle, to go on from where it stopped; the lexer can also be
+     * {@link #checkpoint() suspended} in between.
+     *
+     * @param maxTokens stop after this many tokens, 0 for no limit; it can go over by one, since the same char
+     *                  can end a token and be another one
+     * @param maxDuration stop after about this long, null for no limit
+     */
+    public Batch lexBounded(int maxTokens, Duration maxDuration) throws IOException {
+        if (maxTokens < 0)
+            throw new IllegalArgumentException("maxTokens can't be negative");
+        ArrayList<Token> tokens = new ArrayList<>();
+        long deadline = maxDuration == null ? 0 : System.nanoTime() + maxDuration.toNanos();
+        tokenBudgetEnd = maxTokens == 0 ? Long.MAX_VALUE : (long) tokenCount + maxTokens;
+        try {
+            do {
+                advance(tokens::add, BOUNDED_STEP);
+            } while (!finished && tokenCount < tokenBudgetEnd
+                    && (maxDuration == null || System.nanoTime() - deadline < 0));
+        } finally {
+            tokenBudgetEnd = Long.MAX_VALUE;
+        }
+        return new Batch(tokens, finished);
+    }
+
+    private void lex() throws IOException {
+        lex(Long.MAX_VALUE);
+    }
+
+    private void lex(long maxChars) throws IOException {
+        try {
+            lexChars(maxChars);
+        } finally {
+            //time between advance() calls doesn't count
+            if (profileNanos != null)
+                profilePhase(null);
+        }
+    }
+
+    private void lexChars(long maxChars) throws IOException {
+        for (long n = 0; n < maxChars && !finished; n++) {
+            n += scanPlainRun(maxChars - n);
+            if (n >= maxChars)
+                return;
+
+            char c;
+            int read = read();
             if (read < 0) {
+                if (lastChar == '\n' || addedFinalNewline) {
+                    finishTrivia();
+                    finishCustomToken();
+                    finishUnterminated();
+                    finished = true;
+                    return;
+                }
                 //make sure to end with EOL
-                if (c != '\n')
-                    c = '\n';
-                else
-                    return tokens;
+                c = '\n';
+                addedFinalNewline = true;
             } else {
                 c = (char) read;
+                if (!options.hooks.isEmpty())
+                    peek(CustomTokenHook.LOOKAHEAD - 1); //so hooks see the same chars ahead wherever the chunk ends
+            }
+            //a lone surrogate stays one, and isn't an identifier char
+            codePoint = c;
+            if (Character.isHighSurrogate(c)) {
+                int next = peek(0);
+                if (next >= 0 && Character.isLowSurrogate((char) next))
+                    codePoint = Character.toCodePoint(c, (char) next);
             }
+            char previousChar = lastChar;
+            lastChar = c;
+            if (lineTexts != null && c != '\n')
+                appendLineText(c);
 
 //            System.err.printf("%d:%d '%s' %s %s %d,%d %d(%s)\n", line, column, c, state,
 //                    stringEscapeState, rawStringHashCount, rawStringEndHashCount,
 //                    nestedCommentDepth, outerCommentState);
 
-            switch (state) {
-                case INITIAL -> initialState(c);
-                case ID_OR_UNDERSCORE -> idOrUnderscore(c);
-                case ID_OR_KEYWORD_OR_SUFFIX -> idOrKeywordOrSuffix(c);
-                case MAYBE_RAW_STRING -> maybeRawString(c);
-                case MAYBE_BYTE_OR_BYTE_STRING -> maybeByteOrByteString(c);
-                case CHAR_LITERAL_OR_LIFETIME_OR_LABEL -> charLiteralOrLifetimeOrLabel(c);
-                case LIFETIME_OR_LABEL -> lifetimeOrLabel(c);
-                case STRING_LITERAL, CHAR_LITERAL_ESCAPED -> stringOrCharOrByteLiteral(c, false);
-                case BYTE_LITERAL, BYTE_STRING_LITERAL -> stringOrCharOrByteLiteral(c, true);
-                case CHAR_LITERAL_END -> charLiteralEnd(c);
-                case BYTE_LITERAL_END -> byteLiteralEnd(c);
-                case RAW_STRING_LITERAL_START -> rawStringLiteralStart(c);
-                case RAW_STRING_LITERAL -> rawStringLiteral(c);
-                case RAW_STRING_LITERAL_MAYBE_END -> rawStringLiteralMaybeEnd(c);
-                case NUMBER_LITERAL -> numberLiteral(c);
-                case NUMBER_LITERAL_START_ZERO -> numberLiteralStartZero(c);
-                case INT_LITERAL_HEX -> intLiteralHex(c);
-                case INT_LITERAL_OCT -> intLiteralOct(c);
-                case INT_LITERAL_BIN -> intLiteralBin(c);
-                case INT_LITERAL_HEX_NO_DIGITS -> intLiteralHexNoDigits(c);
-                case INT_LITERAL_OCT_NO_DIGITS -> intLiteralOctNoDigits(c);
-                case INT_LITERAL_BIN_NO_DIGITS -> intLiteralBinNoDigits(c);
-                case FLOAT_LITERAL_DOT -> floatLiteralDot(c);
-                case FLOAT_LITERAL_EXPONENT -> floatLiteralExponent(c);
-                case FLOAT_LITERAL_EXPONENT_START -> floatLiteralExponentStart(c);
-                case FLOAT_LITERAL_EXPONENT_NO_DIGITS -> floatLiteralExponentNoDigits(c);
-                case SLASH -> slash(c);
-                case COMMENT_BLOCK -> commentBlock(c, TokenType.COMMENT);
-                case COMMENT_BLOCK_START -> commentBlockStart(c);
-                case COMMENT_BLOCK_MAYBE_OUTER_DOC_START -> commentBlockMaybeOuterDocStart(c);
-                case COMMENT_BLOCK_INNER_DOC -> commentBlock(c, TokenType.COMMENT_INNER_DOC);
-                case COMMENT_BLOCK_OUTER_DOC -> commentBlock(c, TokenType.COMMENT_OUTER_DOC);
-                case COMMENT_LINE -> commentLine(c, TokenType.COMMENT);
-                case COMMENT_LINE_START -> commentLineStart(c);
-                case COMMENT_LINE_MAYBE_OUTER_DOC_START -> commentLineMaybeOuterDocStart(c);
-                case COMMENT_LINE_INNER_DOC -> commentLine(c, TokenType.COMMENT_INNER_DOC);
-                case COMMENT_LINE_OUTER_DOC -> commentLine(c, TokenType.COMMENT_OUTER_DOC);
-                case PLUS -> plus(c);
-                case MINUS -> minus(c);
-                case STAR -> star(c);
-                case PERCENT -> percent(c);
-                case CARET -> caret(c);
-                case NOT -> not(c);
-                case AND -> and(c);
-                case OR -> or(c);
-                case LT -> lessThan(c);
-                case GT -> greaterThan(c);
-                case SHL -> shiftLeft(c);
-                case SHR -> shiftRight(c);
-                case EQ -> equals(c);
-                case DOT -> dot(c);
-                case DOT_DOT -> dotDot(c);
-                case COLON -> colon(c);
+            if (startsFile && offset == 0 && c == '\uFEFF') {
+                warn(LexWarning.Kind.BOM, 0, 0, 0, 1, "Byte order mark at the start of the file");
+            } else if (c == '#' && startsFile && (offset == 0 || (offset == 1 && previousChar == '\uFEFF'))
+                    && isShebang()) {
+                startBufferAndSet(c, State.SHEBANG);
+            } else {
+                State before = state;
+                switch (state) {
+                    case INITIAL -> initialState(c);
+                    case ID_OR_UNDERSCORE -> idOrUnderscore(c);
+                    case ID_OR_KEYWORD_OR_SUFFIX -> idOrKeywordOrSuffix(c);
+                    case MAYBE_RAW_STRING -> maybeRawString(c);
+                    case MAYBE_BYTE_OR_BYTE_STRING -> maybeByteOrByteString(c);
+                    case CHAR_LITERAL_OR_LIFETIME_OR_LABEL -> charLiteralOrLifetimeOrLabel(c);
+                    case LIFETIME_OR_LABEL -> lifetimeOrLabel(c);
+                    case RAW_IDENTIFIER -> rawIdentifier(c);
+                    case RAW_LIFETIME_START -> rawLifetimeStart(c);
+                    case RAW_LIFETIME_OR_LABEL -> rawLifetimeOrLabel(c);
+                    case STRING_LITERAL, CHAR_LITERAL_ESCAPED -> stringOrCharOrByteLiteral(c, false);
+                    case BYTE_LITERAL, BYTE_STRING_LITERAL -> stringOrCharOrByteLiteral(c, true);
+                    case CHAR_LITERAL_END -> charLiteralEnd(c);
+                    case BYTE_LITERAL_END -> byteLiteralEnd(c);
+                    case RAW_STRING_LITERAL_START -> rawStringLiteralStart(c);
+                    case RAW_STRING_LITERAL -> rawStringLiteral(c);
+                    case RAW_STRING_LITERAL_MAYBE_END -> rawStringLiteralMaybeEnd(c);
+                    case NUMBER_LITERAL -> numberLiteral(c);
+                    case LITERAL_SUFFIX -> literalSuffix(c);
+                    case QUOTED_LITERAL_END -> quotedLiteralEnd(c);
+                    case NUMBER_LITERAL_START_ZERO -> numberLiteralStartZero(c);
+                    case INT_LITERAL_HEX -> intLiteralHex(c);
+                    case INT_LITERAL_OCT -> intLiteralOct(c);
+                    case INT_LITERAL_BIN -> intLiteralBin(c);
+                    case INT_LITERAL_HEX_NO_DIGITS -> intLiteralHexNoDigits(c);
+                    case INT_LITERAL_OCT_NO_DIGITS -> intLiteralOctNoDigits(c);
+                    case INT_LITERAL_BIN_NO_DIGITS -> intLiteralBinNoDigits(c);
+                    case INT_LITERAL_INVALID_DIGIT -> intLiteralInvalidDigit(c);
+                    case FLOAT_LITERAL_DOT -> floatLiteralDot(c);
+                    case FLOAT_LITERAL_EXPONENT -> floatLiteralExponent(c);
+                    case FLOAT_LITERAL_EXPONENT_START -> floatLiteralExponentStart(c);
+                    case FLOAT_LITERAL_EXPONENT_NO_DIGITS -> floatLiteralExponentNoDigits(c);
+                    case SLASH -> slash(c);
+                    case COMMENT_BLOCK -> commentBlock(c, TokenType.COMMENT);
+                    case COMMENT_BLOCK_START -> commentBlockStart(c);
+                    case COMMENT_BLOCK_MAYBE_OUTER_DOC_START -> commentBlockMaybeOuterDocStart(c);
+                    case COMMENT_BLOCK_INNER_DOC -> commentBlock(c, TokenType.COMMENT_INNER_DOC);
+                    case COMMENT_BLOCK_OUTER_DOC -> commentBlock(c, TokenType.COMMENT_OUTER_DOC);
+                    case COMMENT_LINE -> commentLine(c, TokenType.COMMENT);
+                    case COMMENT_LINE_START -> commentLineStart(c);
+                    case COMMENT_LINE_MAYBE_OUTER_DOC_START -> commentLineMaybeOuterDocStart(c);
+                    case COMMENT_LINE_INNER_DOC -> commentLine(c, TokenType.COMMENT_INNER_DOC);
+                    case COMMENT_LINE_OUTER_DOC -> commentLine(c, TokenType.COMMENT_OUTER_DOC);
+                    case PLUS -> plus(c);
+                    case MINUS -> minus(c);
+                    case STAR -> star(c);
+                    case PERCENT -> percent(c);
+                    case CARET -> caret(c);
+                    case NOT -> not(c);
+                    case AND -> and(c);
+                    case OR -> or(c);
+                    case LT -> lessThan(c);
+                    case GT -> greaterThan(c);
+                    case SHL -> shiftLeft(c);
+                    case SHR -> shiftRight(c);
+                    case EQ -> equals(c);
+                    case DOT -> dot(c);
+                    case DOT_DOT -> dotDot(c);
+                    case COLON -> colon(c);
+                    case CUSTOM_TOKEN -> customToken(c);
+                    case SHEBANG -> shebang(c);
+                    case WHITESPACE -> whitespace(c);
+                }
+                if (profileNanos != null)
+                    profile(before, c, 1);
+            }
+
+            if (errorCount > 0 && !options.recovery && !options.collectErrors) {
+                finished = true;
+                return;
+            }
+            if (options.maxTokens > 0 && tokenCount >= options.maxTokens) {
+                emit(new LexError(line, column, offset, 0, LexError.Kind.TOKEN_LIMIT,
+                        "Token limit reached: " + options.maxTokens));
+                finished = true;
+                return;
             }
 
+            if (lineTexts != null && c == '\n')
+                nextLineText();
             if (c == '\n') {
                 line++;
                 column = 0;
             } else {
                 column++;
             }
+            offset++;
+            if (tokenCount >= tokenBudgetEnd)
+                return;
         }
     }
 
-    private boolean isIdentifierChar(char c) {
-        return (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || (c >= '0' && c <= '9') || c == '_';
+    private int read() throws IOException {
+        int c = peek(0);
+        if (c >= 0)
+            chunkPosition++;
+        return c;
+    }
+
+    /**
+     * @return the char that many chars after the next one, without consuming anything, or -1 past the end
+     */
+    private int peek(int ahead) throws IOException {
+        while (chunkPosition + ahead >= chunkLength) {
+            if (chunkPosition > 0) {
+                System.arraycopy(chunk, chunkPosition, chunk, 0, chunkLength - chunkPosition);
+                chunkLength -= chunkPosition;
+                chunkPosition = 0;
+            }
+            if (chunkLength == chunk.length)
+                chunk = Arrays.copyOf(chunk, chunk.length * 2);
+            int read = in.read(chunk, chunkLength, chunk.length - chunkLength);
+            if (read < 0)
+                return -1;
+            refillCount++;
+            chunkLength += read;
+        }
+        return chunk[chunkPosition + ahead];
+    }
+
+    /**
+     * Most chars inside strings, comments, identifiers and whitespace don't change the state. Instead of going
+     * through the state machine for each of them, this looks for the next one that does and appends everything
+     * before it to the buffer. Identifiers and whitespace are only scanned this way while they're ASCII,
+     * anything else goes the slow way.
+     *
+     * @return how many chars were consumed
+     */
+    private int scanPlainRun(long maxChars) throws IOException {
+        switch (state) {
+            case STRING_LITERAL, BYTE_STRING_LITERAL -> {
+                if (stringEscapeState != State.StringEscape.NONE)
+                    return 0;
+            }
+            case INITIAL -> {
+                if (options.emitTrivia)
+                    return 0;
+            }
+            case RAW_STRING_LITERAL, COMMENT_BLOCK, COMMENT_BLOCK_INNER_DOC, COMMENT_BLOCK_OUTER_DOC, COMMENT_LINE,
+                    COMMENT_LINE_INNER_DOC, COMMENT_LINE_OUTER_DOC, ID_OR_KEYWORD_OR_SUFFIX, LIFETIME_OR_LABEL,
+                    RAW_IDENTIFIER, RAW_LIFETIME_OR_LABEL, WHITESPACE -> {
+            }
+            default -> {
+                return 0;
+            }
+        }
+        if (chunkPosition == chunkLength && peek(0) < 0)
+            return 0;
+
+        int start = chunkPosition;
+        int limit = (int) Math.min(chunkLength, start + maxChars);
+        int end = switch (state) {
+            case STRING_LITERAL -> indexOfAny(start, limit, '"', '\\');
+            case BYTE_STRING_LITERAL -> {
+                if (!options.validateLiterals)
+                    yield indexOfAny(start, limit, '"', '\\');
+                int i = start;
+                while (i < limit && chunk[i] != '"' && chunk[i] != '\\' && chunk[i] < 128)
+                    i++;
+                yield i;
+            }
+            case RAW_STRING_LITERAL -> indexOfAny(start, limit, '"', '"');
+            case COMMENT_LINE, COMMENT_LINE_INNER_DOC, COMMENT_LINE_OUTER_DOC -> indexOfAny(start, limit, '\n', '\n');
+            case ID_OR_KEYWORD_OR_SUFFIX, LIFETIME_OR_LABEL, RAW_IDENTIFIER, RAW_LIFETIME_OR_LABEL ->
+                    indexOfNotAscii(start, limit, ASCII_IDENTIFIER);
+            case INITIAL, WHITESPACE -> indexOfNotAscii(start, limit, ASCII_WHITESPACE);
+            default -> indexOfAny(start, limit, '*', '/');
+        };
+        if (end == start)
+            return 0;
+
+        if (state != State.INITIAL)
+            buffer.append(chunk, start, end - start);
+        for (int i = start; i < end; i++) {
+            if (lineTexts != null) {
+                if (chunk[i] == '\n')
+                    nextLineText();
+                else
+                    appendLineText(chunk[i]);
+            }
+            if (chunk[i] == '\n') {
+                line++;
+                column = 0;
+            } else {
+                column++;
+            }
+        }
+        offset += end - start;
+        bulkChars += end - start;
+        lastChar = chunk[end - 1];
+        chunkPosition = end;
+        if (profileNanos != null)
+            profile(state, lastChar, end - start);
+        return end - start;
+    }
+
+    /**
+     * Counts chars for the phase of the state they were read in. A char that starts a token counts for the token's
+     * phase, and one that ends a token counts for that token.
+     */
+    private void profile(State before, char c, int chars) {
+        State phaseState = before == State.INITIAL ? state : before;
+        Profile.Phase phase = switch (phaseState) {
+            case INITIAL -> isWhitespace(c) ? Profile.Phase.WHITESPACE : Profile.Phase.OTHER;
+            case ID_OR_KEYWORD_OR_SUFFIX, ID_OR_UNDERSCORE, MAYBE_RAW_STRING, MAYBE_BYTE_OR_BYTE_STRING,
+                    LIFETIME_OR_LABEL, RAW_IDENTIFIER, RAW_LIFETIME_START, RAW_LIFETIME_OR_LABEL ->
+                    Profile.Phase.IDENTIFIERS;
+            case CHAR_LITERAL_OR_LIFETIME_OR_LABEL, STRING_LITERAL, CHAR_LITERAL_ESCAPED, CHAR_LITERAL_END,
+                    BYTE_LITERAL, BYTE_LITERAL_END, BYTE_STRING_LITERAL, RAW_STRING_LITERAL, RAW_STRING_LITERAL_START,
+                    RAW_STRING_LITERAL_MAYBE_END, QUOTED_LITERAL_END -> Profile.Phase.STRINGS;
+            case NUMBER_LITERAL, LITERAL_SUFFIX, NUMBER_LITERAL_START_ZERO, INT_LITERAL_HEX, INT_LITERAL_OCT,
+                    INT_LITERAL_BIN, INT_LITERAL_HEX_NO_DIGITS, INT_LITERAL_OCT_NO_DIGITS, INT_LITERAL_BIN_NO_DIGITS,
+                    INT_LITERAL_INVALID_DIGIT, FLOAT_LITERAL_DOT, FLOAT_LITERAL_EXPONENT, FLOAT_LITERAL_EXPONENT_START,
+                    FLOAT_LITERAL_EXPONENT_NO_DIGITS -> Profile.Phase.NUMBERS;
+            case COMMENT_BLOCK, COMMENT_BLOCK_START, COMMENT_BLOCK_MAYBE_OUTER_DOC_START, COMMENT_BLOCK_INNER_DOC,
+                    COMMENT_BLOCK_OUTER_DOC, COMMENT_LINE, COMMENT_LINE_START, COMMENT_LINE_MAYBE_OUTER_DOC_START,
+                    COMMENT_LINE_INNER_DOC, COMMENT_LINE_OUTER_DOC, SHEBANG -> Profile.Phase.COMMENTS;
+            case WHITESPACE -> Profile.Phase.WHITESPACE;
+            default -> Profile.Phase.OTHER;
+        };
+        profileChars[phase.ordinal()] += chars;
+        if (phase != currentPhase)
+            profilePhase(phase);
+    }
+
+    private void profilePhase(Profile.Phase phase) {
+        long now = System.nanoTime();
+        if (currentPhase != null)
+            profileNanos[currentPhase.ordinal()] += now - phaseStart;
+        currentPhase = phase;
+        phaseStart = now;
+    }
+
+    private int indexOfAny(int from, int to, char first, char second) {
+        for (int i = from; i < to; i++) {
+            char c = chunk[i];
+            if (c == first || c == second)
+                return i;
+        }
+        return to;
+    }
+
+    private int indexOfNotAscii(int from, int to, byte charClass) {
+        for (int i = from; i < to; i++) {
+            char c = chunk[i];
+            if (c >= 128 || (ASCII_CLASSES[c] & charClass) == 0)
+                return i;
+        }
+        return to;
+    }
+
+    /**
+     * Pattern_White_Space, which is what rustc skips: ASCII whitespace including vertical tab and form feed,
+     * next line (U+0085), the left-to-right and right-to-left marks, and the line and paragraph separators.
+     * Unlike {@link Character#isWhitespace}, other Unicode spaces like U+3000 aren't whitespace.
+     */
+    public static boolean isWhitespace(char c) {
+        return (c >= '\t' && c <= '\r') || c == ' ' || c == '\u0085' || c == '\u200E' || c == '\u200F'
+                || c == '\u2028' || c == '\u2029';
+    }
+
+    /**
+     * ASCII letters, the underscore, and non-ASCII chars that can start identifiers in the runtime's Unicode
+     * version. Whether "_" alone is an identifier is up to the caller, the lexer makes it {@link TokenType#UNDERSCORE}.
+     * Surrogates are never identifier chars by themselves, see {@link #isIdentifierStart(int)}.
+     */
+    public static boolean isIdentifierStart(char c) {
+        return isIdentifierStart((int) c, LexerOptions.UnicodeVersion.RUNTIME);
+    }
+
+    public static boolean isIdentifierStart(char c, LexerOptions.UnicodeVersion unicodeVersion) {
+        return isIdentifierStart((int) c, unicodeVersion);
+    }
+
+    /**
+     * Same as {@link #isIdentifierStart(char)}, for any code point, including ones outside the Basic Multilingual
+     * Plane like U+1D465 (mathematical italic x).
+     */
+    public static boolean isIdentifierStart(int codePoint) {
+        return isIdentifierStart(codePoint, LexerOptions.UnicodeVersion.RUNTIME);
+    }
+
+    public static boolean isIdentifierStart(int codePoint, LexerOptions.UnicodeVersion unicodeVersion) {
+        return (codePoint >= 'a' && codePoint <= 'z') || (codePoint >= 'A' && codePoint <= 'Z') || codePoint == '_'
+                || (codePoint >= 0x80 && unicodeVersion.isIdentifierStart(codePoint));
+    }
+
+    /**
+     * Chars after the first one in an identifier: {@link #isIdentifierStart} chars, digits, and non-ASCII chars
+     * like combining marks.
+     */
+    public static boolean isIdentifierChar(char c) {
+        return isIdentifierChar((int) c, LexerOptions.UnicodeVersion.RUNTIME);
+    }
+
+    public static boolean isIdentifierChar(char c, LexerOptions.UnicodeVersion unicodeVersion) {
+        return isIdentifierChar((int) c, unicodeVersion);
+    }
+
+    /**
+     * Same as {@link #isIdentifierChar(char)}, for any code point.
+     */
+    public static boolean isIdentifierChar(int codePoint) {
+        return isIdentifierChar(codePoint, LexerOptions.UnicodeVersion.RUNTIME);
+    }
+
+    public static boolean isIdentifierChar(int codePoint, LexerOptions.UnicodeVersion unicodeVersion) {
+        return (codePoint >= 'a' && codePoint <= 'z') || (codePoint >= 'A' && codePoint <= 'Z')
+                || (codePoint >= '0' && codePoint <= '9') || codePoint == '_'
+                || (codePoint >= 0x80 && unicodeVersion.isIdentifierPart(codePoint));
+    }
+
+    /**
+     * Whether the char just read starts an identifier. A high surrogate is checked together with the low one after
+     * it, which is then accepted by {@link #identifierChar}.
+     */
+    private boolean identifierStart(char c) {
+        return isIdentifierStart(Character.isHighSurrogate(c) ? codePoint : c, options.unicodeVersion);
+    }
+
+    /**
+     * Whether the char just read continues the identifier in the buffer, by code point like {@link #identifierStart}
+     */
+    private boolean identifierChar(char c) {
+        //the high surrogate before it was only taken if the pair is an identifier char
+        if (Character.isLowSurrogate(c))
+            return buffer.length() > 0 && Character.isHighSurrogate(buffer.charAt(buffer.length() - 1));
+        return isIdentifierChar(Character.isHighSurrogate(c) ? codePoint : c, options.unicodeVersion);
+    }
+
+    /**
+     * Chars that start an operator or punctuation token, like + for +, += and +=. The apostrophe, which starts
+     * lifetimes and char literals, isn't one of them.
+     */
+    public static boolean isPunctuationStart(char c) {
+        return PUNCTUATION_START.indexOf(c) >= 0;
+    }
+
+    private boolean isSuffixStart(char c) {
+        return (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z');
     }
 
     private boolean isHexDigit(char c) {
@@ -185,20 +932,322 @@ public final class Lexer {
         this.state = state;
         bufferStartLine = line;
         bufferStartColumn = column;
+        bufferStartOffset = offset;
+    }
+
+    private void emit(Token token) {
+        //warnings are checked with columns in chars
+        Token reported = lineTexts == null ? token
+                : token.withPosition(token.line, displayColumn(token.line, token.column), token.offset);
+        if (options.collectErrors && token instanceof LexError) {
+            collectedErrors.add((LexError) reported);
+            return;
+        }
+        if (!options.injections.isEmpty())
+            reported = inject(reported);
+        tokenCount++;
+        sink.accept(withoutText(reported));
+        if (warningsEnabled)
+            checkWarnings(token);
+    }
+
+    /**
+     * Adds a char to the text of the current line, before the lexer reads it, so tokens ending at the char can
+     * find the columns of the ones that started before it on the line.
+     */
+    private void appendLineText(char c) {
+        if (lineTexts.isEmpty()) {
+            //resumed from a checkpoint that had no line texts, the part before it counts in chars
+            firstLineText = line;
+            lineTexts.add(new StringBuilder(" ".repeat(column)));
+        }
+        lineTexts.get(lineTexts.size() - 1).append(c);
+    }
+
+    /**
+     * Starts the text of the next line once a line break is read, forgetting the lines before the one
+     * the unfinished token started on.
+     */
+    private void nextLineText() {
+        if (lineTexts.isEmpty())
+            return;
+        lineTexts.add(new StringBuilder());
+        int keepFrom = state == State.INITIAL ? line + 1 : bufferStartLine;
+        while (firstLineText < keepFrom) {
+            lineTexts.remove(0);
+            firstLineText++;
+        }
+    }
+
+    /**
+     * @param column in chars
+     * @return the column in {@link LexerOptions.Builder#columns the options' mode}
+     */
+    private int displayColumn(int line, int column) {
+        if (lineTexts == null)
+            return column;
+        int index = line - firstLineText;
+        CharSequence text = index >= 0 && index < lineTexts.size() ? lineTexts.get(index) : "";
+        int displayColumn;
+        if (line == cachedLine && column >= cachedCharColumn)
+            displayColumn = cachedColumn + DisplayColumns.width(text, cachedCharColumn, column, options.columnMode);
+        else
+            displayColumn = DisplayColumns.width(text, 0, column, options.columnMode);
+        //the char being read can be half of a surrogate pair, and columns past it are guesses
+        if (column < text.length()) {
+            cachedLine = line;
+            cachedCharColumn = column;
+            cachedColumn = displayColumn;
+        }
+        return displayColumn;
+    }
+
+    private void warn(LexWarning.Kind kind, int line, int column, int offset, int length, String message) {
+        warn(kind, line, column, offset, length, message, null);
+    }
+
+    private void warn(LexWarning.Kind kind, int line, int column, int offset, int length, String message,
+                      String suggestion) {
+        switch (options.severity(kind)) {
+            case ALLOW -> {
+            }
+            case WARN -> {
+                if (options.warningSink != null)
+                    options.warningSink.accept(new LexWarning(line, displayColumn(line, column), offset, length, kind,
+                            message, suggestion));
+            }
+            case DENY -> {
+                errorCount++;
+                emit(new LexError(line, column, offset, length, LexError.Kind.DENIED_WARNING, message));
+            }
+        }
+    }
+
+    private void checkWarnings(Token token) {
+        switch (token.type) {
+            case IDENTIFIER -> {
+                checkConfusable(token);
+                checkNormalized(token);
+                checkFutureKeyword(token);
+            }
+            case STRING_LITERAL, CHAR_LITERAL, BYTE_STRING_LITERAL, BYTE_LITERAL -> checkEscapes(token);
+            case INT_LITERAL_DEC, INT_LITERAL_HEX, INT_LITERAL_OCTAL, INT_LITERAL_BIN, FLOAT_LITERAL ->
+                    checkUnderscores(token);
+        }
+    }
+
+    /**
+     * Warns about underscores that don't sit between two digits: right after the radix prefix or the exponent mark,
+     * at the end of the integer, fraction or exponent digits, and repeated ones. One right before a suffix, as in
+     * 1_u8, is fine.
+     */
+    private void checkUnderscores(Token token) {
+        String text = token.value;
+        int prefix = token.type == TokenType.INT_LITERAL_DEC || token.type == TokenType.FLOAT_LITERAL ? 0 : 2;
+        boolean hex = token.type == TokenType.INT_LITERAL_HEX;
+        int digitsStart = prefix;
+        int i = prefix;
+        while (i < text.length()) {
+            char c = text.charAt(i);
+            if (c != '_') {
+                boolean exponent = !hex && (c == 'e' || c == 'E');
+                if (!isDigit(c, hex) && c != '.' && !exponent)
+                    break;
+                i++;
+                if (exponent && i < text.length() && (text.charAt(i) == '+' || text.charAt(i) == '-'))
+                    i++;
+                if (!isDigit(c, hex))
+                    digitsStart = i;
+                continue;
+            }
+            int runStart = i;
+            while (i < text.length() && text.charAt(i) == '_')
+                i++;
+            char next = i < text.length() ? text.charAt(i) : ' ';
+            boolean digitsEnd = !isDigit(next, hex);
+            boolean suffix = digitsEnd && isSuffixStart(next) && (hex || (next != 'e' && next != 'E'));
+            String message;
+            String suggestion = "";
+            if (runStart == digitsStart) {
+                //no digits at all is already an error
+                if (digitsEnd)
+                    continue;
+                message = runStart == prefix ? "Underscore right after " + text.substring(0, 2)
+                        : "Underscore right after the exponent mark";
+            } else if (digitsEnd && !suffix) {
+                message = "Underscore after the last digit";
+            } else if (i - runStart > 1) {
+                message = "Repeated underscore";
+                suggestion = "_";
+            } else {
+                continue;
+            }
+            warn(LexWarning.Kind.MISPLACED_UNDERSCORE, token.line, token.column + runStart, token.offset + runStart,
+                    i - runStart, message, suggestion);
+        }
+    }
+
+    private static boolean isDigit(char c, boolean hex) {
+        return (c >= '0' && c <= '9') || (hex && ((c >= 'a' && c <= 'f') || (c >= 'A' && c <= 'F')));
+    }
+
+    private void checkConfusable(Token token) {
+        String name = token.value;
+        EnumSet<Character.UnicodeScript> scripts = EnumSet.noneOf(Character.UnicodeScript.class);
+        for (int i = 0; i < name.length(); i += Character.charCount(name.codePointAt(i))) {
+            Character.UnicodeScript script = Character.UnicodeScript.of(name.codePointAt(i));
+            if (script == Character.UnicodeScript.LATIN || script == Character.UnicodeScript.CYRILLIC
+                    || script == Character.UnicodeScript.GREEK)
+                scripts.add(script);
+        }
+        if (scripts.size() > 1)
+            warn(LexWarning.Kind.CONFUSABLE_IDENTIFIER, token.line, token.column, token.offset, token.length,
+                    "Identifier mixes letters from different scripts " + scripts + ": " + name);
+    }
+
+    private void checkNormalized(Token token) {
+        if (!Normalizer.isNormalized(token.value, Normalizer.Form.NFC)) {
+            String normalized = Normalizer.normalize(token.value, Normalizer.Form.NFC);
+            warn(LexWarning.Kind.NON_NFC_IDENTIFIER, token.line, token.column, token.offset, token.length,
+                    "Identifier is not in NFC, it's the same identifier as " + normalized, normalized);
+        }
+    }
+
+    private void checkFutureKeyword(Token token) {
+        TokenType keywordType = keywords.get(token.value);
+        if (keywordType == null || options.reserves(keywordType))
+            return;
+        for (LexerOptions.Edition edition : LexerOptions.Edition.values()) {
+            if (edition.reserves(keywordType)) {
+                warn(LexWarning.Kind.FUTURE_KEYWORD, token.line, token.column, token.offset, token.length,
+                        token.value + " is a keyword from Rust " + edition.name().substring("RUST_".length())
+                                + " on, use r#" + token.value + " to keep it an identifier", "r#" + token.value);
+                return;
+            }
+        }
+    }
+
+    private void checkEscapes(Token token) {
+        String text = token.text();
+        char quote = token.type == TokenType.CHAR_LITERAL || token.type == TokenType.BYTE_LITERAL ? '\'' : '"';
+        for (Literal.EscapeSequence escape : new Literal(token).escapes()) {
+            String message = null;
+            LexWarning.Kind kind = LexWarning.Kind.UNUSUAL_ESCAPE;
+            String suggestion = null;
+            if (escape.kind == Literal.EscapeSequence.Kind.SIMPLE && escape.value == 0
+                    && escape.end < text.length() && Character.isDigit(text.charAt(escape.end))) {
+                message = "\\0 followed by a digit is a null char and the digit, not an octal escape";
+            } else if (escape.kind == Literal.EscapeSequence.Kind.UNICODE
+                    && text.substring(escape.start, escape.end).indexOf('_') >= 0) {
+                message = "Underscores in a Unicode escape";
+            } else if (escape.kind == Literal.EscapeSequence.Kind.SIMPLE
+                    && (escape.value == '\'' || escape.value == '"') && escape.value != quote) {
+                kind = LexWarning.Kind.UNNECESSARY_ESCAPE;
+                suggestion = String.valueOf((char) escape.value);
+                message = "No need to escape " + suggestion + " here";
+            } else if ((escape.kind == Literal.EscapeSequence.Kind.HEX
+                    || escape.kind == Literal.EscapeSequence.Kind.UNICODE)
+                    && escape.value >= ' ' && escape.value < 0x7F && escape.value != '\\' && escape.value != quote) {
+                kind = LexWarning.Kind.ESCAPED_PRINTABLE;
+                suggestion = String.valueOf((char) escape.value);
+                message = text.substring(escape.start, escape.end) + " can be written as " + suggestion;
+            } else if (escape.kind == Literal.EscapeSequence.Kind.LINE_CONTINUATION && escape.end < text.length()
+                    && (Character.isWhitespace(text.charAt(escape.end))
+                    || Character.isSpaceChar(text.charAt(escape.end)))) {
+                warnInToken(token, LexWarning.Kind.UNUSUAL_ESCAPE, escape.end, escape.end + 1,
+                        "Only ASCII whitespace is skipped after a line continuation, this char stays in the string",
+                        null);
+            }
+
+            if (message != null)
+                warnInToken(token, kind, escape.start, escape.end, message, suggestion);
+        }
+        if (token.type == TokenType.STRING_LITERAL || token.type == TokenType.BYTE_STRING_LITERAL)
+            checkBackslashSpace(token);
+    }
+
+    /**
+     * Warns about spaces or tabs between a backslash and the end of the line: the backslash escapes the space
+     * rather than the line break, so it isn't a line continuation.
+     */
+    private void checkBackslashSpace(Token token) {
+        String text = token.text();
+        for (int i = text.indexOf('\\'); i >= 0 && i + 1 < text.length(); i = text.indexOf('\\', i + 2)) {
+            int end = i + 1;
+            while (end < text.length() && (text.charAt(end) == ' ' || text.charAt(end) == '\t'))
+                end++;
+            if (end == i + 1 || end == text.length())
+                continue;
+            if (text.charAt(end) == '\n' || (text.charAt(end) == '\r' && text.startsWith("\n", end + 1)))
+                warnInToken(token, LexWarning.Kind.SPACE_AFTER_BACKSLASH, i + 1, end,
+                        "Whitespace after a backslash at the end of the line, it's not a line continuation", "");
+        }
+    }
+
+    /**
+     * @param start index in the token text
+     */
+    private void warnInToken(Token token, LexWarning.Kind kind, int start, int end, String message,
+                             String suggestion) {
+        String text = token.text();
+        int line = token.line;
+        int column = token.column;
+        for (int i = 0; i < start; i++) {
+            if (text.charAt(i) == '\n') {
+                line++;
+                column = 0;
+            } else {
+                column++;
+            }
+        }
+        warn(kind, line, column, token.offset + start, end - start, message, suggestion);
+    }
+
+    /**
+     * @return the token, or an {@link InjectedLiteral} if an injection hook matches it
+     */
+    private Token inject(Token token) {
+        if (token.type == TokenType.WHITESPACE || token.type == TokenType.ERROR)
+            return token;
+        Token injected = token;
+        switch (token.type) {
+            case STRING_LITERAL, RAW_STRING_LITERAL, BYTE_STRING_LITERAL, RAW_BYTE_STRING_LITERAL -> {
+                List<Token> preceding = List.copyOf(injectionContext);
+                for (InjectionHook hook : options.injections) {
+                    if (hook.matches(token, preceding)) {
+                        injected = InjectedLiteral.inject(token, hook);
+                        break;
+                    }
+                }
+            }
+            default -> {
+            }
+        }
+        if (injectionContext.size() == INJECTION_CONTEXT)
+            injectionContext.removeFirst();
+        injectionContext.addLast(token);
+        return injected;
+    }
+
+    private Token withoutText(Token token) {
+        if (options.storeText || token.value == null || token.type == TokenType.ERROR || token instanceof CustomToken
+                || token instanceof InjectedLiteral)
+            return token;
+        return new Token(token.line, token.column, token.offset, token.length, token.type, null, token.flags());
     }
 
     private void emptyFromCurrentPosAndReset(TokenType type) {
-        tokens.add(new Token(line, column, type));
+        emit(new Token(line, column, offset, 1, type));
         state = State.INITIAL;
     }
 
     private void addEmptyAndReset(TokenType type) {
-        tokens.add(new Token(bufferStartLine, bufferStartColumn, type));
+        emit(new Token(bufferStartLine, bufferStartColumn, bufferStartOffset, type.text.length(), type));
         state = State.INITIAL;
     }
 
     private void addAndReset(TokenType type, String data) {
-        tokens.add(new Token(bufferStartLine, bufferStartColumn, type, data));
+        emit(new Token(bufferStartLine, bufferStartColumn, bufferStartOffset, data.length(), type, data));
         state = State.INITIAL;
     }
 
@@ -206,15 +1255,40 @@ public final class Lexer {
         addAndReset(type, buffer.toString());
     }
 
-    private void errorAndReset(String errorMessage) {
-        addAndReset(TokenType.ERROR, errorMessage);
+    private void errorAndReset(LexError.Kind kind, String errorMessage) {
+        errorAtBufferStart(kind, errorMessage);
+        recoveryCount++;
+        state = State.INITIAL;
     }
 
-    private void errorAtBufferStart(String errorMessage) {
-        tokens.add(new Token(bufferStartLine, bufferStartColumn, TokenType.ERROR, errorMessage));
+    private void errorAtBufferStart(LexError.Kind kind, String errorMessage) {
+        errorCount++;
+        emit(new LexError(bufferStartLine, bufferStartColumn, bufferStartOffset, buffer.length(),
+                kind, errorMessage));
+    }
+
+    /**
+     * Reports an error covering the escape sequence at the end of the buffer, from its backslash.
+     */
+    private void errorAtEscape(LexError.Kind kind, String errorMessage) {
+        int start = buffer.lastIndexOf("\\");
+        int line = bufferStartLine;
+        int column = bufferStartColumn;
+        for (int i = 0; i < start; i++) {
+            if (buffer.charAt(i) == '\n') {
+                line++;
+                column = 0;
+            } else {
+                column++;
+            }
+        }
+        errorCount++;
+        emit(new LexError(line, column, bufferStartOffset + start, buffer.length() - start, kind, errorMessage));
     }
 
     private void initialState(char c) {
+        if (!options.hooks.isEmpty() && startCustomTokenAhead(c))
+            return;
         if (c == '_') {
             startBufferAndSet(c, State.ID_OR_UNDERSCORE);
         } else if (c == 'r') {
@@ -229,7 +1303,7 @@ public final class Lexer {
             startBufferAndSet(c, State.NUMBER_LITERAL_START_ZERO);
         } else if (c >= '1' && c <= '9') {
             startBufferAndSet(c, State.NUMBER_LITERAL);
-        } else if (isIdentifierChar(c)) {
+        } else if (identifierStart(c)) {
             startBufferAndSet(c, State.ID_OR_KEYWORD_OR_SUFFIX);
         } else if (c == '/') {
             startBufferAndSet(c, State.SLASH);
@@ -283,11 +1357,163 @@ public final class Lexer {
             emptyFromCurrentPosAndReset(TokenType.CURLY_L);
         } else if (c == '}') {
             emptyFromCurrentPosAndReset(TokenType.CURLY_R);
-        } else if (!Character.isWhitespace(c)) {
-            errorAndReset("Unexpected symbol: " + c);
+        } else if (isWhitespace(c)) {
+            if (options.emitTrivia)
+                startBufferAndSet(c, State.WHITESPACE);
+        } else if (!startCustomToken(c)) {
+            startBufferAndSet(c, State.INITIAL);
+            errorAndReset(LexError.Kind.UNEXPECTED_SYMBOL, "Unexpected symbol: " + c);
         }
     }
 
+    /**
+     * Called after a # at the start of the file. Like rustc, "#!" starts a shebang unless the next thing after it,
+     * skipping whitespace and comments, is a "[", which makes it an inner attribute.
+     */
+    private boolean isShebang() throws IOException {
+        int ahead = 0;
+        if (peek(ahead++) != '!')
+            return false;
+        int c = peek(ahead++);
+        while (ahead < SHEBANG_LOOKAHEAD) {
+            if (c >= 0 && isWhitespace((char) c)) {
+                c = peek(ahead++);
+                continue;
+            }
+            if (c != '/')
+                return c != '[';
+            c = peek(ahead++);
+            if (c == '/') {
+                //doc comments count as tokens
+                int next = peek(ahead++);
+                if (next == '!' || (next == '/' && peek(ahead++) != '/'))
+                    return true;
+                while (next >= 0 && next != '\n' && ahead < SHEBANG_LOOKAHEAD)
+                    next = peek(ahead++);
+                c = next;
+            } else if (c == '*') {
+                int next = peek(ahead++);
+                int previous = 0;
+                if (next == '!')
+                    return true;
+                if (next == '*') {
+                    //"/**/" and "/***" aren't doc comments
+                    previous = next;
+                    next = peek(ahead++);
+                    if (next != '*' && next != '/')
+                        return true;
+                }
+                int depth = 1;
+                while (depth > 0 && next >= 0 && ahead < SHEBANG_LOOKAHEAD) {
+                    if (previous == '/' && next == '*') {
+                        depth++;
+                        next = 0;
+                    } else if (previous == '*' && next == '/') {
+                        depth--;
+                        next = 0;
+                    }
+                    previous = next;
+                    next = peek(ahead++);
+                }
+                c = next;
+            } else {
+                return true;
+            }
+        }
+        return true;
+    }
+
+    private void shebang(char c) {
+        if (c == '\n') {
+            addAndReset(TokenType.SHEBANG);
+            initialState(c);
+        } else {
+            buffer.append(c);
+        }
+    }
+
+    private void whitespace(char c) {
+        if (isWhitespace(c)) {
+            buffer.append(c);
+        } else {
+            addAndReset(TokenType.WHITESPACE);
+            initialState(c);
+        }
+    }
+
+    private void finishTrivia() {
+        if (state != State.WHITESPACE)
+            return;
+        if (addedFinalNewline)
+            buffer.setLength(buffer.length() - 1);
+        if (buffer.length() > 0)
+            addAndReset(TokenType.WHITESPACE);
+    }
+
+    /**
+     * A hook can take the final line break into its token, then nothing would end the token.
+     */
+    private void finishCustomToken() {
+        if (state != State.CUSTOM_TOKEN)
+            return;
+        if (addedFinalNewline)
+            buffer.setLength(buffer.length() - 1);
+        if (buffer.length() > 0)
+            emit(new CustomToken(bufferStartLine, bufferStartColumn, bufferStartOffset, activeHook.kind(),
+                    buffer.toString()));
+        activeHook = null;
+        state = State.INITIAL;
+    }
+
+    /**
+     * Reports a token that the end of the input cut off. The state stays, so {@link #endedInsideToken()} still
+     * tells about it.
+     */
+    private void finishUnterminated() {
+        if (!endedInsideToken())
+            return;
+        String what = switch (state) {
+            case STRING_LITERAL -> "string literal";
+            case BYTE_STRING_LITERAL -> "byte string literal";
+            case RAW_STRING_LITERAL, RAW_STRING_LITERAL_START, RAW_STRING_LITERAL_MAYBE_END -> "raw string literal";
+            case CHAR_LITERAL_ESCAPED, CHAR_LITERAL_END -> "char literal";
+            case BYTE_LITERAL, BYTE_LITERAL_END -> "byte literal";
+            case COMMENT_BLOCK, COMMENT_BLOCK_START, COMMENT_BLOCK_MAYBE_OUTER_DOC_START, COMMENT_BLOCK_INNER_DOC,
+                    COMMENT_BLOCK_OUTER_DOC -> "block comment";
+            default -> "token";
+        };
+        if (addedFinalNewline && buffer.charAt(buffer.length() - 1) == '\n')
+            buffer.setLength(buffer.length() - 1);
+        errorAtBufferStart(LexError.Kind.UNTERMINATED, "Unterminated " + what);
+    }
+
+    /**
+     * Asks hooks before the lexer's own rules, c was just read and the chars after it are in the read-ahead buffer.
+     */
+    private boolean startCustomTokenAhead(char c) {
+        CharSequence ahead = CharBuffer.wrap(chunk, chunkPosition,
+                Math.min(CustomTokenHook.LOOKAHEAD, chunkLength - chunkPosition));
+        for (CustomTokenHook hook : options.hooks) {
+            if (hook.start(c, ahead)) {
+                activeHook = hook;
+                startBufferAndSet(c, State.CUSTOM_TOKEN);
+                return true;
+            }
+        }
+        return false;
+    }
+
+    private boolean startCustomToken(char c) {
+        for (CustomTokenHook hook : options.hooks) {
+            if (hook.start(c)) {
+                activeHook = hook;
+                startBufferAndSet(c, State.CUSTOM_TOKEN);
+                return true;
+            }
+        }
+        return false;
+    }
+
 //    private int peekNext() throws IOException {
 //        in.mark(1);
 //        int nextChar = in.read();
@@ -296,34 +1522,44 @@ public final class Lexer {
 //    }
 
     private void idOrUnderscore(char c) {
-        if (isIdentifierChar(c)) {
+        if (identifierChar(c)) {
             state = State.ID_OR_KEYWORD_OR_SUFFIX;
             buffer.append(c);
         } else {
             addEmptyAndReset(TokenType.UNDERSCORE);
-            state = State.INITIAL;
+            initialState(c);
         }
     }
 
     private void idOrKeywordOrSuffix(char c) {
-        if (isIdentifierChar(c)) {
+        if (identifierChar(c)) {
+            buffer.append(c);
+        } else if (c == '#' && buffer.length() == 1 && buffer.charAt(0) == 'k'
+                && options.features.contains(LexerOptions.Feature.RAW_KEYWORDS)) {
+            //k#keyword, lexed the same way as r#ident
             buffer.append(c);
+            state = State.RAW_IDENTIFIER;
         } else {
-            //Special case for weak keyword "union"
-            if (!tokens.isEmpty()) {
-                Token token = tokens.get(tokens.size() - 1);
-                if (token.type == TokenType.IDENTIFIER && token.value.equals("union")) {
-                    tokens.remove(tokens.size() - 1);
-                    tokens.add(new Token(token.line, token.column, TokenType.UNION));
-                }
-            }
-
             String s = buffer.toString();
-            TokenType keywordType = KEYWORDS.get(s);
-            if (keywordType != null)
+            TokenType keywordType = options.defaultKeywords ? TokenType.fromKeyword(s) : keywords.get(s);
+            boolean contextual = CONTEXTUAL_KEYWORDS.contains(s)
+                    || (s.equals("builtin") && options.features.contains(LexerOptions.Feature.BUILTIN_SYNTAX));
+            if (keywordType != null && !options.reserves(keywordType)) {
+                //"dyn" was already a weak keyword in 2015
+                contextual = keywordType == TokenType.DYN;
+                keywordType = null;
+            }
+            if (keywordType != null && s.equals(keywordType.text)) {
                 addEmptyAndReset(keywordType);
-            else
+            } else if (keywordType != null) {
+                addAndReset(keywordType, s);
+            } else if (contextual) {
+                emit(new Token(bufferStartLine, bufferStartColumn, bufferStartOffset, s.length(),
+                        TokenType.IDENTIFIER, s, true));
+                state = State.INITIAL;
+            } else {
                 addAndReset(TokenType.IDENTIFIER, s);
+            }
             initialState(c);
         }
     }
@@ -369,28 +1605,33 @@ public final class Lexer {
         } else if (c == '\'') {
             buffer.append(c);
             if (buffer.length() == 2) {
-                errorAndReset("Empty char literal");
-            } else if (buffer.length() == 3) {
-                addAndReset(TokenType.CHAR_LITERAL);
+                errorAndReset(LexError.Kind.EMPTY_CHAR_LITERAL, "Empty char literal");
+            } else if (buffer.length() == 2 + Character.charCount(buffer.codePointAt(1))) {
+                endQuotedLiteral(TokenType.CHAR_LITERAL);
             }
-        } else if (buffer.length() == 1) {
+        } else if (buffer.length() == 1
+                || (buffer.length() == 2 && Character.isHighSurrogate(buffer.charAt(1)) && Character.isLowSurrogate(c))) {
             buffer.append(c);
+        } else if (c == '#' && buffer.length() == 2 && buffer.charAt(1) == 'r'
+                && options.edition.compareTo(LexerOptions.Edition.RUST_2021) >= 0) {
+            //'r#lifetime
+            buffer.append(c);
+            state = State.RAW_LIFETIME_START;
+        } else if (isIdentifierStart(buffer.codePointAt(1), options.unicodeVersion)) {
+            //not a char literal, so a lifetime or label, which can be one letter long like 'a
+            state = State.LIFETIME_OR_LABEL;
+            lifetimeOrLabel(c);
+        } else if (isIdentifierChar(buffer.codePointAt(1), options.unicodeVersion)) {
+            errorAndReset(LexError.Kind.INVALID_QUOTE, "Lifetime or label can't start with " + buffer.substring(1));
+            initialState(c);
         } else {
-            if (!isIdentifierChar(buffer.charAt(buffer.length() - 1))) {
-                buffer.append(c);
-                errorAndReset("Unexpected character in char literal, lifetime, or label: " + c);
-            } else if (!isIdentifierChar(c)) {
-                buffer.append(c);
-                errorAndReset("Unexpected character in char literal, lifetime, or label: " + c);
-            } else {
-                state = State.LIFETIME_OR_LABEL;
-                lifetimeOrLabel(c);
-            }
+            buffer.append(c);
+            errorAndReset(LexError.Kind.INVALID_QUOTE, "Unexpected character in char literal, lifetime, or label: " + c);
         }
     }
 
     private void lifetimeOrLabel(char c) {
-        if (isIdentifierChar(c)) {
+        if (identifierChar(c)) {
             buffer.append(c);
         } else if (c == ':') {
             buffer.append(c);
@@ -405,10 +1646,57 @@ public final class Lexer {
         }
     }
 
+    private void rawIdentifier(char c) {
+        if (identifierChar(c)) {
+            buffer.append(c);
+            return;
+        }
+        String name = buffer.substring(2);
+        if (buffer.charAt(0) == 'k') {
+            TokenType keywordType = options.defaultKeywords ? TokenType.fromKeyword(name) : keywords.get(name);
+            if (keywordType != null)
+                addAndReset(keywordType);
+            else
+                errorAndReset(LexError.Kind.INVALID_RAW_IDENTIFIER, "Not a keyword: " + buffer);
+        } else if (RAW_DISALLOWED.contains(name))
+            errorAndReset(LexError.Kind.INVALID_RAW_IDENTIFIER, name + " can't be a raw identifier");
+        else
+            addAndReset(TokenType.RAW_IDENTIFIER);
+        initialState(c);
+    }
+
+    private void rawLifetimeStart(char c) {
+        buffer.append(c);
+        if (identifierStart(c))
+            state = State.RAW_LIFETIME_OR_LABEL;
+        else
+            errorAndReset(LexError.Kind.INVALID_QUOTE, "Unexpected character in raw lifetime or label: " + c);
+    }
+
+    private void rawLifetimeOrLabel(char c) {
+        if (identifierChar(c)) {
+            buffer.append(c);
+            return;
+        }
+        String name = buffer.substring(3);
+        if (c == ':')
+            buffer.append(c);
+        if (RAW_DISALLOWED.contains(name))
+            errorAndReset(LexError.Kind.INVALID_RAW_IDENTIFIER, name + " can't be a raw lifetime or label");
+        else
+            addAndReset(c == ':' ? TokenType.RAW_LABEL : TokenType.RAW_LIFETIME);
+        if (c != ':')
+            initialState(c);
+    }
+
     private void stringOrCharOrByteLiteral(
             char c,
             boolean isByte
     ) {
+        if (!options.validateLiterals && state != State.CHAR_LITERAL_ESCAPED && state != State.BYTE_LITERAL) {
+            unvalidatedString(c);
+            return;
+        }
         switch (stringEscapeState) {
             case NONE -> escapeNone(c, isByte);
             case SLASH -> escapeSlash(c, isByte);
@@ -417,41 +1705,57 @@ public final class Lexer {
         }
     }
 
+    private void unvalidatedString(char c) {
+        buffer.append(c);
+        if (stringEscapeState == State.StringEscape.SLASH)
+            stringEscapeState = State.StringEscape.NONE;
+        else if (c == '\\')
+            stringEscapeState = State.StringEscape.SLASH;
+        else if (c == '"')
+            endQuotedLiteral(state == State.STRING_LITERAL ? TokenType.STRING_LITERAL : TokenType.BYTE_STRING_LITERAL);
+    }
+
     private void escapeNone(char c, boolean isByte) {
-        if (c >= 128 && isByte) {
-            errorAtBufferStart("Unexpected character in byte string: " + c);
-            return;
-        }
+        if (c >= 128 && isByte)
+            errorAtBufferStart(LexError.Kind.NON_ASCII_BYTE, "Unexpected character in byte string: " + c);
 
         buffer.append(c);
 
-        if (c == '"') {
-            if (state == State.STRING_LITERAL)
-                addAndReset(TokenType.STRING_LITERAL);
-            else if (state == State.BYTE_STRING_LITERAL)
-                addAndReset(TokenType.BYTE_STRING_LITERAL);
-        } else if (c == '\\') {
+        if (c == '\\') {
             stringEscapeState = State.StringEscape.SLASH;
-        } else {
-            if (state == State.CHAR_LITERAL_ESCAPED) {
-                state = State.CHAR_LITERAL_END;
-            } else if (state == State.BYTE_LITERAL) {
-                state = State.BYTE_LITERAL_END;
-            }
+        } else if (state == State.STRING_LITERAL) {
+            if (c == '"')
+                endQuotedLiteral(TokenType.STRING_LITERAL);
+        } else if (state == State.BYTE_STRING_LITERAL) {
+            if (c == '"')
+                endQuotedLiteral(TokenType.BYTE_STRING_LITERAL);
+        } else if (state == State.CHAR_LITERAL_ESCAPED) {
+            state = State.CHAR_LITERAL_END;
+        } else if (state == State.BYTE_LITERAL) {
+            state = State.BYTE_LITERAL_END;
         }
     }
 
     private void escapeSlash(char c, boolean isByte) {
+        if (buffer.charAt(buffer.length() - 1) == '\r' && c != '\n') {
+            errorAtEscape(LexError.Kind.UNKNOWN_ESCAPE, "Unknown character escape: \\r");
+            continueAfterEscape(c, isByte);
+            return;
+        }
         buffer.append(c);
         switch (c) {
             case '\'', '"', 'n', 'r', 't', '\\', '0' -> {
-                stringEscapeState = State.StringEscape.NONE;
+                escapeEnd();
+            }
+            case '\r' -> {
+                //a line continuation if \n follows
             }
             case '\n' -> {
-                if (state == State.STRING_LITERAL) {
+                if (state == State.STRING_LITERAL || state == State.BYTE_STRING_LITERAL) {
                     stringEscapeState = State.StringEscape.NONE;
                 } else {
-                    errorAtBufferStart("Backslash before newline is only possible in string literals.");
+                    errorAtEscape(LexError.Kind.LINE_CONTINUATION_OUTSIDE_STRING, "Backslash before newline is only possible in string literals.");
+                    escapeEnd();
                 }
             }
             case 'x' -> {
@@ -459,99 +1763,137 @@ public final class Lexer {
             }
             case 'u' -> {
                 if (isByte) {
-                    errorAtBufferStart("Unicode escape sequences are not allowed in byte strings.");
+                    errorAtEscape(LexError.Kind.UNICODE_ESCAPE_IN_BYTES, "Unicode escape sequences are not allowed in byte strings.");
                     stringEscapeState = State.StringEscape.NONE;
                 } else {
                     stringEscapeState = State.StringEscape.UNICODE;
                 }
             }
+            default -> {
+                errorAtEscape(LexError.Kind.UNKNOWN_ESCAPE, "Unknown character escape: \\" + c);
+                escapeEnd();
+            }
         }
     }
 
     private void escapeAsciiOrByte(char c, boolean isByte) {
-        if (buffer.charAt(buffer.length() - 1) == 'x') {
-            buffer.append(c);
-            if (isHexDigit(c)) {
-                if (!(c >= '0' && c <= '7') && !isByte) {
-                    errorAtBufferStart("Unexpected " + c + " (ASCII escape sequence character code can't be higher than 7F)");
-                    stringEscapeState = State.StringEscape.NONE;
-                }
-            } else {
-                errorAtBufferStart("Unexpected symbol in hex character code: " + c);
-                stringEscapeState = State.StringEscape.NONE;
-            }
-        } else if (buffer.charAt(buffer.length() - 2) == 'x') {
-            buffer.append(c);
-            if (!isHexDigit(c)) {
-                errorAtBufferStart("Unexpected symbol in hex character code: " + c);
-                stringEscapeState = State.StringEscape.NONE;
-            }
-        } else {
-            stringEscapeState = State.StringEscape.NONE;
-            if (state == State.CHAR_LITERAL_ESCAPED) {
-                state = State.CHAR_LITERAL_END;
-                charLiteralEnd(c);
-            } else if (state == State.BYTE_LITERAL) {
-                state = State.BYTE_LITERAL_END;
-                byteLiteralEnd(c);
-            } else {
-                escapeNone(c, isByte);
+        if (!isDigit(c, true)) {
+            errorAtEscape(LexError.Kind.INVALID_HEX_ESCAPE, "Unexpected symbol in hex character code: " + c);
+            continueAfterEscape(c, isByte);
+            return;
+        }
+        buffer.append(c);
+        char first = buffer.charAt(buffer.length() - 2);
+        if (first != 'x') {
+            if (first > '7' && !isByte) {
+                errorAtEscape(LexError.Kind.ASCII_ESCAPE_OUT_OF_RANGE, "ASCII escape sequence character code can't be higher than 7F: \\x" + first + c);
             }
+            escapeEnd();
         }
     }
 
     private void escapeUnicode(char c) {
-        buffer.append(c);
-        if (buffer.charAt(buffer.length() - 2) == 'u') {
-            if (c != '{') {
-                errorAtBufferStart("Unicode escape sequence must start with {");
-                stringEscapeState = State.StringEscape.NONE;
+        char previous = buffer.charAt(buffer.length() - 1);
+        if (previous == 'u') {
+            if (c == '{') {
+                buffer.append(c);
+            } else {
+                errorAtEscape(LexError.Kind.UNICODE_ESCAPE_NO_BRACE, "Unicode escape sequence must start with {");
+                continueAfterEscape(c, false);
             }
+        } else if (c == '}') {
+            buffer.append(c);
+            checkUnicodeEscape();
+            escapeEnd();
+        } else if (isDigit(c, true) || (c == '_' && previous != '{')) {
+            //underscores are allowed after the first digit
+            buffer.append(c);
         } else {
-            int currentDigits = 0;
-            while (buffer.charAt(buffer.length() - 1 - currentDigits) != '{')
-                currentDigits++;
+            errorAtEscape(LexError.Kind.INVALID_UNICODE_ESCAPE, "Unexpected symbol in Unicode hex character code: " + c);
+            continueAfterEscape(c, false);
+        }
+    }
 
-            if (isHexDigit(c)) {
-                if (currentDigits + 1 > 6) {
-                    errorAtBufferStart("Too many digits in Unicode escape sequence");
-                    stringEscapeState = State.StringEscape.NONE;
-                }
-            } else if (c == '}') {
-                stringEscapeState = State.StringEscape.NONE;
-            } else {
-                errorAtBufferStart("Unexpected symbol in Unicode hex character code: " + c);
-                stringEscapeState = State.StringEscape.NONE;
-            }
+    /**
+     * Checks the Unicode escape at the end of the buffer, which ends with its closing }.
+     */
+    private void checkUnicodeEscape() {
+        String digits = buffer.substring(buffer.lastIndexOf("{") + 1, buffer.length() - 1).replace("_", "");
+        if (digits.isEmpty()) {
+            errorAtEscape(LexError.Kind.INVALID_UNICODE_ESCAPE, "Empty Unicode escape sequence");
+            return;
+        }
+        if (digits.length() > 6) {
+            errorAtEscape(LexError.Kind.UNICODE_ESCAPE_TOO_LONG, "Too many digits in Unicode escape sequence, at most 6 are allowed");
+            return;
+        }
+        int value = Integer.parseInt(digits, 16);
+        if (value > Character.MAX_CODE_POINT) {
+            errorAtEscape(LexError.Kind.UNICODE_ESCAPE_OUT_OF_RANGE, "Unicode escape sequence character code can't be higher than 10FFFF");
+        } else if (value >= Character.MIN_SURROGATE && value <= Character.MAX_SURROGATE) {
+            errorAtEscape(LexError.Kind.UNICODE_ESCAPE_SURROGATE, "Unicode escape sequence can't be a surrogate code point (D800 to DFFF)");
+        }
+    }
+
+    /**
+     * Lexes a char that ended a malformed escape without being part of it.
+     */
+    private void continueAfterEscape(char c, boolean isByte) {
+        stringEscapeState = State.StringEscape.NONE;
+        if (state == State.CHAR_LITERAL_ESCAPED) {
+            state = State.CHAR_LITERAL_END;
+            charLiteralEnd(c);
+        } else if (state == State.BYTE_LITERAL) {
+            state = State.BYTE_LITERAL_END;
+            byteLiteralEnd(c);
+        } else {
+            escapeNone(c, isByte);
         }
     }
 
+    private void escapeEnd() {
+        stringEscapeState = State.StringEscape.NONE;
+        if (state == State.CHAR_LITERAL_ESCAPED)
+            state = State.CHAR_LITERAL_END;
+        else if (state == State.BYTE_LITERAL)
+            state = State.BYTE_LITERAL_END;
+    }
+
     private void charLiteralEnd(char c) {
         buffer.append(c);
         if (c == '\'') {
-            addAndReset(TokenType.CHAR_LITERAL);
+            endQuotedLiteral(TokenType.CHAR_LITERAL);
         } else {
-            errorAtBufferStart("Did not expect more than one character in char literal");
+            errorAtBufferStart(LexError.Kind.CHAR_LITERAL_TOO_LONG, "Did not expect more than one character in char literal");
         }
     }
 
     private void byteLiteralEnd(char c) {
         buffer.append(c);
         if (c == '\'') {
-            addAndReset(TokenType.BYTE_LITERAL);
+            endQuotedLiteral(TokenType.BYTE_LITERAL);
         } else {
-            errorAtBufferStart("Did not expect more than one byte in byte literal");
+            errorAtBufferStart(LexError.Kind.BYTE_LITERAL_TOO_LONG, "Did not expect more than one byte in byte literal");
         }
     }
 
     private void rawStringLiteralStart(char c) {
+        if (rawStringHashCount == 1 && buffer.length() == 2 && buffer.charAt(0) == 'r'
+                && identifierStart(c)) {
+            //r#ident
+            rawStringHashCount = 0;
+            buffer.append(c);
+            state = State.RAW_IDENTIFIER;
+            return;
+        }
         buffer.append(c);
         if (c == '#') {
             rawStringHashCount++;
         } else if (c == '"') {
+            rawStringCount++;
             state = State.RAW_STRING_LITERAL;
         } else {
-            errorAndReset("Unexpected character at start of raw string: " + c + " (expected \" or #)");
+            errorAndReset(LexError.Kind.INVALID_RAW_STRING_START, "Unexpected character at start of raw string: " + c + " (expected \" or #)");
             rawStringHashCount = 0;
         }
     }
@@ -568,21 +1910,20 @@ public final class Lexer {
             rawStringHashCount = 0;
             rawStringEndHashCount = 0;
 
-            TokenType type;
             if (buffer.charAt(0) == 'b')
-                type = TokenType.RAW_BYTE_STRING_LITERAL;
+                endQuotedLiteral(TokenType.RAW_BYTE_STRING_LITERAL);
             else
-                type = TokenType.RAW_STRING_LITERAL;
-
-            addAndReset(type);
-            initialState(c);
+                endQuotedLiteral(TokenType.RAW_STRING_LITERAL);
+            quotedLiteralEnd(c);
 
         } else if (c == '#') {
             rawStringEndHashCount++;
             buffer.append(c);
         } else {
             rawStringEndHashCount = 0;
-            state = State.RAW_STRING_LITERAL;
+            //a quote can start a new candidate end, e.g. r##""#"##
+            if (c != '"')
+                state = State.RAW_STRING_LITERAL;
             buffer.append(c);
         }
     }
@@ -604,7 +1945,7 @@ public final class Lexer {
     }
 
     private void numberLiteral(char c) {
-        if (c == 'E') {
+        if (c == 'E' || c == 'e') {
             buffer.append(c);
             state = State.FLOAT_LITERAL_EXPONENT_START;
         } else if (c == '.') {
@@ -612,6 +1953,8 @@ public final class Lexer {
             state = State.FLOAT_LITERAL_DOT;
         } else if ((c >= '0' && c <= '9') || c == '_') {
             buffer.append(c);
+        } else if (isSuffixStart(c)) {
+            startSuffix(TokenType.INT_LITERAL_DEC, c);
         } else {
             addAndReset(TokenType.INT_LITERAL_DEC);
             initialState(c);
@@ -628,8 +1971,13 @@ public final class Lexer {
     }
 
     private void intLiteralOct(char c) {
-        if ((c >= '0' && c <= '8') || c == '_') {
+        if ((c >= '0' && c <= '7') || c == '_') {
             buffer.append(c);
+        } else if (c >= '8' && c <= '9') {
+            buffer.append(c);
+            state = State.INT_LITERAL_INVALID_DIGIT;
+        } else if (isSuffixStart(c)) {
+            startSuffix(TokenType.INT_LITERAL_OCTAL, c);
         } else {
             addAndReset(TokenType.INT_LITERAL_OCTAL);
             initialState(c);
@@ -639,6 +1987,11 @@ public final class Lexer {
     private void intLiteralBin(char c) {
         if (c == '0' || c == '1' || c == '_') {
             buffer.append(c);
+        } else if (c >= '2' && c <= '9') {
+            buffer.append(c);
+            state = State.INT_LITERAL_INVALID_DIGIT;
+        } else if (isSuffixStart(c)) {
+            startSuffix(TokenType.INT_LITERAL_BIN, c);
         } else {
             addAndReset(TokenType.INT_LITERAL_BIN);
             initialState(c);
@@ -652,19 +2005,22 @@ public final class Lexer {
         } else if (c == '_') {
             buffer.append(c);
         } else {
-            errorAndReset("Hex literal must contain at least one digit");
+            noDigits(16, "Hex literal must contain at least one digit");
             initialState(c);
         }
     }
 
     private void intLiteralOctNoDigits(char c) {
-        if (c >= '0' && c <= '9') {
+        if (c >= '0' && c <= '7') {
             buffer.append(c);
             state = State.INT_LITERAL_OCT;
+        } else if (c >= '8' && c <= '9') {
+            buffer.append(c);
+            state = State.INT_LITERAL_INVALID_DIGIT;
         } else if (c == '_') {
             buffer.append(c);
         } else {
-            errorAndReset("Octal literal must contain at least one digit");
+            noDigits(8, "Octal literal must contain at least one digit");
             initialState(c);
         }
     }
@@ -673,20 +2029,70 @@ public final class Lexer {
         if (c == '0' || c == '1') {
             buffer.append(c);
             state = State.INT_LITERAL_BIN;
+        } else if (c >= '2' && c <= '9') {
+            buffer.append(c);
+            state = State.INT_LITERAL_INVALID_DIGIT;
         } else if (c == '_') {
             buffer.append(c);
         } else {
-            errorAndReset("Binary literal must contain at least one digit");
+            noDigits(2, "Binary literal must contain at least one digit");
             initialState(c);
         }
     }
 
+    // points at where the digits should be, after the 0x and any underscores
+    private void noDigits(int radix, String errorMessage) {
+        errorCount++;
+        emit(new LexError(bufferStartLine, bufferStartColumn + buffer.length(), bufferStartOffset + buffer.length(),
+                0, LexError.Kind.NO_DIGITS, errorMessage, radix));
+        state = State.INITIAL;
+    }
+
+    /**
+     * Binary or octal literal with a digit that's too big, like 0b12: the rest of it is skipped and the error
+     * points at the first such digit.
+     */
+    private void intLiteralInvalidDigit(char c) {
+        if (identifierChar(c)) {
+            buffer.append(c);
+            return;
+        }
+        int radix = buffer.charAt(1) == 'b' ? 2 : 8;
+        int index = 2;
+        while (buffer.charAt(index) == '_' || Character.digit(buffer.charAt(index), radix) >= 0)
+            index++;
+        errorCount++;
+        emit(new LexError(bufferStartLine, bufferStartColumn + index, bufferStartOffset + index,
+                buffer.length() - index, LexError.Kind.INVALID_DIGIT,
+                "Digit " + buffer.charAt(index) + " is invalid in " + (radix == 2 ? "a binary" : "an octal")
+                        + " literal", radix));
+        state = State.INITIAL;
+        initialState(c);
+    }
+
     private void floatLiteralDot(char c) {
-        if ((c >= '0' && c <= '9') || c == '_') {
+        if (buffer.charAt(buffer.length() - 1) == '.'
+                && (c == '.' || c == '_' || identifierStart(c))) {
+            //2..5, 2.max(3), 2._0 and 2.e5 are an integer and a dot
+            buffer.setLength(buffer.length() - 1);
+            emit(new Token(bufferStartLine, bufferStartColumn, bufferStartOffset, buffer.length(),
+                    TokenType.INT_LITERAL_DEC, buffer.toString(), Token.FLAG_DOT_NOT_CONSUMED));
+            startBufferAndSet('.', State.DOT);
+            bufferStartColumn = column - 1;
+            bufferStartOffset = offset - 1;
+            dot(c);
+        } else if ((c >= '0' && c <= '9') || c == '_') {
             buffer.append(c);
         } else if (c == 'E' || c == 'e') {
             buffer.append(c);
             state = State.FLOAT_LITERAL_EXPONENT_START;
+        } else if (isSuffixStart(c)) {
+            startSuffix(TokenType.FLOAT_LITERAL, c);
+        } else if (buffer.charAt(buffer.length() - 1) == '.') {
+            emit(new Token(bufferStartLine, bufferStartColumn, bufferStartOffset, buffer.length(),
+                    TokenType.FLOAT_LITERAL, buffer.toString(), Token.FLAG_TRAILING_DOT));
+            state = State.INITIAL;
+            initialState(c);
         } else {
             addAndReset(TokenType.FLOAT_LITERAL);
             initialState(c);
@@ -694,13 +2100,11 @@ public final class Lexer {
     }
 
     private void floatLiteralExponentStart(char c) {
-        if (c == '+' || c == '-') {
+        state = State.FLOAT_LITERAL_EXPONENT_NO_DIGITS;
+        if (c == '+' || c == '-')
             buffer.append(c);
-            state = State.FLOAT_LITERAL_EXPONENT_NO_DIGITS;
-        } else {
-            errorAndReset("Expected + or - at the start of exponent");
-            initialState(c);
-        }
+        else
+            floatLiteralExponentNoDigits(c);
     }
 
     private void floatLiteralExponentNoDigits(char c) {
@@ -710,7 +2114,12 @@ public final class Lexer {
             buffer.append(c);
             state = State.FLOAT_LITERAL_EXPONENT;
         } else {
-            errorAndReset("Exponent should have at least one digit");
+            int exponent = Math.max(buffer.lastIndexOf("e"), buffer.lastIndexOf("E"));
+            errorCount++;
+            emit(new LexError(bufferStartLine, bufferStartColumn + exponent, bufferStartOffset + exponent,
+                    buffer.length() - exponent, LexError.Kind.EXPONENT_NO_DIGITS,
+                    "Exponent should have at least one digit"));
+            state = State.INITIAL;
             initialState(c);
         }
     }
@@ -718,19 +2127,54 @@ public final class Lexer {
     private void floatLiteralExponent(char c) {
         if ((c >= '0' && c <= '9') || c == '_') {
             buffer.append(c);
+        } else if (isSuffixStart(c)) {
+            startSuffix(TokenType.FLOAT_LITERAL, c);
         } else {
             addAndReset(TokenType.FLOAT_LITERAL);
             initialState(c);
         }
     }
 
+    private void startSuffix(TokenType literalType, char c) {
+        buffer.append(c);
+        suffixedLiteralType = literalType;
+        state = State.LITERAL_SUFFIX;
+    }
+
+    private void endQuotedLiteral(TokenType literalType) {
+        suffixedLiteralType = literalType;
+        state = State.QUOTED_LITERAL_END;
+    }
+
+    /**
+     * Any identifier right after a string or char literal is its suffix, e.g. "abc"x. The parser rejects those
+     * outside of macros.
+     */
+    private void quotedLiteralEnd(char c) {
+        if (identifierStart(c)) {
+            startSuffix(suffixedLiteralType, c);
+        } else {
+            addAndReset(suffixedLiteralType);
+            initialState(c);
+        }
+    }
+
+    private void literalSuffix(char c) {
+        if (identifierChar(c)) {
+            buffer.append(c);
+        } else {
+            addAndReset(suffixedLiteralType);
+            initialState(c);
+        }
+    }
+
     private void slash(char c) {
         if (c == '*') {
             buffer.append(c);
             state = State.COMMENT_BLOCK_START;
         } else if (c == '/') {
             buffer.append(c);
-            state = State.COMMENT_LINE;
+            state = State.COMMENT_LINE_START;
         } else if (c == '=') {
             addEmptyAndReset(TokenType.SLASH_EQ);
         } else {
@@ -740,18 +2184,23 @@ public final class Lexer {
     }
 
     private void onCommentBlockStart() {
-        if (nestedCommentDepth == 0)
+        if (nestedCommentDepth == 0) {
             outerCommentState = state;
+            nestedCommentEnd = -1;
+        } else
+            nestedCommentCount++;
         nestedCommentDepth++;
     }
 
     private void onCommentBlockEnd(TokenType type, boolean isNested) {
         if (isNested)
             nestedCommentDepth--;
-        if (nestedCommentDepth == 0)
+        if (nestedCommentDepth == 0) {
             addAndReset(type);
-        else
+        } else {
             state = outerCommentState;
+            nestedCommentEnd = buffer.length();
+        }
     }
 
 
@@ -788,7 +2237,7 @@ public final class Lexer {
 
         if (c == '/' && buffer.charAt(buffer.length() - 2) == '*')
             onCommentBlockEnd(type, true);
-        else if (c == '*' && buffer.charAt(buffer.length() - 2) == '/')
+        else if (c == '*' && buffer.charAt(buffer.length() - 2) == '/' && nestedCommentEnd != buffer.length() - 1)
             state = State.COMMENT_BLOCK_START;
     }
 
@@ -801,6 +2250,7 @@ public final class Lexer {
             state = State.COMMENT_LINE_MAYBE_OUTER_DOC_START;
         } else if (c == '\n') { //
             addAndReset(TokenType.COMMENT);
+            initialState(c);
         } else {
             buffer.append(c);
             state = State.COMMENT_LINE;
@@ -820,6 +2270,7 @@ public final class Lexer {
     private void commentLine(char c, TokenType type) {
         if (c == '\n') {
             addAndReset(type);
+            initialState(c);
         } else {
             buffer.append(c);
         }
@@ -982,4 +2433,326 @@ public final class Lexer {
             initialState(c);
         }
     }
+
+    private void customToken(char c) {
+        if (activeHook.accept(buffer, c)) {
+            buffer.append(c);
+        } else {
+            emit(new CustomToken(bufferStartLine, bufferStartColumn, bufferStartOffset, activeHook.kind(),
+                    buffer.toString()));
+            activeHook = null;
+            state = State.INITIAL;
+            initialState(c);
+        }
+    }
+
+    /**
+     * See {@link #fromBytes}.
+     */
+    public enum Utf8Policy {
+        /** Fail on the first invalid sequence, with its exact position */
+        ERROR,
+        /**
+         * Replace every invalid byte with U+FFFD, one char for each byte. Token spans in bytes are given by
+         * {@link #byteOffset}
+         */
+        LOSSY,
+        /** Drop invalid bytes */
+        SKIP
+    }
+
+    /**
+     * See {@link #profile()}. Each char is counted in the phase of the token it's part of, chars between tokens
+     * count as whitespace or other.
+     */
+    public static final class Profile {
+        public enum Phase {
+            IDENTIFIERS,
+            NUMBERS,
+            /** Strings, chars and their byte and raw variants */
+            STRINGS,
+            COMMENTS,
+            WHITESPACE,
+            /** Punctuation and custom tokens */
+            OTHER
+        }
+
+        private final long[] nanos;
+        private final long[] chars;
+
+        private Profile(long[] nanos, long[] chars) {
+            this.nanos = nanos;
+            this.chars = chars;
+        }
+
+        public long nanos(Phase phase) {
+            return nanos[phase.ordinal()];
+        }
+
+        public long chars(Phase phase) {
+            return chars[phase.ordinal()];
+        }
+
+        public long totalNanos() {
+            long total = 0;
+            for (long n : nanos)
+                total += n;
+            return total;
+        }
+
+        Profile plus(Profile other) {
+            long[] sumNanos = nanos.clone();
+            long[] sumChars = chars.clone();
+            for (int i = 0; i < sumNanos.length; i++) {
+                sumNanos[i] += other.nanos[i];
+                sumChars[i] += other.chars[i];
+            }
+            return new Profile(sumNanos, sumChars);
+        }
+
+        @Override
+        public String toString() {
+            StringBuilder result = new StringBuilder();
+            long total = Math.max(totalNanos(), 1);
+            for (Phase phase : Phase.values()) {
+                result.append(String.format(Locale.ROOT, "%-12s %10.3f ms %5.1f%% %12d chars%n",
+                        phase.name().toLowerCase(Locale.ROOT), nanos(phase) / 1e6, nanos(phase) * 100.0 / total,
+                        chars(phase)));
+            }
+            return result.toString();
+        }
+    }
+
+    /**
+     * Tokens from one {@link #lexBounded} call
+     */
+    public static final class Batch {
+        public final List<Token> tokens;
+        /** False if the budget ran out first */
+        public final boolean finished;
+
+        private Batch(List<Token> tokens, boolean finished) {
+            this.tokens = Collections.unmodifiableList(tokens);
+            this.finished = finished;
+        }
+    }
+
+    public static final class Tokenized {
+        /** Everything except errors */
+        public final List<Token> tokens;
+        public final List<LexError> errors;
+
+        private Tokenized(List<Token> tokens, List<LexError> errors) {
+            this.tokens = Collections.unmodifiableList(tokens);
+            this.errors = Collections.unmodifiableList(errors);
+        }
+    }
+
+    public static final class Checkpoint implements Serializable {
+        private static final long serialVersionUID = 1L;
+
+        private final int tokenCount;
+
+        private final State state;
+        private final State.StringEscape stringEscapeState;
+        private final int rawStringHashCount;
+        private final int rawStringEndHashCount;
+        private final int nestedCommentDepth;
+        private final State outerCommentState;
+        private final int nestedCommentEnd;
+        private final int activeHookIndex;
+        private final TokenType suffixedLiteralType;
+
+        private final String buffer;
+        private final int bufferStartLine;
+        private final int bufferStartColumn;
+        private final int bufferStartOffset;
+
+        private final int line;
+        private final int column;
+        private final int offset;
+        private final int errorCount;
+        private final ArrayList<SavedError> collectedErrors;
+        private final ArrayList<SavedToken> injectionContext;
+        //null when columns are counted in chars
+        private final ArrayList<String> lineTexts;
+        private final int firstLineText;
+        private final boolean startsFile;
+        private final int rawStringCount;
+        private final int nestedCommentCount;
+        private final int refillCount;
+        private final long bulkChars;
+        private final int recoveryCount;
+        private final boolean addedFinalNewline;
+        private final char lastChar;
+        private final boolean finished;
+
+        private Checkpoint(Lexer lexer) {
+            tokenCount = lexer.tokenCount;
+
+            state = lexer.state;
+            stringEscapeState = lexer.stringEscapeState;
+            rawStringHashCount = lexer.rawStringHashCount;
+            rawStringEndHashCount = lexer.rawStringEndHashCount;
+            nestedCommentDepth = lexer.nestedCommentDepth;
+            outerCommentState = lexer.outerCommentState;
+            nestedCommentEnd = lexer.nestedCommentEnd;
+            activeHookIndex = lexer.activeHook != null ? lexer.options.hooks.indexOf(lexer.activeHook) : -1;
+            suffixedLiteralType = lexer.suffixedLiteralType;
+
+            buffer = lexer.buffer != null ? lexer.buffer.toString() : null;
+            bufferStartLine = lexer.bufferStartLine;
+            bufferStartColumn = lexer.bufferStartColumn;
+            bufferStartOffset = lexer.bufferStartOffset;
+
+            line = lexer.line;
+            column = lexer.column;
+            offset = lexer.offset;
+            errorCount = lexer.errorCount;
+            collectedErrors = new ArrayList<>(lexer.collectedErrors.size());
+            for (LexError error : lexer.collectedErrors)
+                collectedErrors.add(new SavedError(error));
+            injectionContext = new ArrayList<>(lexer.injectionContext.size());
+            for (Token token : lexer.injectionContext)
+                injectionContext.add(new SavedToken(token));
+            if (lexer.lineTexts != null) {
+                lineTexts = new ArrayList<>(lexer.lineTexts.size());
+                for (StringBuilder text : lexer.lineTexts)
+                    lineTexts.add(text.toString());
+            } else {
+                lineTexts = null;
+            }
+            firstLineText = lexer.firstLineText;
+            startsFile = lexer.startsFile;
+            rawStringCount = lexer.rawStringCount;
+            nestedCommentCount = lexer.nestedCommentCount;
+            refillCount = lexer.refillCount;
+            bulkChars = lexer.bulkChars;
+            recoveryCount = lexer.recoveryCount;
+            addedFinalNewline = lexer.addedFinalNewline;
+            lastChar = lexer.lastChar;
+            finished = lexer.finished;
+        }
+
+        private void restore(Lexer lexer) throws IOException {
+            if (activeHookIndex >= lexer.options.hooks.size())
+                throw new IOException("Checkpoint was made with different custom token hooks");
+
+            lexer.tokenCount = tokenCount;
+
+            lexer.state = state;
+            lexer.stringEscapeState = stringEscapeState;
+            lexer.rawStringHashCount = rawStringHashCount;
+            lexer.rawStringEndHashCount = rawStringEndHashCount;
+            lexer.nestedCommentDepth = nestedCommentDepth;
+            lexer.outerCommentState = outerCommentState;
+            lexer.nestedCommentEnd = nestedCommentEnd;
+            lexer.activeHook = activeHookIndex >= 0 ? lexer.options.hooks.get(activeHookIndex) : null;
+            lexer.suffixedLiteralType = suffixedLiteralType;
+
+            lexer.buffer = buffer != null ? new StringBuilder(buffer) : null;
+            lexer.bufferStartLine = bufferStartLine;
+            lexer.bufferStartColumn = bufferStartColumn;
+            lexer.bufferStartOffset = bufferStartOffset;
+
+            lexer.line = line;
+            lexer.column = column;
+            lexer.offset = offset;
+            lexer.errorCount = errorCount;
+            lexer.collectedErrors.clear();
+            for (SavedError error : collectedErrors)
+                lexer.collectedErrors.add(error.toError());
+            lexer.injectionContext.clear();
+            for (SavedToken token : injectionContext)
+                lexer.injectionContext.add(token.toToken());
+            if (lexer.lineTexts != null && lineTexts != null) {
+                lexer.lineTexts.clear();
+                for (String text : lineTexts)
+                    lexer.lineTexts.add(new StringBuilder(text));
+                lexer.firstLineText = firstLineText;
+            }
+            lexer.startsFile = startsFile;
+            lexer.rawStringCount = rawStringCount;
+            lexer.nestedCommentCount = nestedCommentCount;
+            lexer.refillCount = refillCount;
+            lexer.bulkChars = bulkChars;
+            lexer.recoveryCount = recoveryCount;
+            lexer.addedFinalNewline = addedFinalNewline;
+            lexer.lastChar = lastChar;
+            lexer.finished = finished;
+        }
+
+        /**
+         * @return how many chars of the input were lexed
+         */
+        public int offset() {
+            return offset;
+        }
+
+        /**
+         * An error collected in {@link LexerOptions.Builder#collectErrors collect errors} mode, tokens aren't
+         * serializable.
+         */
+        private static final class SavedError implements Serializable {
+            private static final long serialVersionUID = 1L;
+
+            private final int line;
+            private final int column;
+            private final int offset;
+            private final int length;
+            private final LexError.Kind kind;
+            private final String value;
+            private final int radix;
+            private final int flags;
+
+            private SavedError(LexError error) {
+                line = error.line;
+                column = error.column;
+                offset = error.offset;
+                length = error.length;
+                kind = error.kind;
+                value = error.value;
+                radix = error.radix;
+                flags = error.flags();
+            }
+
+            private LexError toError() {
+                return new LexError(line, column, offset, length, kind, value, radix, flags);
+            }
+        }
+
+        /**
+         * A token kept for {@link InjectionHook#matches}
+         */
+        private static final class SavedToken implements Serializable {
+            private static final long serialVersionUID = 1L;
+
+            private final int line;
+            private final int column;
+            private final int offset;
+            private final int length;
+            private final TokenType type;
+            private final String value;
+            private final int flags;
+            //null unless it's a CustomToken
+            private final String customKind;
+
+            private SavedToken(Token token) {
+                line = token.line;
+                column = token.column;
+                offset = token.offset;
+                length = token.length;
+                type = token.type;
+                value = token.value;
+                flags = token.flags();
+                customKind = token instanceof CustomToken ? ((CustomToken) token).kind : null;
+            }
+
+            private Token toToken() {
+                if (customKind != null)
+                    return new CustomToken(line, column, offset, customKind, value, flags);
+                return new Token(line, column, offset, length, type, value, flags);
+            }
+        }
+    }
 }
@@ -0,0 +1,471 @@
+package ua.yuriih.rustlexer;
+
+import java.nio.charset.Charset;
+import java.nio.charset.StandardCharsets;
+import java.util.ArrayList;
+import java.util.EnumMap;
+import java.util.EnumSet;
+import java.util.HashMap;
+import java.util.List;
+import java.util.Locale;
+import java.util.Map;
+import java.util.Set;
+import java.util.TreeMap;
+import java.util.function.Consumer;
+
+/**
+ * Lexer configuration, see {@link Lexer#withOptions}. Create it with {@link #builder()}.
+ */
+public final class LexerOptions {
+    public static final LexerOptions DEFAULT = builder().build();
+
+    final Map<String, TokenType> keywords;
+    //no keywords added or removed, so the lexer can use TokenType.fromKeyword
+    final boolean defaultKeywords;
+    final List<CustomTokenHook> hooks;
+    final List<InjectionHook> injections;
+    final boolean emitTrivia;
+    final boolean recovery;
+    final int maxTokens;
+    final Charset charset;
+    final boolean storeText;
+    final boolean collectErrors;
+    final Map<LexWarning.Kind, LexWarning.Severity> severities;
+    final Consumer<? super LexWarning> warningSink;
+    final Edition edition;
+    final boolean validateLiterals;
+    final boolean profile;
+    final boolean log;
+    final Set<Feature> features;
+    final UnicodeVersion unicodeVersion;
+    final ColumnMode columnMode;
+
+    private LexerOptions(Builder builder) {
+        this.keywords = Map.copyOf(builder.keywords);
+        this.defaultKeywords = keywords.equals(Lexer.KEYWORDS);
+        this.hooks = List.copyOf(builder.hooks);
+        this.injections = List.copyOf(builder.injections);
+        this.emitTrivia = builder.emitTrivia;
+        this.recovery = builder.recovery;
+        this.maxTokens = builder.maxTokens;
+        this.charset = builder.charset;
+        this.storeText = builder.storeText;
+        this.collectErrors = builder.collectErrors;
+        this.severities = new EnumMap<>(builder.severities);
+        this.warningSink = builder.warningSink;
+        this.edition = builder.edition;
+        this.validateLiterals = builder.validateLiterals;
+        this.profile = builder.profile;
+        this.log = builder.log;
+        this.features = builder.features.isEmpty() ? Set.of() : EnumSet.copyOf(builder.features);
+        this.unicodeVersion = builder.unicodeVersion;
+        this.columnMode = builder.columnMode;
+    }
+
+    /**
+     * Unicode version of the tables that decide which chars make up identifiers, e.g. "13.0".
+     * Null if it's the runtime's and this Java version is newer than the ones the lexer knows about.
+     */
+    public String unicodeVersion() {
+        return unicodeVersion.version();
+    }
+
+    /**
+     * Text that differs between options that can lex the same source to different tokens, for caches. Hooks are
+     * told apart by their class, the warning sink, profiling and logging don't count.
+     */
+    String fingerprint() {
+        StringBuilder s = new StringBuilder();
+        new TreeMap<>(keywords).forEach((word, type) -> s.append(word).append('=').append(type.code).append(' '));
+        s.append('\n');
+        for (CustomTokenHook hook : hooks)
+            s.append(hook.getClass().getName()).append(':').append(hook.kind()).append(' ');
+        s.append('\n');
+        for (InjectionHook injection : injections)
+            s.append(injection.getClass().getName()).append(':').append(injection.language()).append(' ');
+        s.append('\n');
+        String unicode = unicodeVersion == UnicodeVersion.RUNTIME
+                ? "runtime " + Runtime.version().feature() : unicodeVersion.name();
+        s.append(emitTrivia).append(' ').append(recovery).append(' ').append(maxTokens).append(' ')
+                .append(charset.name()).append(' ').append(storeText).append(' ').append(collectErrors).append(' ')
+                .append(severities).append(' ').append(edition).append(' ').append(validateLiterals).append(' ')
+                .append(features).append(' ').append(unicode).append(' ').append(columnMode);
+        return s.toString();
+    }
+
+    LexWarning.Severity severity(LexWarning.Kind kind) {
+        return severities.getOrDefault(kind, kind.defaultSeverity);
+    }
+
+    /**
+     * @return false for keywords that are identifiers with this edition and these features
+     */
+    boolean reserves(TokenType keyword) {
+        return edition.reserves(keyword) || (keyword == TokenType.GEN && features.contains(Feature.GEN_BLOCKS));
+    }
+
+    public static Builder builder() {
+        return new Builder();
+    }
+
+    public Builder toBuilder() {
+        Builder builder = new Builder();
+        builder.keywords.clear();
+        builder.keywords.putAll(keywords);
+        builder.hooks.addAll(hooks);
+        builder.injections.addAll(injections);
+        builder.emitTrivia = emitTrivia;
+        builder.recovery = recovery;
+        builder.maxTokens = maxTokens;
+        builder.charset = charset;
+        builder.storeText = storeText;
+        builder.collectErrors = collectErrors;
+        builder.severities.putAll(severities);
+        builder.warningSink = warningSink;
+        builder.edition = edition;
+        builder.validateLiterals = validateLiterals;
+        builder.profile = profile;
+        builder.log = log;
+        builder.features.addAll(features);
+        builder.unicodeVersion = unicodeVersion;
+        builder.columnMode = columnMode;
+        return builder;
+    }
+
+    public static final class Builder {
+        private final HashMap<String, TokenType> keywords = new HashMap<>(Lexer.KEYWORDS);
+        private final ArrayList<CustomTokenHook> hooks = new ArrayList<>();
+        private final ArrayList<InjectionHook> injections = new ArrayList<>();
+        private boolean emitTrivia = false;
+        private boolean recovery = true;
+        private int maxTokens = 0;
+        private Charset charset = StandardCharsets.UTF_8;
+        private boolean storeText = true;
+        private boolean collectErrors = false;
+        private final EnumMap<LexWarning.Kind, LexWarning.Severity> severities = new EnumMap<>(LexWarning.Kind.class);
+        private Consumer<? super LexWarning> warningSink;
+        private Edition edition = Edition.RUST_2021;
+        private boolean validateLiterals = true;
+        private boolean profile = false;
+        private boolean log = false;
+        private final EnumSet<Feature> features = EnumSet.noneOf(Feature.class);
+        private UnicodeVersion unicodeVersion = UnicodeVersion.RUNTIME;
+        private ColumnMode columnMode = ColumnMode.CHARS;
+
+        private Builder() {
+        }
+
+        /**
+         * Adds a keyword that isn't part of Rust, it will be lexed as {@link TokenType#CUSTOM_KEYWORD}.
+         */
+        public Builder addKeyword(String text) {
+            return addKeyword(text, TokenType.CUSTOM_KEYWORD);
+        }
+
+        /**
+         * Lexes the word as the given keyword type, e.g. ("fun", FN) for a language that spells it differently.
+         */
+        public Builder addKeyword(String text, TokenType type) {
+            if (!type.isKeyword())
+                throw new IllegalArgumentException(type + " is not a keyword");
+            keywords.put(text, type);
+            return this;
+        }
+
+        /**
+         * The word will be lexed as a plain identifier.
+         */
+        public Builder removeKeyword(String text) {
+            keywords.remove(text);
+            return this;
+        }
+
+        /**
+         * Hooks are asked about unknown characters in the order they were added.
+         */
+        public Builder addHook(CustomTokenHook hook) {
+            hooks.add(hook);
+            return this;
+        }
+
+        /**
+         * Injection hooks are asked about string literals in the order they were added, the first one that
+         * matches lexes the contents.
+         */
+        public Builder addInjection(InjectionHook injection) {
+            injections.add(injection);
+            return this;
+        }
+
+        /**
+         * Emit {@link TokenType#WHITESPACE} tokens, off by default.
+         */
+        public Builder emitTrivia(boolean emitTrivia) {
+            this.emitTrivia = emitTrivia;
+            return this;
+        }
+
+        /**
+         * Keep lexing after an error, on by default. When off, lexing stops after the first error token.
+         */
+        public Builder recovery(boolean recovery) {
+            this.recovery = recovery;
+            return this;
+        }
+
+        /**
+         * Stop with an error once this many tokens are produced, 0 (default) for no limit.
+         */
+        public Builder maxTokens(int maxTokens) {
+            if (maxTokens < 0)
+                throw new IllegalArgumentException("maxTokens can't be negative");
+            this.maxTokens = maxTokens;
+            return this;
+        }
+
+        /**
+         * Encoding of the input, UTF-8 by default.
+         */
+        public Builder charset(Charset charset) {
+            this.charset = charset;
+            return this;
+        }
+
+        /**
+         * Keep the source text of identifiers, literals and comments in {@link Token#value}, on by default.
+         * When off, read it with {@link TokenStream#text} instead.
+         */
+        public Builder storeText(boolean storeText) {
+            this.storeText = storeText;
+            return this;
+        }
+
+        /**
+         * Keep error tokens out of the output and collect them in {@link Lexer#errors()} instead, off by default.
+         * Lexing always recovers from errors in this mode. A string or block comment that the end of the input
+         * cuts off is collected too.
+         */
+        public Builder collectErrors(boolean collectErrors) {
+            this.collectErrors = collectErrors;
+            return this;
+        }
+
+        /**
+         * Receives warnings with {@link LexWarning.Severity#WARN} severity, by default they're dropped.
+         */
+        public Builder warningSink(Consumer<? super LexWarning> warningSink) {
+            this.warningSink = warningSink;
+            return this;
+        }
+
+        /**
+         * Every kind of warning has {@link LexWarning.Severity#WARN} severity by default, except for the ones with
+         * another {@link LexWarning.Kind#defaultSeverity}.
+         */
+        public Builder severity(LexWarning.Kind kind, LexWarning.Severity severity) {
+            severities.put(kind, severity);
+            return this;
+        }
+
+        /**
+         * Keywords added in later editions are lexed as identifiers, 2021 by default.
+         */
+        public Builder edition(Edition edition) {
+            this.edition = edition;
+            return this;
+        }
+
+        /**
+         * Check escapes in string and byte string literals, and report non-ASCII chars in byte strings,
+         * on by default. When off, a backslash just keeps the next char from ending the string, and the literal
+         * can be checked later with {@link Literal#validate()}. Strings can then end in a different place than they
+         * would with validation, but only where the lexer recovers from a malformed escape.
+         */
+        public Builder validateLiterals(boolean validateLiterals) {
+            this.validateLiterals = validateLiterals;
+            return this;
+        }
+
+        /**
+         * Measure where lexing time goes, see {@link Lexer#profile()}. Off by default, it makes lexing slower.
+         */
+        public Builder profile(boolean profile) {
+            this.profile = profile;
+            return this;
+        }
+
+        /**
+         * Log stats of every lexed file, and {@link TokenCache} hits and misses, with java.util.logging at
+         * {@link java.util.logging.Level#FINE FINE} level: time per phase, errors recovered from, and how often
+         * the slow paths were taken. Off by default, it profiles lexing like {@link #profile(boolean)} does.
+         */
+        public Builder log(boolean log) {
+            this.log = log;
+            return this;
+        }
+
+        /**
+         * Lex syntax that isn't stable Rust yet, none of it is enabled by default.
+         */
+        public Builder enable(Feature feature) {
+            features.add(feature);
+            return this;
+        }
+
+        public Builder disable(Feature feature) {
+            features.remove(feature);
+            return this;
+        }
+
+        /**
+         * Which Unicode version's identifier chars to use, the Java runtime's by default. Pin a version to get
+         * the same tokens whichever Java runs the lexer.
+         */
+        public Builder unicodeVersion(UnicodeVersion unicodeVersion) {
+            this.unicodeVersion = unicodeVersion;
+            return this;
+        }
+
+        /**
+         * How token and warning columns are counted, in chars by default. Offsets and lengths are always in chars.
+         * A lexer {@link Lexer#resume resumed} from a checkpoint counts the part of the line before the checkpoint
+         * in chars.
+         */
+        public Builder columns(ColumnMode columnMode) {
+            this.columnMode = columnMode;
+            return this;
+        }
+
+        public LexerOptions build() {
+            return new LexerOptions(this);
+        }
+    }
+
+    public enum Edition {
+        RUST_2015,
+        RUST_2018,
+        RUST_2021,
+        RUST_2024;
+
+        /**
+         * @param year e.g. "2018"
+         */
+        public static Edition of(String year) {
+            for (Edition edition : values()) {
+                if (edition.name().endsWith("_" + year))
+                    return edition;
+            }
+            throw new IllegalArgumentException("Unknown edition: " + year);
+        }
+
+        /**
+         * @return false for keywords that are identifiers in this edition, like "async" in 2015
+         */
+        public boolean reserves(TokenType keyword) {
+            return switch (keyword) {
+                case ASYNC, AWAIT, DYN, TRY -> compareTo(RUST_2018) >= 0;
+                case GEN -> compareTo(RUST_2024) >= 0;
+                default -> true;
+            };
+        }
+    }
+
+    /**
+     * Unstable syntax, for tools that follow nightly Rust. These can change or go away along with the syntax.
+     */
+    public enum Feature {
+        /** "gen" is a keyword in every edition, not just 2024, as with #![feature(gen_blocks)] */
+        GEN_BLOCKS,
+        /** "builtin" is a contextual keyword, as in builtin # offset_of(Struct, field) */
+        BUILTIN_SYNTAX,
+        /** k#name is the keyword name, even where the edition doesn't reserve it, e.g. k#gen in 2021 */
+        RAW_KEYWORDS;
+
+        /**
+         * @param name e.g. "gen_blocks" or "GEN_BLOCKS"
+         */
+        public static Feature of(String name) {
+            try {
+                return valueOf(name.toUpperCase(Locale.ROOT).replace('-', '_'));
+            } catch (IllegalArgumentException e) {
+                throw new IllegalArgumentException("Unknown feature: " + name);
+            }
+        }
+    }
+
+    /**
+     * Unicode version for identifier chars, see {@link Builder#unicodeVersion}. Older versions have their tables
+     * bundled with the lexer, so they don't depend on the Java version.
+     */
+    public enum UnicodeVersion {
+        /** Whatever {@link Character} has, which changes with the Java version */
+        RUNTIME,
+        /** What Java 15 to 18 have */
+        UNICODE_13_0;
+
+        /**
+         * @param version e.g. "13.0", or "runtime"
+         */
+        public static UnicodeVersion of(String version) {
+            if (version.equalsIgnoreCase("runtime"))
+                return RUNTIME;
+            for (UnicodeVersion unicodeVersion : values()) {
+                if (unicodeVersion.name().equals("UNICODE_" + version.replace('.', '_')))
+                    return unicodeVersion;
+            }
+            throw new IllegalArgumentException("Unknown Unicode version: " + version);
+        }
+
+        /**
+         * The version number, e.g. "13.0". For {@link #RUNTIME} it's looked up from the Java version, null if
+         * that's newer than the lexer knows about.
+         */
+        public String version() {
+            if (this != RUNTIME)
+                return name().substring("UNICODE_".length()).replace('_', '.');
+            return switch (Runtime.version().feature()) {
+                case 15, 16, 17, 18 -> "13.0";
+                case 19 -> "14.0";
+                case 20, 21 -> "15.0";
+                case 22, 23 -> "15.1";
+                case 24, 25 -> "16.0";
+                default -> null;
+            };
+        }
+
+        boolean isIdentifierStart(int codePoint) {
+            if (this == RUNTIME)
+                return Character.isUnicodeIdentifierStart(codePoint);
+            return UnicodeTables.isIdentifierStart13(codePoint);
+        }
+
+        boolean isIdentifierPart(int codePoint) {
+            if (this == RUNTIME)
+                return Character.isUnicodeIdentifierPart(codePoint) && !Character.isIdentifierIgnorable(codePoint);
+            return UnicodeTables.isIdentifierPart13(codePoint);
+        }
+    }
+
+    /**
+     * What a column is, see {@link Builder#columns}.
+     */
+    public enum ColumnMode {
+        /** UTF-16 chars, so a char outside the Basic Multilingual Plane takes two columns */
+        CHARS,
+        /** Extended grapheme clusters, e.g. an emoji with a skin tone modifier or a letter with an accent */
+        GRAPHEMES,
+        /** Grapheme clusters, taking two columns for wide East Asian chars and emoji like terminals do */
+        WIDTH;
+
+        /**
+         * @param name e.g. "graphemes"
+         */
+        public static ColumnMode of(String name) {
+            try {
+                return valueOf(name.toUpperCase(Locale.ROOT));
+            } catch (IllegalArgumentException e) {
+                throw new IllegalArgumentException("Unknown column mode: " + name);
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.Comparator;
+import java.util.List;
+
+/**
+ * Checks that work on lexed tokens rather than raw text, so they aren't fooled by string literals.
+ */
+public final class Lints {
+    public static final List<String> DEFAULT_TODO_MARKERS = List.of("TODO", "FIXME", "HACK", "XXX");
+
+    private Lints() {
+    }
+
+    public static List<TodoComment> todoComments(List<Token> tokens) {
+        return todoComments(tokens, DEFAULT_TODO_MARKERS);
+    }
+
+    /**
+     * Finds markers in comments (including doc comments), as whole words.
+     * The message is the rest of the line after the marker, without an optional "(owner)" and ":".
+     */
+    public static List<TodoComment> todoComments(List<Token> tokens, List<String> markers) {
+        ArrayList<TodoComment> found = new ArrayList<>();
+        for (Token token : tokens) {
+            if (!isComment(token.type) || token.text() == null)
+                continue;
+            String text = token.text();
+            int line = token.line;
+            int column = token.column;
+            for (int i = 0; i < text.length(); i++) {
+                if (i > 0 && text.charAt(i - 1) == '\n') {
+                    line++;
+                    column = 0;
+                } else if (i > 0) {
+                    column++;
+                }
+
+                String marker = markerAt(text, i, markers);
+                if (marker != null) {
+                    found.add(new TodoComment(marker, message(text, i + marker.length()), line, column,
+                            token.offset + i, marker.length()));
+                    //rest of the marker is skipped, keep the column in sync
+                    i += marker.length() - 1;
+                    column += marker.length() - 1;
+                }
+            }
+        }
+        return found;
+    }
+
+    /**
+     * Finds mixed line endings, indentation that doesn't match the rest of the file, and trailing whitespace.
+     * The tokens must be lexed with {@link LexerOptions.Builder#emitTrivia trivia}. Only whitespace and comments
+     * are looked at, the insides of strings are left alone.
+     */
+    public static Layout layout(List<Token> tokens) {
+        Layout layout = new Layout();
+        ArrayList<LayoutIssue> crlf = new ArrayList<>();
+        ArrayList<LayoutIssue> lf = new ArrayList<>();
+        ArrayList<LayoutIssue> tabIndents = new ArrayList<>();
+        ArrayList<LayoutIssue> spaceIndents = new ArrayList<>();
+        //a line comment takes the CR of a CRLF, the LF is in the whitespace after it
+        int commentCr = -1;
+        for (Token token : tokens) {
+            String text = token.text();
+            if (!(token.type == TokenType.WHITESPACE || isComment(token.type)) || text == null || token.isSynthetic())
+                continue;
+            boolean lineComment = isComment(token.type) && text.startsWith("//");
+            int line = token.line;
+            int lineStart = 0;
+            for (int i = 0; i <= text.length(); i++) {
+                if (i < text.length() && text.charAt(i) != '\n')
+                    continue;
+                int column = line == token.line ? token.column : 0;
+                int end = i;
+                boolean lineEnds = i < text.length();
+                if (lineEnds && end > lineStart && text.charAt(end - 1) == '\r') {
+                    end--;
+                    crlf.add(new LayoutIssue(LayoutIssue.Kind.LINE_ENDING, line, column + end - lineStart,
+                            token.offset + end, 2));
+                } else if (lineEnds && i == 0 && token.offset == commentCr + 1) {
+                    crlf.add(new LayoutIssue(LayoutIssue.Kind.LINE_ENDING, line, column - 1, commentCr, 2));
+                } else if (lineEnds) {
+                    lf.add(new LayoutIssue(LayoutIssue.Kind.LINE_ENDING, line, column + end - lineStart,
+                            token.offset + end, 1));
+                } else if (lineComment && end > lineStart && text.charAt(end - 1) == '\r') {
+                    end--;
+                    commentCr = token.offset + end;
+                }
+
+                if (token.type == TokenType.WHITESPACE && lineEnds || lineComment && !lineEnds) {
+                    int trailing = end;
+                    while (trailing > lineStart
+                            && (text.charAt(trailing - 1) == ' ' || text.charAt(trailing - 1) == '\t'))
+                        trailing--;
+                    if (trailing < end) {
+                        layout.issues.add(new LayoutIssue(LayoutIssue.Kind.TRAILING_WHITESPACE, line,
+                                column + trailing - lineStart, token.offset + trailing, end - trailing));
+                    }
+                } else if (token.type == TokenType.WHITESPACE && !lineEnds && column == 0 && end > lineStart) {
+                    String indent = text.substring(lineStart, end);
+                    boolean tabs = indent.indexOf('\t') >= 0;
+                    boolean spaces = indent.indexOf(' ') >= 0;
+                    LayoutIssue issue = new LayoutIssue(tabs && spaces ? LayoutIssue.Kind.MIXED_INDENT
+                            : LayoutIssue.Kind.INDENT_STYLE, line, 0, token.offset + lineStart, end - lineStart);
+                    if (tabs && spaces)
+                        layout.issues.add(issue);
+                    else if (tabs)
+                        tabIndents.add(issue);
+                    else if (spaces)
+                        spaceIndents.add(issue);
+                }
+                line++;
+                lineStart = i + 1;
+            }
+        }
+
+        layout.crlfLines = crlf.size();
+        layout.lfLines = lf.size();
+        layout.tabIndentedLines = tabIndents.size();
+        layout.spaceIndentedLines = spaceIndents.size();
+        //whichever is less common is the odd one out, LF and spaces win a tie
+        layout.issues.addAll(crlf.size() > lf.size() ? lf : crlf);
+        layout.issues.addAll(tabIndents.size() > spaceIndents.size() ? spaceIndents : tabIndents);
+        layout.issues.sort(Comparator.comparingInt((LayoutIssue issue) -> issue.offset));
+        return layout;
+    }
+
+    private static boolean isComment(TokenType type) {
+        return type == TokenType.COMMENT || type == TokenType.COMMENT_INNER_DOC || type == TokenType.COMMENT_OUTER_DOC;
+    }
+
+    private static String markerAt(String text, int i, List<String> markers) {
+        if (i > 0 && Character.isLetterOrDigit(text.charAt(i - 1)))
+            return null;
+        for (String marker : markers) {
+            int end = i + marker.length();
+            if (text.startsWith(marker, i) && (end == text.length() || !Character.isLetterOrDigit(text.charAt(end))))
+                return marker;
+        }
+        return null;
+    }
+
+    private static String message(String text, int i) {
+        int lineEnd = text.indexOf('\n', i);
+        String rest = text.substring(i, lineEnd < 0 ? text.length() : lineEnd);
+        if (rest.startsWith("(")) {
+            int close = rest.indexOf(')');
+            if (close >= 0)
+                rest = rest.substring(close + 1);
+        }
+        rest = rest.strip();
+        if (rest.startsWith(":"))
+            rest = rest.substring(1);
+        if (lineEnd < 0 && rest.endsWith("*/"))
+            rest = rest.substring(0, rest.length() - 2);
+        return rest.strip();
+    }
+
+    public static final class Layout {
+        private int lfLines;
+        private int crlfLines;
+        private int spaceIndentedLines;
+        private int tabIndentedLines;
+        private final ArrayList<LayoutIssue> issues = new ArrayList<>();
+
+        private Layout() {
+        }
+
+        public int lfLines() {
+            return lfLines;
+        }
+
+        public int crlfLines() {
+            return crlfLines;
+        }
+
+        public int spaceIndentedLines() {
+            return spaceIndentedLines;
+        }
+
+        public int tabIndentedLines() {
+            return tabIndentedLines;
+        }
+
+        /**
+         * @return in order of position
+         */
+        public List<LayoutIssue> issues() {
+            return Collections.unmodifiableList(issues);
+        }
+    }
+
+    public static final class LayoutIssue {
+        public enum Kind {
+            /** A line ending in CRLF in a file that mostly uses LF, or the other way around */
+            LINE_ENDING,
+            /** Indentation with both tabs and spaces */
+            MIXED_INDENT,
+            /** Indented with tabs in a file that mostly uses spaces, or the other way around */
+            INDENT_STYLE,
+            TRAILING_WHITESPACE
+        }
+
+        public final Kind kind;
+        public final int line;
+        public final int column;
+        public final int offset;
+        public final int length;
+
+        private LayoutIssue(Kind kind, int line, int column, int offset, int length) {
+            this.kind = kind;
+            this.line = line;
+            this.column = column;
+            this.offset = offset;
+            this.length = length;
+        }
+
+        @Override
+        public String toString() {
+            return line + ":" + column + "\t" + kind;
+        }
+    }
+
+    public static final class TodoComment {
+        public final String marker;
+        public final String message;
+        public final int line;
+        public final int column;
+        public final int offset;
+        public final int length;
+
+        private TodoComment(String marker, String message, int line, int column, int offset, int length) {
+            this.marker = marker;
+            this.message = message;
+            this.line = line;
+            this.column = column;
+            this.offset = offset;
+            this.length = length;
+        }
+
+        @Override
+        public String toString() {
+            return line + ":" + column + "\t" + marker + "\t" + message;
+        }
+    }
+}
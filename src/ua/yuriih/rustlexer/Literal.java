@@ -0,0 +1,676 @@
+package ua.yuriih.rustlexer;
+
+import java.math.BigInteger;
+import java.util.ArrayList;
+import java.util.List;
+import java.util.Locale;
+import java.util.Map;
+
+/**
+ * A literal token taken apart: its kind, suffix and value. Everything is worked out from the token's text
+ * when it's first asked for.
+ */
+public final class Literal {
+    public static final BigInteger U128_MAX = BigInteger.ONE.shiftLeft(128).subtract(BigInteger.ONE);
+
+    private static final Map<String, Integer> INT_SUFFIX_BITS = Map.ofEntries(
+            Map.entry("u8", 8), Map.entry("u16", 16), Map.entry("u32", 32), Map.entry("u64", 64),
+            Map.entry("u128", 128), Map.entry("usize", 64),
+            Map.entry("i8", 8), Map.entry("i16", 16), Map.entry("i32", 32), Map.entry("i64", 64),
+            Map.entry("i128", 128), Map.entry("isize", 64)
+    );
+
+    public final Token token;
+    //computed on first use
+    private Kind kind;
+    private String suffix;
+    private String cooked;
+
+    public Literal(Token token) {
+        this.token = token;
+    }
+
+    public enum Kind {
+        CHAR,
+        BYTE,
+        STRING,
+        BYTE_STRING,
+        RAW_STRING,
+        RAW_BYTE_STRING,
+        INT,
+        FLOAT;
+
+        public boolean isRaw() {
+            return this == RAW_STRING || this == RAW_BYTE_STRING;
+        }
+
+        public boolean isNumber() {
+            return this == INT || this == FLOAT;
+        }
+    }
+
+    /**
+     * @throws IllegalStateException if the token isn't a literal
+     */
+    public Kind kind() {
+        if (kind == null) {
+            kind = switch (token.type) {
+                case CHAR_LITERAL -> Kind.CHAR;
+                case BYTE_LITERAL -> Kind.BYTE;
+                case STRING_LITERAL -> Kind.STRING;
+                case BYTE_STRING_LITERAL -> Kind.BYTE_STRING;
+                case RAW_STRING_LITERAL -> Kind.RAW_STRING;
+                case RAW_BYTE_STRING_LITERAL -> Kind.RAW_BYTE_STRING;
+                case INT_LITERAL_DEC, INT_LITERAL_HEX, INT_LITERAL_OCTAL, INT_LITERAL_BIN -> Kind.INT;
+                case FLOAT_LITERAL -> Kind.FLOAT;
+                default -> throw new IllegalStateException("Not a literal: " + token.type);
+            };
+        }
+        return kind;
+    }
+
+    /**
+     * Number of # around a raw string, e.g. 2 for r##"a"##, 0 for other literals.
+     */
+    public int hashCount() {
+        if (!kind().isRaw())
+            return 0;
+        String text = token.text();
+        return text.indexOf('"') - text.indexOf('r') - 1;
+    }
+
+    /**
+     * The literal as written in the source, with quotes, prefix and suffix.
+     */
+    public String raw() {
+        return token.text();
+    }
+
+    /**
+     * The value of a char or string literal, see {@link #unescape()}. Null for numbers, which have
+     * {@link #parseInt()} and {@link #parseFloat()} instead.
+     */
+    public String cooked() {
+        if (cooked == null && !kind().isNumber())
+            cooked = unescape();
+        return cooked;
+    }
+
+    /**
+     * Parses an integer literal in any base, ignoring underscores.
+     * <p>
+     * The value must fit into u128, and into the suffix type if there is one.
+     * Signed types allow one more than their maximum, since "-128i8" is lexed as a minus and 128i8.
+     * usize and isize are assumed to be 64 bits wide.
+     */
+    public BigInteger parseInt() throws LiteralParseException {
+        int radix = radix();
+        if (radix == 0)
+            throw new LiteralParseException(LiteralParseException.Kind.WRONG_TOKEN_TYPE, 0,
+                    "Not an integer literal: " + token.type);
+
+        String text = token.text();
+        int i = radix == 10 ? 0 : 2;
+        BigInteger value = BigInteger.ZERO;
+        BigInteger bigRadix = BigInteger.valueOf(radix);
+        boolean hasDigits = false;
+        for (; i < text.length(); i++) {
+            char c = text.charAt(i);
+            if (c == '_')
+                continue;
+            int digit = Character.digit(c, 16);
+            if (digit < 0 || (radix != 16 && c > '9'))
+                break; //start of the suffix
+            if (digit >= radix)
+                throw new LiteralParseException(LiteralParseException.Kind.INVALID_DIGIT, i,
+                        "Invalid digit for a base " + radix + " literal: " + c);
+
+            value = value.multiply(bigRadix).add(BigInteger.valueOf(digit));
+            hasDigits = true;
+        }
+        if (!hasDigits)
+            throw new LiteralParseException(LiteralParseException.Kind.NO_DIGITS, i, "No digits in integer literal");
+
+        String suffix = text.substring(i);
+        BigInteger max = U128_MAX;
+        if (!suffix.isEmpty()) {
+            Integer bits = INT_SUFFIX_BITS.get(suffix);
+            if (bits == null)
+                throw new LiteralParseException(LiteralParseException.Kind.INVALID_SUFFIX, i,
+                        "Invalid suffix for integer literal: " + suffix);
+            if (suffix.charAt(0) == 'i')
+                max = BigInteger.ONE.shiftLeft(bits - 1);
+            else
+                max = BigInteger.ONE.shiftLeft(bits).subtract(BigInteger.ONE);
+        }
+        if (value.compareTo(max) > 0)
+            throw new LiteralParseException(LiteralParseException.Kind.OVERFLOW, 0,
+                    "Integer literal is too large for " + (suffix.isEmpty() ? "u128" : suffix));
+        return value;
+    }
+
+    /**
+     * Parses a float literal, or a decimal integer literal with f32/f64 suffix.
+     * Conversion is correctly rounded straight from the decimal text, so f32 values aren't double-rounded.
+     *
+     * @return a Float for f32 literals, a Double otherwise
+     */
+    public Number parseFloat() throws LiteralParseException {
+        if (token.type != TokenType.FLOAT_LITERAL && token.type != TokenType.INT_LITERAL_DEC)
+            throw new LiteralParseException(LiteralParseException.Kind.WRONG_TOKEN_TYPE, 0,
+                    "Not a float literal: " + token.type);
+
+        String text = token.text();
+        StringBuilder number = new StringBuilder();
+        int i = 0;
+        for (; i < text.length(); i++) {
+            char c = text.charAt(i);
+            if (c == '_')
+                continue;
+            if ((c == 'e' || c == 'E') && i + 1 < text.length() && isExponentStart(text.charAt(i + 1))) {
+                number.append(c);
+                if (text.charAt(++i) != '_')
+                    number.append(text.charAt(i));
+            } else if ((c >= '0' && c <= '9') || c == '.') {
+                number.append(c);
+            } else {
+                break; //start of the suffix
+            }
+        }
+
+        String suffix = text.substring(i);
+        if (!suffix.isEmpty() && !suffix.equals("f32") && !suffix.equals("f64"))
+            throw new LiteralParseException(LiteralParseException.Kind.INVALID_SUFFIX, i,
+                    "Invalid suffix for float literal: " + suffix);
+        if (token.type == TokenType.INT_LITERAL_DEC && suffix.isEmpty())
+            throw new LiteralParseException(LiteralParseException.Kind.WRONG_TOKEN_TYPE, 0,
+                    "Integer literal without a float suffix");
+
+        try {
+            return parseFloat(number.toString(), suffix.equals("f32"));
+        } catch (NumberFormatException e) {
+            throw new LiteralParseException(LiteralParseException.Kind.NO_DIGITS, i, "Malformed float literal");
+        }
+    }
+
+    private static Number parseFloat(String number, boolean isF32) throws LiteralParseException {
+        if (isF32) {
+            float value = Float.parseFloat(number);
+            if (Float.isInfinite(value))
+                throw new LiteralParseException(LiteralParseException.Kind.OVERFLOW, 0,
+                        "Float literal is too large for f32");
+            return value;
+        } else {
+            double value = Double.parseDouble(number);
+            if (Double.isInfinite(value))
+                throw new LiteralParseException(LiteralParseException.Kind.OVERFLOW, 0,
+                        "Float literal is too large for f64");
+            return value;
+        }
+    }
+
+    /**
+     * Canonical rendering of a number literal with the {@link CanonicalStyle#DEFAULT default style}.
+     */
+    public Canonical canonicalize() throws LiteralParseException {
+        return canonicalize(CanonicalStyle.DEFAULT);
+    }
+
+    /**
+     * Renders a number literal the same way every time: lowercase radix prefix and exponent, digits grouped
+     * with underscores, no leading zeros, and "2." written as "2.0". The value is parsed (and checked) first.
+     */
+    public Canonical canonicalize(CanonicalStyle style) throws LiteralParseException {
+        int radix = radix();
+        if (radix == 0 && token.type != TokenType.FLOAT_LITERAL)
+            throw new LiteralParseException(LiteralParseException.Kind.WRONG_TOKEN_TYPE, 0,
+                    "Not a number literal: " + token.type);
+        String suffix = suffix();
+        boolean isFloat = token.type == TokenType.FLOAT_LITERAL || suffix.equals("f32") || suffix.equals("f64");
+        StringBuilder text = new StringBuilder();
+        Number value;
+        if (!isFloat) {
+            BigInteger intValue = parseInt();
+            value = intValue;
+            String digits = intValue.toString(radix);
+            if (style.upperCaseHex)
+                digits = digits.toUpperCase(Locale.ROOT);
+            text.append(switch (radix) {
+                case 16 -> "0x";
+                case 8 -> "0o";
+                case 2 -> "0b";
+                default -> "";
+            });
+            text.append(style.group(digits, style.groupSize(radix), false));
+        } else {
+            value = parseFloat();
+            String number = token.text().substring(0, token.text().length() - suffix.length()).replace("_", "");
+            int exponent = Math.max(number.indexOf('e'), number.indexOf('E'));
+            String mantissa = exponent >= 0 ? number.substring(0, exponent) : number;
+            int dot = mantissa.indexOf('.');
+            String integer = dot >= 0 ? mantissa.substring(0, dot) : mantissa;
+            integer = integer.replaceFirst("^0+(?=.)", "");
+            text.append(style.group(integer, style.groupSize(10), false));
+            if (dot >= 0) {
+                String fraction = mantissa.substring(dot + 1);
+                text.append('.').append(fraction.isEmpty() ? "0" : style.group(fraction, style.groupSize(10), true));
+            }
+            if (exponent >= 0) {
+                String exponentDigits = number.substring(exponent + 1);
+                boolean negative = exponentDigits.startsWith("-");
+                if (negative || exponentDigits.startsWith("+"))
+                    exponentDigits = exponentDigits.substring(1);
+                text.append('e').append(negative ? "-" : "").append(exponentDigits.replaceFirst("^0+(?=.)", ""));
+            }
+        }
+        if (suffix.isEmpty() && style.defaultSuffixes)
+            suffix = isFloat ? "f64" : "i32";
+        text.append(suffix);
+        return new Canonical(text.toString(), value);
+    }
+
+    /**
+     * The literal's suffix, e.g. "u8" for 1u8 or "x" for "abc"x, empty if it has none.
+     * Suffixes on string and char literals are lexed, but only allowed in macro input.
+     */
+    public String suffix() {
+        if (suffix == null)
+            suffix = readSuffix();
+        return suffix;
+    }
+
+    private String readSuffix() {
+        String text = token.text();
+        int i;
+        switch (token.type) {
+            case CHAR_LITERAL, BYTE_LITERAL -> i = text.lastIndexOf('\'') + 1;
+            case STRING_LITERAL, BYTE_STRING_LITERAL -> i = text.lastIndexOf('"') + 1;
+            case RAW_STRING_LITERAL, RAW_BYTE_STRING_LITERAL -> {
+                i = text.lastIndexOf('"') + 1;
+                while (i < text.length() && text.charAt(i) == '#')
+                    i++;
+            }
+            case INT_LITERAL_HEX -> {
+                i = 2;
+                while (i < text.length() && (text.charAt(i) == '_' || Character.digit(text.charAt(i), 16) >= 0))
+                    i++;
+            }
+            case INT_LITERAL_OCTAL, INT_LITERAL_BIN, INT_LITERAL_DEC, FLOAT_LITERAL -> {
+                i = token.type == TokenType.INT_LITERAL_DEC || token.type == TokenType.FLOAT_LITERAL ? 0 : 2;
+                for (; i < text.length(); i++) {
+                    char c = text.charAt(i);
+                    if ((c == 'e' || c == 'E') && i + 1 < text.length() && isExponentStart(text.charAt(i + 1)))
+                        i++;
+                    else if (!(c >= '0' && c <= '9') && c != '_' && c != '.')
+                        break;
+                }
+            }
+            default -> throw new IllegalStateException("Not a literal: " + token.type);
+        }
+        return text.substring(i);
+    }
+
+    /**
+     * Splits a float literal like 0.1 into 0, "." and 1, for nested tuple fields like x.0.1, which the lexer can't
+     * tell apart from a float. "1." gives 1 and ".".
+     *
+     * @return the parts, or null if the literal isn't just digits around a dot
+     */
+    public List<Token> splitTupleIndex() {
+        if (token.type != TokenType.FLOAT_LITERAL)
+            return null;
+        String text = token.text();
+        int dot = text.indexOf('.');
+        if (dot <= 0)
+            return null;
+        for (int i = 0; i < text.length(); i++) {
+            if (i != dot && (text.charAt(i) < '0' || text.charAt(i) > '9'))
+                return null;
+        }
+
+        ArrayList<Token> parts = new ArrayList<>(3);
+        parts.add(part(TokenType.INT_LITERAL_DEC, text.substring(0, dot), 0));
+        parts.add(part(TokenType.DOT, null, dot));
+        if (dot + 1 < text.length())
+            parts.add(part(TokenType.INT_LITERAL_DEC, text.substring(dot + 1), dot + 1));
+        return parts;
+    }
+
+    private Token part(TokenType type, String value, int start) {
+        if (token.isSynthetic())
+            return Token.synthetic(type, value);
+        int length = value != null ? value.length() : type.text.length();
+        return new Token(token.line, token.column + start, token.offset + start, length, type, value,
+                token.flags() & Token.PROVENANCE_FLAGS);
+    }
+
+    private static boolean isExponentStart(char c) {
+        return (c >= '0' && c <= '9') || c == '+' || c == '-' || c == '_';
+    }
+
+    /**
+     * Escape sequences in a char, byte, string or byte string literal, in order.
+     * Malformed escapes (which the lexer reports as errors) are skipped.
+     */
+    public List<EscapeSequence> escapes() {
+        ArrayList<EscapeSequence> escapes = new ArrayList<>();
+        char quote;
+        switch (token.type) {
+            case CHAR_LITERAL, BYTE_LITERAL -> quote = '\'';
+            case STRING_LITERAL, BYTE_STRING_LITERAL -> quote = '"';
+            default -> {
+                return escapes;
+            }
+        }
+
+        String text = token.text();
+        int end = text.lastIndexOf(quote);
+        int i = text.indexOf(quote) + 1;
+        while (i < end) {
+            if (text.charAt(i) != '\\') {
+                i++;
+                continue;
+            }
+            EscapeSequence escape = readEscape(text, i, end);
+            if (escape == null) {
+                i += 2;
+            } else {
+                escapes.add(escape);
+                i = escape.end;
+            }
+        }
+        return escapes;
+    }
+
+    /**
+     * The text the literal stands for, with escapes replaced by their chars and line continuations taken out.
+     * Bytes of byte literals are chars from 0 to 255. Raw strings are returned as they are, and malformed escapes
+     * (which the lexer reports as errors) are kept as written.
+     */
+    public String unescape() {
+        String text = token.text();
+        String suffix = suffix();
+        switch (token.type) {
+            case RAW_STRING_LITERAL, RAW_BYTE_STRING_LITERAL -> {
+                int open = text.indexOf('"');
+                return text.substring(open + 1, text.length() - suffix.length() - hashCount() - 1);
+            }
+            case CHAR_LITERAL, BYTE_LITERAL, STRING_LITERAL, BYTE_STRING_LITERAL -> {
+            }
+            default -> throw new IllegalStateException("Not a char or string literal: " + token.type);
+        }
+        char quote = token.type == TokenType.CHAR_LITERAL || token.type == TokenType.BYTE_LITERAL ? '\'' : '"';
+        int start = text.indexOf(quote) + 1;
+        int end = text.length() - suffix.length() - 1;
+        StringBuilder value = new StringBuilder(end - start);
+        int i = start;
+        for (EscapeSequence escape : escapes()) {
+            value.append(text, i, escape.start);
+            if (escape.kind != EscapeSequence.Kind.LINE_CONTINUATION)
+                value.appendCodePoint(escape.value);
+            i = escape.end;
+        }
+        value.append(text, i, end);
+        return value.toString();
+    }
+
+    /**
+     * Checks a string or byte string literal lexed without {@link LexerOptions.Builder#validateLiterals validation}.
+     *
+     * @return the errors the lexer would have reported inside the literal, positioned in the token's source
+     * @throws IllegalStateException if the token was lexed without {@link LexerOptions.Builder#storeText storing
+     *                               its text}, {@link TokenStream#intoOwned()} can fill it in
+     */
+    public List<LexError> validate() {
+        if (token.type != TokenType.STRING_LITERAL && token.type != TokenType.BYTE_STRING_LITERAL)
+            return List.of();
+        if (token.text() == null)
+            throw new IllegalStateException("Literal at " + token.line + ":" + token.column
+                    + " has no text, it was lexed without storing text");
+        ArrayList<LexError> errors = new ArrayList<>();
+        for (LexError error : Lexer.tokenize(token.text()).errors) {
+            int column = error.line == 0 ? token.column + error.column : error.column;
+            errors.add((LexError) error.withPosition(token.line + error.line, column, token.offset + error.offset));
+        }
+        return errors;
+    }
+
+    private static EscapeSequence readEscape(String text, int start, int end) {
+        if (start + 1 >= end)
+            return null;
+        char c = text.charAt(start + 1);
+        return switch (c) {
+            case 'n' -> new EscapeSequence(EscapeSequence.Kind.SIMPLE, '\n', start, start + 2);
+            case 'r' -> new EscapeSequence(EscapeSequence.Kind.SIMPLE, '\r', start, start + 2);
+            case 't' -> new EscapeSequence(EscapeSequence.Kind.SIMPLE, '\t', start, start + 2);
+            case '0' -> new EscapeSequence(EscapeSequence.Kind.SIMPLE, 0, start, start + 2);
+            case '\\', '\'', '"' -> new EscapeSequence(EscapeSequence.Kind.SIMPLE, c, start, start + 2);
+            case 'x' -> {
+                int value = start + 4 > end ? -1 : parseHex(text, start + 2, start + 4);
+                yield value < 0 ? null : new EscapeSequence(EscapeSequence.Kind.HEX, value, start, start + 4);
+            }
+            case 'u' -> {
+                int close = text.indexOf('}', start);
+                if (start + 2 >= end || text.charAt(start + 2) != '{' || close < 0 || close >= end)
+                    yield null;
+                int value = parseHex(text, start + 3, close);
+                long digits = text.substring(start + 3, close).chars().filter(d -> d != '_').count();
+                if (value < 0 || value > 0x10FFFF || (value >= 0xD800 && value <= 0xDFFF) || digits > 6)
+                    yield null;
+                yield new EscapeSequence(EscapeSequence.Kind.UNICODE, value, start, close + 1);
+            }
+            case '\n', '\r' -> {
+                int i = start + 1;
+                if (c == '\r' && (i + 1 >= end || text.charAt(i + 1) != '\n'))
+                    yield null;
+                //only ASCII whitespace is skipped, like rustc does
+                while (i < end && isContinuationWhitespace(text.charAt(i)))
+                    i++;
+                yield new EscapeSequence(EscapeSequence.Kind.LINE_CONTINUATION, -1, start, i);
+            }
+            default -> null;
+        };
+    }
+
+    static boolean isContinuationWhitespace(char c) {
+        return c == ' ' || c == '\t' || c == '\n' || c == '\r';
+    }
+
+    private static int parseHex(String text, int start, int end) {
+        int value = 0;
+        boolean hasDigits = false;
+        for (int i = start; i < end; i++) {
+            char c = text.charAt(i);
+            if (c == '_')
+                continue;
+            int digit = Character.digit(c, 16);
+            if (digit < 0 || value > 0x10FFFF)
+                return -1;
+            value = value * 16 + digit;
+            hasDigits = true;
+        }
+        return hasDigits ? value : -1;
+    }
+
+    private int radix() {
+        return switch (token.type) {
+            case INT_LITERAL_DEC -> 10;
+            case INT_LITERAL_HEX -> 16;
+            case INT_LITERAL_OCTAL -> 8;
+            case INT_LITERAL_BIN -> 2;
+            default -> 0;
+        };
+    }
+
+    public static final class Canonical {
+        public final String text;
+        /**
+         * A BigInteger for integers, a Float for f32 literals and a Double for other floats
+         */
+        public final Number value;
+
+        private Canonical(String text, Number value) {
+            this.text = text;
+            this.value = value;
+        }
+
+        @Override
+        public String toString() {
+            return text;
+        }
+    }
+
+    /**
+     * How {@link #canonicalize(CanonicalStyle)} writes literals, create it with {@link #builder()}.
+     */
+    public static final class CanonicalStyle {
+        public static final CanonicalStyle DEFAULT = builder().build();
+
+        private final boolean upperCaseHex;
+        private final int decimalGroup;
+        private final int hexGroup;
+        private final int octalGroup;
+        private final int binaryGroup;
+        private final int minGroupedDigits;
+        private final boolean defaultSuffixes;
+
+        private CanonicalStyle(Builder builder) {
+            this.upperCaseHex = builder.upperCaseHex;
+            this.decimalGroup = builder.decimalGroup;
+            this.hexGroup = builder.hexGroup;
+            this.octalGroup = builder.octalGroup;
+            this.binaryGroup = builder.binaryGroup;
+            this.minGroupedDigits = builder.minGroupedDigits;
+            this.defaultSuffixes = builder.defaultSuffixes;
+        }
+
+        public static Builder builder() {
+            return new Builder();
+        }
+
+        private int groupSize(int radix) {
+            return switch (radix) {
+                case 16 -> hexGroup;
+                case 8 -> octalGroup;
+                case 2 -> binaryGroup;
+                default -> decimalGroup;
+            };
+        }
+
+        /**
+         * @param fromStart group a fraction from its first digit rather than from the last
+         */
+        private String group(String digits, int size, boolean fromStart) {
+            if (size == 0 || digits.length() < minGroupedDigits)
+                return digits;
+            StringBuilder grouped = new StringBuilder(digits.length() + digits.length() / size);
+            int first = fromStart ? size : (digits.length() - 1) % size + 1;
+            for (int i = 0; i < digits.length(); i++) {
+                if (i > 0 && (i - first) % size == 0)
+                    grouped.append('_');
+                grouped.append(digits.charAt(i));
+            }
+            return grouped.toString();
+        }
+
+        public static final class Builder {
+            private boolean upperCaseHex = false;
+            private int decimalGroup = 3;
+            private int hexGroup = 4;
+            private int octalGroup = 3;
+            private int binaryGroup = 4;
+            private int minGroupedDigits = 5;
+            private boolean defaultSuffixes = false;
+
+            private Builder() {
+            }
+
+            /**
+             * Write hex digits as A-F rather than a-f, off by default. The 0x prefix is always lowercase.
+             */
+            public Builder upperCaseHex(boolean upperCaseHex) {
+                this.upperCaseHex = upperCaseHex;
+                return this;
+            }
+
+            /**
+             * Digits between underscores for a radix, 0 for no underscores. By default that's 3 for decimal and
+             * octal numbers and 4 for hex and binary ones.
+             */
+            public Builder groupSize(int radix, int size) {
+                if (size < 0)
+                    throw new IllegalArgumentException("Group size can't be negative");
+                switch (radix) {
+                    case 10 -> decimalGroup = size;
+                    case 16 -> hexGroup = size;
+                    case 8 -> octalGroup = size;
+                    case 2 -> binaryGroup = size;
+                    default -> throw new IllegalArgumentException("Not a Rust literal radix: " + radix);
+                }
+                return this;
+            }
+
+            /**
+             * Shorter runs of digits aren't grouped, 5 by default, so 1000 stays as it is and 10000 becomes 10_000.
+             */
+            public Builder minGroupedDigits(int minGroupedDigits) {
+                this.minGroupedDigits = minGroupedDigits;
+                return this;
+            }
+
+            /**
+             * Add i32 or f64 to literals without a suffix, the types Rust falls back to when nothing else decides.
+             * Off by default, since the code around the literal can make it another type.
+             */
+            public Builder defaultSuffixes(boolean defaultSuffixes) {
+                this.defaultSuffixes = defaultSuffixes;
+                return this;
+            }
+
+            public CanonicalStyle build() {
+                return new CanonicalStyle(this);
+            }
+        }
+    }
+
+    public static final class EscapeSequence {
+        public enum Kind {
+            /**
+             * One of {@code \n \r \t \\ \0 \' \"}
+             */
+            SIMPLE,
+            /**
+             * Two hex digits, e.g. {@code \x7F}
+             */
+            HEX,
+            /**
+             * Up to six hex digits in braces
+             */
+            UNICODE,
+            /**
+             * Backslash before a newline in a string, which skips the newline and the following whitespace: the
+             * escape's range is the text that's taken out. Only spaces, tabs and line breaks are skipped, other
+             * Unicode whitespace stays in the string.
+             */
+            LINE_CONTINUATION
+        }
+
+        public final Kind kind;
+        /**
+         * Code point (or byte value) the escape stands for, -1 for line continuations
+         */
+        public final int value;
+        /**
+         * Range in the token text, end is exclusive
+         */
+        public final int start;
+        public final int end;
+
+        EscapeSequence(Kind kind, int value, int start, int end) {
+            this.kind = kind;
+            this.value = value;
+            this.start = start;
+            this.end = end;
+        }
+    }
+}
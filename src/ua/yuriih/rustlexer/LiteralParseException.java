@@ -0,0 +1,25 @@
+package ua.yuriih.rustlexer;
+
+public class LiteralParseException extends Exception {
+    private static final long serialVersionUID = 1L;
+
+    public enum Kind {
+        WRONG_TOKEN_TYPE,
+        NO_DIGITS,
+        INVALID_DIGIT,
+        INVALID_SUFFIX,
+        OVERFLOW
+    }
+
+    public final Kind kind;
+    /**
+     * Index in the token text where the problem was found
+     */
+    public final int index;
+
+    public LiteralParseException(Kind kind, int index, String message) {
+        super(message);
+        this.kind = kind;
+        this.index = index;
+    }
+}
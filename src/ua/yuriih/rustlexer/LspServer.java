@@ -0,0 +1,419 @@
+package ua.yuriih.rustlexer;
+
+import java.io.ByteArrayOutputStream;
+import java.io.IOException;
+import java.io.InputStream;
+import java.io.OutputStream;
+import java.io.UncheckedIOException;
+import java.nio.charset.StandardCharsets;
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.HashMap;
+import java.util.LinkedHashMap;
+import java.util.List;
+import java.util.Locale;
+import java.util.Map;
+
+/**
+ * rlex lsp: a language server on stdin and stdout, as a fallback highlighter and checker for editors without Rust
+ * support. It provides semantic tokens, full and for a range, and publishes lexical errors as diagnostics.
+ * <p>
+ * Documents are synced incrementally, and an edit within one line only re-lexes that line if it can,
+ * see {@link TokenStream#relexLine}. Positions are in UTF-16 code units, the LSP default.
+ */
+final class LspServer {
+    static final List<String> TOKEN_TYPES = List.of("keyword", "comment", "string", "number", "operator",
+            "typeParameter");
+    static final List<String> TOKEN_MODIFIERS = List.of("documentation");
+
+    private static final int PARSE_ERROR = -32700;
+    private static final int INVALID_REQUEST = -32600;
+    private static final int METHOD_NOT_FOUND = -32601;
+    private static final int INVALID_PARAMS = -32602;
+    private static final int SERVER_NOT_INITIALIZED = -32002;
+
+    private final InputStream in;
+    private final OutputStream out;
+    private final LexerOptions options = LexerOptions.DEFAULT;
+    private final HashMap<String, Document> documents = new HashMap<>();
+    private boolean initialized = false;
+    private boolean shutdown = false;
+
+    LspServer(InputStream in, OutputStream out) {
+        this.in = in;
+        this.out = out;
+    }
+
+    /**
+     * Serves requests until the client sends "exit" or closes the input.
+     *
+     * @return exit status: 0 if the client asked to shut down first, 1 otherwise
+     */
+    int run() throws IOException {
+        while (true) {
+            String message = readMessage();
+            if (message == null)
+                return 1;
+
+            Object parsed;
+            try {
+                parsed = Json.parse(message);
+            } catch (IllegalArgumentException e) {
+                sendError(null, PARSE_ERROR, e.getMessage());
+                continue;
+            }
+            if (!(parsed instanceof Map) || !(((Map<?, ?>) parsed).get("method") instanceof String)) {
+                sendError(parsed instanceof Map ? ((Map<?, ?>) parsed).get("id") : null, INVALID_REQUEST,
+                        "Not a request");
+                continue;
+            }
+            Map<?, ?> request = (Map<?, ?>) parsed;
+            Object id = request.get("id");
+            String method = (String) request.get("method");
+            if (method.equals("exit"))
+                return shutdown ? 0 : 1;
+
+            Object params = request.get("params");
+            try {
+                Object result = handle(method, params instanceof Map ? (Map<?, ?>) params : Map.of(), id == null);
+                if (id != null) {
+                    LinkedHashMap<String, Object> response = new LinkedHashMap<>();
+                    response.put("jsonrpc", "2.0");
+                    response.put("id", id);
+                    response.put("result", result);
+                    send(response);
+                }
+            } catch (ResponseError e) {
+                if (id != null)
+                    sendError(id, e.code, e.getMessage());
+            } catch (ClassCastException | NullPointerException | IndexOutOfBoundsException e) {
+                if (id != null)
+                    sendError(id, INVALID_PARAMS, "Invalid params for " + method);
+            }
+        }
+    }
+
+    /**
+     * @param notification there's no id to respond to, so unknown methods and ones sent before "initialize" are
+     *                     ignored
+     */
+    private Object handle(String method, Map<?, ?> params, boolean notification) throws ResponseError {
+        if (!initialized && !method.equals("initialize")) {
+            if (notification)
+                return null;
+            throw new ResponseError(SERVER_NOT_INITIALIZED, "Not initialized");
+        }
+        switch (method) {
+            case "initialize" -> {
+                initialized = true;
+                return capabilities();
+            }
+            case "shutdown" -> {
+                shutdown = true;
+                return null;
+            }
+            case "textDocument/didOpen" -> {
+                Map<?, ?> item = (Map<?, ?>) params.get("textDocument");
+                String uri = (String) item.get("uri");
+                documents.put(uri, new Document(lex((String) item.get("text"))));
+                publishDiagnostics(uri);
+                return null;
+            }
+            case "textDocument/didChange" -> {
+                String uri = uri(params);
+                Document document = document(uri);
+                for (Object change : (List<?>) params.get("contentChanges"))
+                    applyChange(document, (Map<?, ?>) change);
+                publishDiagnostics(uri);
+                return null;
+            }
+            case "textDocument/didClose" -> {
+                String uri = uri(params);
+                documents.remove(uri);
+                sendNotification("textDocument/publishDiagnostics", Map.of("uri", uri, "diagnostics", List.of()));
+                return null;
+            }
+            case "textDocument/semanticTokens/full" -> {
+                Document document = document(uri(params));
+                return Map.of("data", semanticTokens(document, 0, Integer.MAX_VALUE));
+            }
+            case "textDocument/semanticTokens/range" -> {
+                Document document = document(uri(params));
+                Map<?, ?> range = (Map<?, ?>) params.get("range");
+                return Map.of("data", semanticTokens(document, document.offset((Map<?, ?>) range.get("start")),
+                        document.offset((Map<?, ?>) range.get("end"))));
+            }
+            default -> {
+                //notifications like "initialized" and "$/cancelRequest" can be ignored
+                if (notification)
+                    return null;
+                throw new ResponseError(METHOD_NOT_FOUND, "Unknown method: " + method);
+            }
+        }
+    }
+
+    private static Map<String, Object> capabilities() {
+        LinkedHashMap<String, Object> semanticTokens = new LinkedHashMap<>();
+        semanticTokens.put("legend", Map.of("tokenTypes", TOKEN_TYPES, "tokenModifiers", TOKEN_MODIFIERS));
+        semanticTokens.put("full", true);
+        semanticTokens.put("range", true);
+
+        LinkedHashMap<String, Object> capabilities = new LinkedHashMap<>();
+        //incremental
+        capabilities.put("textDocumentSync", Map.of("openClose", true, "change", 2));
+        capabilities.put("semanticTokensProvider", semanticTokens);
+        return Map.of("capabilities", capabilities, "serverInfo", Map.of("name", "rlex"));
+    }
+
+    private static String uri(Map<?, ?> params) {
+        return (String) ((Map<?, ?>) params.get("textDocument")).get("uri");
+    }
+
+    private Document document(String uri) throws ResponseError {
+        Document document = documents.get(uri);
+        if (document == null)
+            throw new ResponseError(INVALID_PARAMS, "Document isn't open: " + uri);
+        return document;
+    }
+
+    private TokenStream lex(String source) {
+        try {
+            return TokenStream.lex(source, options);
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+    }
+
+    private void applyChange(Document document, Map<?, ?> change) {
+        String text = (String) change.get("text");
+        Map<?, ?> range = (Map<?, ?>) change.get("range");
+        if (range == null) {
+            document.update(lex(text));
+            return;
+        }
+
+        String source = document.tokens.source();
+        Map<?, ?> startPosition = (Map<?, ?>) range.get("start");
+        int start = document.offset(startPosition);
+        int end = Math.max(start, document.offset((Map<?, ?>) range.get("end")));
+        int line = ((Number) startPosition.get("line")).intValue();
+        TokenStream relexed = null;
+        if (line < document.lineStarts.length && text.indexOf('\n') < 0 && text.indexOf('\r') < 0
+                && source.substring(start, end).indexOf('\n') < 0) {
+            int lineStart = document.lineStarts[line];
+            int lineEnd = source.indexOf('\n', lineStart);
+            if (lineEnd < 0)
+                lineEnd = source.length();
+            String newLine = source.substring(lineStart, start) + text + source.substring(end, lineEnd);
+            relexed = document.tokens.relexLine(line, newLine, options);
+        }
+        document.update(relexed != null ? relexed : lex(source.substring(0, start) + text + source.substring(end)));
+    }
+
+    /**
+     * Tokens overlapping [from, to), encoded as LSP wants them. Tokens spanning several lines are split,
+     * since clients don't have to support multiline tokens.
+     */
+    private static List<Integer> semanticTokens(Document document, int from, int to) {
+        ArrayList<Integer> data = new ArrayList<>();
+        int previousLine = 0;
+        int previousColumn = 0;
+        for (Token token : document.tokens) {
+            int type = semanticType(token.type);
+            if (type < 0 || token.isSynthetic() || token.end() <= from || token.offset >= to)
+                continue;
+            int modifiers = token.type == TokenType.COMMENT_INNER_DOC || token.type == TokenType.COMMENT_OUTER_DOC
+                    ? 1 : 0;
+            CharSequence text = document.tokens.text(token);
+            int line = token.line;
+            int column = token.column;
+            int pieceStart = 0;
+            for (int i = 0; i <= text.length(); i++) {
+                if (i < text.length() && text.charAt(i) != '\n')
+                    continue;
+                int pieceEnd = i;
+                if (pieceEnd > pieceStart && text.charAt(pieceEnd - 1) == '\r')
+                    pieceEnd--;
+                if (pieceEnd > pieceStart) {
+                    data.add(line - previousLine);
+                    data.add(line == previousLine ? column - previousColumn : column);
+                    data.add(pieceEnd - pieceStart);
+                    data.add(type);
+                    data.add(modifiers);
+                    previousLine = line;
+                    previousColumn = column;
+                }
+                line++;
+                column = 0;
+                pieceStart = i + 1;
+            }
+        }
+        return data;
+    }
+
+    /**
+     * @return index in {@link #TOKEN_TYPES}, or -1 for tokens left to the editor, like identifiers
+     */
+    private static int semanticType(TokenType type) {
+        String scope = TextMateScopes.DEFAULT.scope(type);
+        return switch (type) {
+            case COMMENT, COMMENT_INNER_DOC, COMMENT_OUTER_DOC, SHEBANG -> 1;
+            case CHAR_LITERAL, BYTE_LITERAL, STRING_LITERAL, BYTE_STRING_LITERAL, RAW_STRING_LITERAL,
+                    RAW_BYTE_STRING_LITERAL -> 2;
+            case INT_LITERAL_DEC, INT_LITERAL_HEX, INT_LITERAL_OCTAL, INT_LITERAL_BIN, FLOAT_LITERAL -> 3;
+            case LIFETIME, RAW_LIFETIME, STATIC_LIFETIME -> 5;
+            case TRUE, FALSE, SELF_VALUE, SELF_TYPE -> 0;
+            default -> {
+                if (type.isKeyword())
+                    yield 0;
+                yield scope != null && scope.startsWith("keyword.operator") ? 4 : -1;
+            }
+        };
+    }
+
+    private void publishDiagnostics(String uri) {
+        Document document = documents.get(uri);
+        ArrayList<Object> diagnostics = new ArrayList<>();
+        for (Token token : document.tokens) {
+            if (token.type != TokenType.ERROR || token.isSynthetic())
+                continue;
+            LinkedHashMap<String, Object> diagnostic = new LinkedHashMap<>();
+            diagnostic.put("range", Map.of("start", document.position(token.offset),
+                    "end", document.position(token.end())));
+            //error
+            diagnostic.put("severity", 1);
+            if (token instanceof LexError)
+                diagnostic.put("code", ((LexError) token).kind.code);
+            diagnostic.put("source", "rlex");
+            diagnostic.put("message", token.value);
+            diagnostics.add(diagnostic);
+        }
+        sendNotification("textDocument/publishDiagnostics", Map.of("uri", uri, "diagnostics", diagnostics));
+    }
+
+    /**
+     * @return the message's content, or null at the end of the input
+     */
+    private String readMessage() throws IOException {
+        int length = -1;
+        while (true) {
+            String header = readHeaderLine();
+            if (header == null)
+                return null;
+            if (header.isEmpty()) {
+                if (length >= 0)
+                    break;
+                continue;
+            }
+            int colon = header.indexOf(':');
+            if (colon > 0 && header.substring(0, colon).trim().toLowerCase(Locale.ROOT).equals("content-length")) {
+                try {
+                    length = Integer.parseInt(header.substring(colon + 1).trim());
+                } catch (NumberFormatException e) {
+                    throw new IOException("Invalid Content-Length: " + header);
+                }
+            }
+        }
+        byte[] content = in.readNBytes(length);
+        if (content.length < length)
+            return null;
+        return new String(content, StandardCharsets.UTF_8);
+    }
+
+    private String readHeaderLine() throws IOException {
+        ByteArrayOutputStream line = new ByteArrayOutputStream();
+        while (true) {
+            int b = in.read();
+            if (b < 0)
+                return null;
+            if (b == '\n')
+                break;
+            if (b != '\r')
+                line.write(b);
+        }
+        return line.toString(StandardCharsets.US_ASCII);
+    }
+
+    private void sendNotification(String method, Object params) {
+        LinkedHashMap<String, Object> notification = new LinkedHashMap<>();
+        notification.put("jsonrpc", "2.0");
+        notification.put("method", method);
+        notification.put("params", params);
+        send(notification);
+    }
+
+    private void sendError(Object id, int code, String message) {
+        LinkedHashMap<String, Object> response = new LinkedHashMap<>();
+        response.put("jsonrpc", "2.0");
+        response.put("id", id);
+        response.put("error", Map.of("code", code, "message", message));
+        send(response);
+    }
+
+    private void send(Object message) {
+        byte[] content = Json.write(message).getBytes(StandardCharsets.UTF_8);
+        try {
+            out.write(("Content-Length: " + content.length + "\r\n\r\n").getBytes(StandardCharsets.US_ASCII));
+            out.write(content);
+            out.flush();
+        } catch (IOException e) {
+            throw new UncheckedIOException(e);
+        }
+    }
+
+    private static final class Document {
+        private TokenStream tokens;
+        private int[] lineStarts;
+
+        private Document(TokenStream tokens) {
+            update(tokens);
+        }
+
+        private void update(TokenStream tokens) {
+            this.tokens = tokens;
+            String source = tokens.source();
+            int[] starts = new int[16];
+            int count = 1;
+            for (int i = 0; i < source.length(); i++) {
+                if (source.charAt(i) != '\n')
+                    continue;
+                if (count == starts.length)
+                    starts = Arrays.copyOf(starts, count * 2);
+                starts[count++] = i + 1;
+            }
+            lineStarts = Arrays.copyOf(starts, count);
+        }
+
+        /**
+         * Positions past the end of a line or of the document are clamped, like the spec says.
+         */
+        private int offset(Map<?, ?> position) {
+            int line = ((Number) position.get("line")).intValue();
+            int character = ((Number) position.get("character")).intValue();
+            String source = tokens.source();
+            if (line >= lineStarts.length)
+                return source.length();
+            int lineEnd = line + 1 < lineStarts.length ? lineStarts[line + 1] - 1 : source.length();
+            return Math.min(lineStarts[Math.max(line, 0)] + Math.max(character, 0), lineEnd);
+        }
+
+        private Map<String, Integer> position(int offset) {
+            int line = Arrays.binarySearch(lineStarts, offset);
+            if (line < 0)
+                line = -line - 2;
+            return Map.of("line", line, "character", offset - lineStarts[line]);
+        }
+    }
+
+    private static final class ResponseError extends Exception {
+        private static final long serialVersionUID = 1L;
+
+        private final int code;
+
+        private ResponseError(int code, String message) {
+            super(message);
+            this.code = code;
+        }
+    }
+}
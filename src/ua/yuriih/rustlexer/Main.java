@@ -2,23 +2,359 @@ package ua.yuriih.rustlexer;
 
 import org.fusesource.jansi.AnsiConsole;
 
-import java.io.BufferedInputStream;
 import java.io.ByteArrayInputStream;
-import java.io.FileInputStream;
 import java.io.IOException;
+import java.io.InputStream;
+import java.nio.charset.StandardCharsets;
 import java.nio.file.Files;
 import java.nio.file.Path;
 import java.util.ArrayList;
+import java.util.LinkedHashMap;
 import java.util.List;
+import java.util.Locale;
 
 import static org.fusesource.jansi.Ansi.ansi;
 
 public class Main {
-    public static void main(String[] args) throws IOException {
+    private static final String USAGE = String.join("\n",
+            "Usage:",
+            "  rlex <file> [--theme <name|file.toml>] [--format text|html|explorer|dot|sarif]",
+            "                        print the file with syntax highlighting and list lexical errors;",
+            "                        themes: terminal (default), light, dark, high-contrast;",
+            "                        explorer is an HTML page for browsing the token tree",
+            "  rlex <dir> [--exclude <glob>]... [--format text|sarif]",
+            "                        lex every .rs file under dir (respecting .gitignore and .ignore)",
+            "                        and list lexical errors",
+            "  rlex tokenize <file|-> [--filename <name>] [--format text|jsonl|sarif]",
+            "                        print one token per line, reading stdin if the file is -;",
+            "                        the file name is used in error messages",
+            "  rlex check <path>... [--quiet] [--deny-warnings]",
+            "                        lex files and every .rs file under directories and list errors and warnings;",
+            "                        exits with 0 if everything is clean, 1 if there are warnings, 2 if there are",
+            "                        errors (or warnings, with --deny-warnings)",
+            "  rlex fix <path>... [--dry-run]",
+            "                        strip byte order marks, use \\n line endings, remove trailing whitespace and",
+            "                        apply warning suggestions, in files and every .rs file under directories;",
+            "                        --dry-run prints a diff instead of changing files",
+            "  rlex explain <file>:<line>:<column>",
+            "                        print the token at the position: type, span, text, literal value and suffix,",
+            "                        and the comments and whitespace attached to it",
+            "  rlex watch <path>     re-lex .rs files under path whenever they change",
+            "  rlex bench <path> [--iterations <n>] [--warmup <n>] [--trivia] [--exclude <glob>]... [--json]",
+            "             [--token-buffer]",
+            "                        lex a file or every .rs file under a directory repeatedly and report",
+            "                        MB/s, tokens/s, peak RSS and the slowest files, or with --token-buffer",
+            "                        compare tokens/s and bytes per token of List<Token> and TokenBuffer",
+            "  rlex lsp              run a language server on stdin and stdout, with semantic tokens",
+            "                        and lexical errors as diagnostics",
+            "  rlex serve [--port <n>]",
+            "                        lex text and files on request, with JSON-RPC messages one per line on stdin",
+            "                        and stdout, or on TCP connections to localhost with --port (0 for any port)"
+    );
+
+    public static void main(String[] args) throws IOException, InterruptedException {
         //Might be necessary to get colored output in IntelliJ IDEA console
 //        System.setProperty(AnsiConsole.JANSI_MODE, AnsiConsole.JANSI_MODE_FORCE);
 
-        byte[] file = Files.readAllBytes(Path.of(args[0]));
+        if (args.length == 0) {
+            System.err.println(USAGE);
+            System.exit(2);
+        }
+
+        switch (args[0]) {
+            case "watch" -> {
+                if (args.length != 2) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                System.exit(new WatchCommand(System.out).run(Path.of(args[1])));
+            }
+            case "lsp" -> {
+                if (args.length != 1) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                System.exit(new LspServer(System.in, System.out).run());
+            }
+            case "serve" -> {
+                int port = -1;
+                if (args.length == 3 && args[1].equals("--port")) {
+                    try {
+                        port = Integer.parseInt(args[2]);
+                    } catch (NumberFormatException e) {
+                        System.err.println("Not a number: " + e.getMessage());
+                        System.exit(2);
+                    }
+                } else if (args.length != 1) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                if (args.length == 3 && (port < 0 || port > 65535)) {
+                    System.err.println("Not a port: " + args[2]);
+                    System.exit(2);
+                }
+                System.exit(port >= 0 ? ServeCommand.listen(port, System.err)
+                        : new ServeCommand(System.in, System.out).run());
+            }
+            case "bench" -> {
+                Path path = null;
+                ArrayList<String> excludes = new ArrayList<>();
+                int iterations = 10;
+                int warmup = 3;
+                boolean trivia = false;
+                boolean json = false;
+                boolean tokenBuffer = false;
+                try {
+                    for (int i = 1; i < args.length; i++) {
+                        if (args[i].equals("--iterations") && i + 1 < args.length) {
+                            iterations = Integer.parseInt(args[++i]);
+                        } else if (args[i].equals("--warmup") && i + 1 < args.length) {
+                            warmup = Integer.parseInt(args[++i]);
+                        } else if (args[i].equals("--exclude") && i + 1 < args.length) {
+                            excludes.add(args[++i]);
+                        } else if (args[i].equals("--trivia")) {
+                            trivia = true;
+                        } else if (args[i].equals("--json")) {
+                            json = true;
+                        } else if (args[i].equals("--token-buffer")) {
+                            tokenBuffer = true;
+                        } else if (path == null && !args[i].startsWith("--")) {
+                            path = Path.of(args[i]);
+                        } else {
+                            System.err.println(USAGE);
+                            System.exit(2);
+                        }
+                    }
+                } catch (NumberFormatException e) {
+                    System.err.println("Not a number: " + e.getMessage());
+                    System.exit(2);
+                }
+                if (path == null || iterations < 1 || warmup < 0) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                LexerOptions options = LexerOptions.builder().emitTrivia(trivia).build();
+                System.exit(new BenchCommand(System.out, options, warmup, iterations, json, tokenBuffer)
+                        .run(path, excludes));
+            }
+            case "check" -> {
+                ArrayList<Path> paths = new ArrayList<>();
+                boolean quiet = false;
+                boolean denyWarnings = false;
+                for (int i = 1; i < args.length; i++) {
+                    if (args[i].equals("--quiet")) {
+                        quiet = true;
+                    } else if (args[i].equals("--deny-warnings")) {
+                        denyWarnings = true;
+                    } else if (!args[i].startsWith("--")) {
+                        paths.add(Path.of(args[i]));
+                    } else {
+                        System.err.println(USAGE);
+                        System.exit(2);
+                    }
+                }
+                if (paths.isEmpty()) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                System.exit(new CheckCommand(System.out, quiet, denyWarnings).run(paths));
+            }
+            case "fix" -> {
+                ArrayList<Path> paths = new ArrayList<>();
+                boolean dryRun = false;
+                for (int i = 1; i < args.length; i++) {
+                    if (args[i].equals("--dry-run")) {
+                        dryRun = true;
+                    } else if (!args[i].startsWith("--")) {
+                        paths.add(Path.of(args[i]));
+                    } else {
+                        System.err.println(USAGE);
+                        System.exit(2);
+                    }
+                }
+                if (paths.isEmpty()) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                System.exit(new FixCommand(System.out, dryRun).run(paths));
+            }
+            case "explain" -> {
+                if (args.length != 2) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                System.exit(new ExplainCommand(System.out).run(args[1]));
+            }
+            case "tokenize" -> {
+                String path = null;
+                String fileName = null;
+                TokenizeCommand.Format format = TokenizeCommand.Format.TEXT;
+                for (int i = 1; i < args.length; i++) {
+                    if (args[i].equals("--filename") && i + 1 < args.length) {
+                        fileName = args[++i];
+                    } else if (args[i].equals("--format") && i + 1 < args.length) {
+                        try {
+                            format = TokenizeCommand.Format.valueOf(args[++i].toUpperCase(Locale.ROOT));
+                        } catch (IllegalArgumentException e) {
+                            System.err.println("Unknown format: " + args[i]);
+                            System.exit(2);
+                        }
+                    } else if (path == null && (args[i].equals("-") || !args[i].startsWith("--"))) {
+                        path = args[i];
+                    } else {
+                        System.err.println(USAGE);
+                        System.exit(2);
+                    }
+                }
+                if (path == null) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+                System.exit(new TokenizeCommand(System.out, System.err, format).run(path, fileName, System.in));
+            }
+            default -> {
+                Path path = null;
+                ArrayList<String> excludes = new ArrayList<>();
+                String format = "text";
+                Theme theme = Theme.TERMINAL;
+                for (int i = 0; i < args.length; i++) {
+                    if (args[i].equals("--exclude") && i + 1 < args.length) {
+                        excludes.add(args[++i]);
+                    } else if (args[i].equals("--format") && i + 1 < args.length) {
+                        format = args[++i];
+                        if (!List.of("text", "html", "explorer", "dot", "sarif").contains(format)) {
+                            System.err.println("Unknown format: " + format);
+                            System.exit(2);
+                        }
+                    } else if (args[i].equals("--theme") && i + 1 < args.length) {
+                        theme = loadTheme(args[++i]);
+                    } else if (path == null && !args[i].startsWith("--")) {
+                        path = Path.of(args[i]);
+                    } else {
+                        System.err.println(USAGE);
+                        System.exit(2);
+                    }
+                }
+                if (path == null) {
+                    System.err.println(USAGE);
+                    System.exit(2);
+                }
+
+                boolean sarif = format.equals("sarif");
+                if (Files.isDirectory(path) && !format.equals("text") && !sarif) {
+                    System.err.println("This format needs a single file");
+                    System.exit(2);
+                }
+                if (Files.isDirectory(path))
+                    System.exit(sarif ? lexDirectorySarif(path, excludes) : lexDirectory(path, excludes));
+                else if (sarif)
+                    System.exit(new TokenizeCommand(System.out, System.err, TokenizeCommand.Format.SARIF)
+                            .run(path.toString(), null, System.in));
+                else if (format.equals("html"))
+                    printHtml(path, theme);
+                else if (format.equals("explorer"))
+                    printExplorer(path, theme);
+                else if (format.equals("dot"))
+                    System.out.print(Dot.toDot(new Lexer(new ByteArrayInputStream(Files.readAllBytes(path))).parse()));
+                else
+                    highlight(path, theme);
+            }
+        }
+    }
+
+    /**
+     * @return exit status: 0 if every file lexed cleanly, 1 if some had errors or couldn't be read
+     */
+    private static int lexDirectory(Path root, List<String> excludes) throws IOException {
+        int errorCount = 0;
+        int failedFiles = 0;
+        List<Path> files = SourceFiles.find(root, excludes);
+        for (Path file : files) {
+            List<Token> tokens;
+            try (InputStream in = Files.newInputStream(file)) {
+                tokens = new Lexer(in).parse();
+            } catch (IOException e) {
+                System.out.println(file + ": FAILED (" + e.getMessage() + ")");
+                failedFiles++;
+                continue;
+            }
+
+            int fileErrors = 0;
+            for (Token token : tokens) {
+                if (token.type == TokenType.ERROR) {
+                    System.out.printf("%s:%d:%d: %s%n", file, token.line + 1, token.column + 1, token.value);
+                    fileErrors++;
+                }
+            }
+            if (fileErrors > 0) {
+                System.out.println(file + ": FAILED (" + fileErrors + " errors)");
+                errorCount += fileErrors;
+                failedFiles++;
+            } else {
+                System.out.println(file + ": ok");
+            }
+        }
+        System.out.printf("%d files, %d errors, %d files failed%n", files.size(), errorCount, failedFiles);
+        return failedFiles > 0 ? 1 : 0;
+    }
+
+    /**
+     * Same as {@link #lexDirectory}, but prints a single SARIF log, unreadable files go to stderr.
+     */
+    private static int lexDirectorySarif(Path root, List<String> excludes) throws IOException {
+        LinkedHashMap<String, List<Token>> results = new LinkedHashMap<>();
+        boolean failed = false;
+        for (Path file : SourceFiles.find(root, excludes)) {
+            List<Token> tokens;
+            try (InputStream in = Files.newInputStream(file)) {
+                tokens = new Lexer(in).parse();
+            } catch (IOException e) {
+                System.err.println(file + ": " + e.getMessage());
+                failed = true;
+                continue;
+            }
+            tokens.removeIf(token -> token.type != TokenType.ERROR);
+            if (!tokens.isEmpty())
+                failed = true;
+            results.put(file.toString().replace('\\', '/'), tokens);
+        }
+        System.out.println(Sarif.toSarif(results));
+        return failed ? 1 : 0;
+    }
+
+    /**
+     * @param name a built-in theme or a TOML file
+     */
+    private static Theme loadTheme(String name) {
+        Theme theme = Theme.named(name);
+        if (theme != null)
+            return theme;
+        try {
+            return Theme.load(Path.of(name));
+        } catch (IOException | IllegalArgumentException e) {
+            System.err.println("Can't load theme " + name + ": " + e.getMessage());
+            System.exit(2);
+            return null;
+        }
+    }
+
+    private static void printHtml(Path path, Theme theme) throws IOException {
+        String source = new String(Files.readAllBytes(path), StandardCharsets.UTF_8);
+        List<Token> tokens = Lexer.fromString(source, LexerOptions.builder().emitTrivia(true).build()).parse();
+        System.out.print(new Highlighter(theme).toHtml(source, tokens));
+    }
+
+    private static void printExplorer(Path path, Theme theme) throws IOException {
+        String source = new String(Files.readAllBytes(path), StandardCharsets.UTF_8);
+        List<Token> tokens = Lexer.fromString(source, LexerOptions.builder().emitTrivia(true).build()).parse();
+        //the terminal theme is for dark terminals
+        if (theme == Theme.TERMINAL)
+            theme = Theme.LIGHT;
+        System.out.print(TreeExplorer.toHtml(path.getFileName().toString(), source, tokens, theme));
+    }
+
+    private static void highlight(Path path, Theme theme) throws IOException {
+        byte[] file = Files.readAllBytes(path);
         ByteArrayInputStream stream = new ByteArrayInputStream(file);
         Lexer lexer = new Lexer(stream);
 
@@ -32,7 +368,7 @@ public class Main {
         AnsiConsole.out().print(ansi().reset());
 
         stream.reset();
-        Highlighter highlighter = new Highlighter();
+        Highlighter highlighter = new Highlighter(theme);
         highlighter.printHighlighted(AnsiConsole.out(), stream, tokens);
     }
 }
@@ -0,0 +1,86 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * Shrinks Rust source by removing comments and whitespace between tokens.
+ */
+public final class Minifier {
+    //tried in order, the first one that keeps both tokens as they are wins
+    private static final String[] SEPARATORS = {"", " ", "\n"};
+
+    private Minifier() {
+    }
+
+    public static String minify(String source) {
+        return minify(source, LexerOptions.DEFAULT);
+    }
+
+    /**
+     * Removes plain comments and leaves a space between tokens only where they would lex differently without it,
+     * or a line break after line doc comments. Doc comments stay, since they're attributes.
+     * <p>
+     * The result is lexed again and checked against the source's tokens.
+     *
+     * @throws IllegalArgumentException if the source has lexical errors
+     * @throws IllegalStateException if the result doesn't lex to the same tokens, which is a bug
+     */
+    public static String minify(String source, LexerOptions options) {
+        LexerOptions lexing = options.toBuilder().emitTrivia(false).build();
+        Lexer.Tokenized tokenized = Lexer.tokenize(source, lexing);
+        if (!tokenized.errors.isEmpty())
+            throw new IllegalArgumentException("Can't minify source with lexical errors: " + tokenized.errors.get(0));
+        List<Token> tokens = significant(tokenized.tokens);
+
+        StringBuilder minified = new StringBuilder(source.length());
+        Token previous = null;
+        String previousText = null;
+        for (Token token : tokens) {
+            String text = source.substring(token.offset, token.end());
+            if (previous != null)
+                minified.append(separator(previous, previousText, token, text, lexing));
+            minified.append(text);
+            previous = token;
+            previousText = text;
+        }
+
+        Lexer.Tokenized relexed = Lexer.tokenize(minified.toString(), lexing);
+        if (!relexed.errors.isEmpty() || !sameTokens(tokens, significant(relexed.tokens)))
+            throw new IllegalStateException("Minified source doesn't lex to the same tokens");
+        return minified.toString();
+    }
+
+    private static String separator(Token previous, String previousText, Token next, String nextText,
+                                    LexerOptions options) {
+        for (String separator : SEPARATORS) {
+            List<Token> pair = Lexer.tokenize(previousText + separator + nextText, options).tokens;
+            if (pair.size() == 2 && sameToken(previous, pair.get(0)) && sameToken(next, pair.get(1)))
+                return separator;
+        }
+        throw new IllegalStateException("Can't separate " + previousText + " from " + nextText);
+    }
+
+    private static List<Token> significant(List<Token> tokens) {
+        ArrayList<Token> significant = new ArrayList<>(tokens.size());
+        for (Token token : tokens) {
+            if (token.type != TokenType.COMMENT && token.type != TokenType.WHITESPACE)
+                significant.add(token);
+        }
+        return significant;
+    }
+
+    private static boolean sameTokens(List<Token> expected, List<Token> actual) {
+        if (expected.size() != actual.size())
+            return false;
+        for (int i = 0; i < expected.size(); i++) {
+            if (!sameToken(expected.get(i), actual.get(i)))
+                return false;
+        }
+        return true;
+    }
+
+    private static boolean sameToken(Token a, Token b) {
+        return a.type == b.type && a.length == b.length && a.isContextualKeyword() == b.isContextualKeyword();
+    }
+}
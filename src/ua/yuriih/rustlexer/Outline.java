@@ -0,0 +1,310 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+import java.util.Locale;
+
+/**
+ * A rough outline of the items in a file, found from keywords and {} nesting without parsing: functions, structs,
+ * enums, impl blocks, modules and traits, with the ones inside their bodies as children. Good enough for
+ * breadcrumbs and folding.
+ * <p>
+ * Items inside () and [] aren't looked for, and neither are the ones in macro_rules! definitions, but the keywords
+ * in a macro's arguments in {} are taken for items. An impl only starts an item at the start of a group or after a
+ * ;, a } or ], a doc comment, unsafe or default, so impl Trait in a return type isn't one.
+ */
+public final class Outline {
+    public enum Kind {
+        FN,
+        STRUCT,
+        ENUM,
+        IMPL,
+        MOD,
+        TRAIT
+    }
+
+    /** Top-level items, in source order */
+    public final List<Symbol> symbols;
+
+    private Outline(List<Symbol> symbols) {
+        this.symbols = Collections.unmodifiableList(symbols);
+    }
+
+    /**
+     * @param tokens with or without trivia
+     */
+    public static Outline of(List<Token> tokens) {
+        ArrayList<Symbol> symbols = new ArrayList<>();
+        scan(TokenTree.build(tokens).root, symbols, 0);
+        return new Outline(symbols);
+    }
+
+    /**
+     * All items, parents before their children.
+     */
+    public List<Symbol> all() {
+        ArrayList<Symbol> all = new ArrayList<>();
+        addAll(symbols, all);
+        return all;
+    }
+
+    private static void addAll(List<Symbol> symbols, List<Symbol> all) {
+        for (Symbol symbol : symbols) {
+            all.add(symbol);
+            addAll(symbol.children, all);
+        }
+    }
+
+    /**
+     * Breadcrumbs for a position.
+     *
+     * @return the items the offset is in, outermost first
+     */
+    public List<Symbol> at(int offset) {
+        ArrayList<Symbol> path = new ArrayList<>();
+        List<Symbol> level = symbols;
+        search:
+        while (true) {
+            for (Symbol symbol : level) {
+                if (symbol.start() <= offset && offset < symbol.end()) {
+                    path.add(symbol);
+                    level = symbol.children;
+                    continue search;
+                }
+            }
+            return path;
+        }
+    }
+
+    /**
+     * Finds items among the children of a {} group or the root and in the blocks nested in it.
+     */
+    private static void scan(TokenTree.Group group, List<Symbol> symbols, int depth) {
+        List<TokenTree.Node> nodes = significant(group.children());
+        for (int i = 0; i < nodes.size(); i++) {
+            TokenTree.Node node = nodes.get(i);
+            if (node instanceof TokenTree.Group) {
+                TokenTree.Group block = (TokenTree.Group) node;
+                if (block.open.type == TokenType.CURLY_L)
+                    scan(block, symbols, depth);
+                continue;
+            }
+            Token keyword = ((TokenTree.Leaf) node).token;
+            if (isMacroRules(nodes, i)) {
+                //the !, the name and the body
+                i += 3;
+                continue;
+            }
+            Kind kind = kind(keyword.type);
+            if (kind == null)
+                continue;
+
+            Token nameToken = null;
+            int header = i + 1;
+            if (kind == Kind.IMPL) {
+                if (!startsImpl(i > 0 ? nodes.get(i - 1) : null))
+                    continue;
+            } else {
+                Token name = header < nodes.size() ? token(nodes.get(header)) : null;
+                if (name == null || (name.type != TokenType.IDENTIFIER && name.type != TokenType.RAW_IDENTIFIER))
+                    continue;
+                nameToken = name;
+                header++;
+            }
+
+            //the header goes on to the body or a ;
+            int end = header;
+            while (end < nodes.size() && !isBody(nodes.get(end)) && !isLeaf(nodes.get(end), TokenType.SEMICOLON))
+                end++;
+            TokenTree.Group body = end < nodes.size() && isBody(nodes.get(end))
+                    ? (TokenTree.Group) nodes.get(end) : null;
+            Token last;
+            if (end < nodes.size())
+                last = body != null ? body.close() : token(nodes.get(end));
+            else
+                last = lastToken(nodes.get(nodes.size() - 1));
+            String name = kind == Kind.IMPL ? implName(nodes.subList(i + 1, Math.min(end, nodes.size())))
+                    : nameToken.name();
+
+            Symbol symbol = new Symbol(kind, name, keyword, nameToken, body, last, depth);
+            symbols.add(symbol);
+            if (body != null)
+                scan(body, symbol.children, depth + 1);
+            i = end;
+        }
+    }
+
+    private static Kind kind(TokenType type) {
+        return switch (type) {
+            case FN -> Kind.FN;
+            case STRUCT -> Kind.STRUCT;
+            case ENUM -> Kind.ENUM;
+            case IMPL -> Kind.IMPL;
+            case MOD -> Kind.MOD;
+            case TRAIT -> Kind.TRAIT;
+            default -> null;
+        };
+    }
+
+    private static boolean isMacroRules(List<TokenTree.Node> nodes, int i) {
+        Token token = token(nodes.get(i));
+        return token != null && token.type == TokenType.IDENTIFIER && "macro_rules".equals(token.text())
+                && i + 1 < nodes.size() && isLeaf(nodes.get(i + 1), TokenType.NOT);
+    }
+
+    private static boolean startsImpl(TokenTree.Node previous) {
+        if (previous == null)
+            return true;
+        if (previous instanceof TokenTree.Group)
+            return ((TokenTree.Group) previous).open.type != TokenType.PAREN_L;
+        Token token = ((TokenTree.Leaf) previous).token;
+        return switch (token.type) {
+            case SEMICOLON, UNSAFE, COMMENT_OUTER_DOC, COMMENT_INNER_DOC -> true;
+            case IDENTIFIER -> "default".equals(token.text());
+            default -> false;
+        };
+    }
+
+    private static boolean isBody(TokenTree.Node node) {
+        return node instanceof TokenTree.Group && ((TokenTree.Group) node).open.type == TokenType.CURLY_L;
+    }
+
+    private static boolean isLeaf(TokenTree.Node node, TokenType type) {
+        return node instanceof TokenTree.Leaf && ((TokenTree.Leaf) node).token.type == type;
+    }
+
+    /**
+     * @return the leaf's token, or null for a group
+     */
+    private static Token token(TokenTree.Node node) {
+        return node instanceof TokenTree.Leaf ? ((TokenTree.Leaf) node).token : null;
+    }
+
+    private static Token lastToken(TokenTree.Node node) {
+        if (node instanceof TokenTree.Leaf)
+            return ((TokenTree.Leaf) node).token;
+        TokenTree.Group group = (TokenTree.Group) node;
+        if (group.close() != null)
+            return group.close();
+        List<TokenTree.Node> children = group.children();
+        return children.isEmpty() ? group.open : lastToken(children.get(children.size() - 1));
+    }
+
+    private static List<TokenTree.Node> significant(List<TokenTree.Node> nodes) {
+        ArrayList<TokenTree.Node> significant = new ArrayList<>(nodes.size());
+        for (TokenTree.Node node : nodes) {
+            if (!(node instanceof TokenTree.Leaf) || !((TokenTree.Leaf) node).token.type.isTrivia())
+                significant.add(node);
+        }
+        return significant;
+    }
+
+    /**
+     * The header of an impl without its generic parameters and where clause, e.g. "Display for Foo<T>".
+     */
+    private static String implName(List<TokenTree.Node> header) {
+        ArrayList<Token> tokens = new ArrayList<>();
+        for (TokenTree.Node node : header)
+            addTokens(node, tokens);
+        int start = 0;
+        if (!tokens.isEmpty() && tokens.get(0).type == TokenType.LT) {
+            int depth = 0;
+            for (; start < tokens.size(); start++) {
+                TokenType type = tokens.get(start).type;
+                depth += type == TokenType.LT ? 1 : type == TokenType.GT ? -1 : type == TokenType.SHR ? -2 : 0;
+                if (depth <= 0) {
+                    start++;
+                    break;
+                }
+            }
+        }
+
+        StringBuilder name = new StringBuilder();
+        Token previous = null;
+        for (Token token : tokens.subList(Math.min(start, tokens.size()), tokens.size())) {
+            if (token.type == TokenType.WHERE)
+                break;
+            if (token.type.isTrivia() || token.type == TokenType.COMMENT_OUTER_DOC)
+                continue;
+            if (previous != null && (isWord(previous) && isWord(token) || previous.type == TokenType.COMMA))
+                name.append(' ');
+            name.append(token.text());
+            previous = token;
+        }
+        return name.toString();
+    }
+
+    private static void addTokens(TokenTree.Node node, List<Token> tokens) {
+        if (node instanceof TokenTree.Leaf) {
+            tokens.add(((TokenTree.Leaf) node).token);
+            return;
+        }
+        TokenTree.Group group = (TokenTree.Group) node;
+        tokens.add(group.open);
+        for (TokenTree.Node child : group.children())
+            addTokens(child, tokens);
+        if (group.close() != null)
+            tokens.add(group.close());
+    }
+
+    private static boolean isWord(Token token) {
+        String text = token.text();
+        if (text == null || text.isEmpty())
+            return false;
+        int last = text.codePointBefore(text.length());
+        return Lexer.isIdentifierChar(last) || token.type == TokenType.CHAR_LITERAL || last == '"';
+    }
+
+    public static final class Symbol {
+        public final Kind kind;
+        /** The item's name, or for an impl block what it implements, like "Display for Foo<T>" */
+        public final String name;
+        public final Token keyword;
+        /** Null for impl blocks */
+        public final Token nameToken;
+        /** Null for items without a body, like struct S; or a function declared in a trait */
+        public final TokenTree.Group body;
+        /** 0 for top-level items */
+        public final int depth;
+        private final Token last;
+        private final ArrayList<Symbol> children = new ArrayList<>();
+
+        private Symbol(Kind kind, String name, Token keyword, Token nameToken, TokenTree.Group body, Token last,
+                       int depth) {
+            this.kind = kind;
+            this.name = name;
+            this.keyword = keyword;
+            this.nameToken = nameToken;
+            this.body = body;
+            this.last = last;
+            this.depth = depth;
+        }
+
+        /**
+         * Items in the body, in source order.
+         */
+        public List<Symbol> children() {
+            return Collections.unmodifiableList(children);
+        }
+
+        /**
+         * @return offset of the keyword: attributes, visibility and qualifiers like unsafe aren't included
+         */
+        public int start() {
+            return keyword.offset;
+        }
+
+        /**
+         * @return end offset of the body's }, of the ;, or of the last token of an item that's cut off
+         */
+        public int end() {
+            return body != null && body.close() == null ? body.end() : last.end();
+        }
+
+        @Override
+        public String toString() {
+            return kind.name().toLowerCase(Locale.ROOT) + " " + name + " " + start() + ".." + end();
+        }
+    }
+}
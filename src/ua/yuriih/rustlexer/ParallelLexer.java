@@ -0,0 +1,275 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.UncheckedIOException;
+import java.util.ArrayList;
+import java.util.List;
+import java.util.concurrent.Callable;
+import java.util.concurrent.ForkJoinPool;
+import java.util.function.Consumer;
+import java.util.stream.IntStream;
+
+/**
+ * Lexes one huge file on several threads. The source is split into chunks at line starts, and every chunk
+ * is lexed as if it started between two tokens. Then, going from the start of the file, a chunk that really
+ * starts inside a token, like a string or block comment from the chunk before, is lexed again until the tokens
+ * line up with the ones lexed in parallel.
+ * <p>
+ * The tokens are the same as from {@link Lexer#parse()}. Options that need to see tokens in order make it lex on
+ * one thread: custom token and injection hooks, warnings,
+ * {@link LexerOptions.Builder#collectErrors collecting errors}, lexing without recovery, a token limit and profiling.
+ * <p>
+ * Work runs on the common fork-join pool unless a {@link Config} says otherwise.
+ */
+public final class ParallelLexer {
+    //smaller chunks aren't worth a thread
+    private static final int MIN_CHUNK_LENGTH = 1 << 20;
+    //how many chars to lex at a time while looking for a token both lexers agree on
+    private static final int REPAIR_STEP = 4096;
+
+    private ParallelLexer() {
+    }
+
+    public static List<Token> lex(String source, LexerOptions options) {
+        return lex(source, options, Runtime.getRuntime().availableProcessors());
+    }
+
+    /**
+     * @param chunks at most how many chunks to split the source into, they're lexed on the common fork-join pool
+     */
+    public static List<Token> lex(String source, LexerOptions options, int chunks) {
+        if (chunks < 1)
+            throw new IllegalArgumentException("chunks must be positive");
+        return lexChunks(source, options, chunks);
+    }
+
+    /**
+     * Lexes on the config's pool, in as many chunks as it says.
+     */
+    public static List<Token> lex(String source, LexerOptions options, Config config) {
+        return config.run(() -> lexChunks(source, options, config.chunks()));
+    }
+
+    /**
+     * Lexes many files on the config's pool. With {@link Config.Chunking#PER_FILE} every file is lexed on a single
+     * thread, otherwise big files are split into chunks too, and threads that run out of work take over chunks
+     * from the others.
+     *
+     * @return tokens of each source, in the same order
+     */
+    public static List<List<Token>> lexAll(List<String> sources, LexerOptions options, Config config) {
+        int chunks = config.chunking == Config.Chunking.PER_FILE ? 1 : config.chunks();
+        return config.run(() -> {
+            List<Token>[] tokens = newArray(sources.size());
+            IntStream.range(0, sources.size()).parallel()
+                    .forEach(i -> tokens[i] = lexChunks(sources.get(i), options, chunks));
+            return List.of(tokens);
+        });
+    }
+
+    @SuppressWarnings("unchecked")
+    private static List<Token>[] newArray(int size) {
+        return (List<Token>[]) new List<?>[size];
+    }
+
+    /**
+     * Parallel streams started from a pool's thread run on that pool.
+     */
+    private static List<Token> lexChunks(String source, LexerOptions options, int chunks) {
+        int[] starts = split(source, Math.min(chunks, source.length() / MIN_CHUNK_LENGTH));
+        int count = starts.length - 1;
+        try {
+            if (count <= 1 || !canSplit(options))
+                return Lexer.fromString(source, options).parse();
+
+            int[] lines = new int[count];
+            IntStream.range(1, count).parallel().forEach(i -> {
+                for (int j = starts[i - 1]; j < starts[i]; j++) {
+                    if (source.charAt(j) == '\n')
+                        lines[i]++;
+                }
+            });
+            for (int i = 1; i < count; i++)
+                lines[i] += lines[i - 1];
+
+            Lexer[] lexers = new Lexer[count];
+            ArrayList<List<Token>> tokens = new ArrayList<>();
+            for (int i = 0; i < count; i++)
+                tokens.add(new ArrayList<>());
+            IntStream.range(0, count).parallel().forEach(i -> {
+                lexers[i] = Lexer.fromLineStart(source, starts[i], lines[i], options);
+                try {
+                    lexers[i].advance(tokens.get(i)::add, starts[i + 1] - starts[i]);
+                } catch (IOException e) {
+                    //StringReader doesn't throw
+                    throw new UncheckedIOException(e);
+                }
+            });
+
+            ArrayList<Token> result = new ArrayList<>(tokens.get(0));
+            Lexer current = lexers[0];
+            for (int i = 1; i < count; i++) {
+                List<Token> speculative = tokens.get(i);
+                if (current.betweenTokens()) {
+                    result.addAll(speculative);
+                    current = lexers[i];
+                    continue;
+                }
+                int synced = repair(current, speculative, starts[i + 1], result);
+                if (synced >= 0) {
+                    result.addAll(speculative.subList(synced + 1, speculative.size()));
+                    current = lexers[i];
+                }
+                //otherwise the chunk was lexed again in full and the same lexer goes on to the next one
+            }
+            current.parse(result::add);
+            return result;
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+    }
+
+    private static boolean canSplit(LexerOptions options) {
+        return options.hooks.isEmpty() && options.injections.isEmpty() && options.warningSink == null
+                && !options.severities.containsValue(LexWarning.Severity.DENY) && !options.collectErrors
+                && options.recovery && options.maxTokens == 0 && !options.profile;
+    }
+
+    /**
+     * @return the start of every chunk, and the source's length at the end
+     */
+    private static int[] split(String source, int count) {
+        ArrayList<Integer> starts = new ArrayList<>();
+        starts.add(0);
+        for (int i = 1; i < count; i++) {
+            int lineEnd = source.indexOf('\n', (int) ((long) source.length() * i / count));
+            if (lineEnd < 0 || lineEnd + 1 >= source.length())
+                break;
+            if (lineEnd + 1 > starts.get(starts.size() - 1))
+                starts.add(lineEnd + 1);
+        }
+        starts.add(source.length());
+        return starts.stream().mapToInt(Integer::intValue).toArray();
+    }
+
+    /**
+     * Lexes a chunk again with the lexer that got to its start, until that lexer produces a token that was also
+     * lexed in parallel. From there on, the tokens are the same.
+     *
+     * @return index of that token in the speculative ones, or -1 if the lexer got to the end of the chunk first
+     */
+    private static int repair(Lexer lexer, List<Token> speculative, int end, List<Token> result)
+            throws IOException {
+        int[] next = {0};
+        int[] synced = {-1};
+        Consumer<Token> consumer = token -> {
+            if (synced[0] >= 0)
+                return;
+            result.add(token);
+            //errors can come from the middle of a token that isn't finished yet
+            if (token.type == TokenType.ERROR)
+                return;
+            while (next[0] < speculative.size() && speculative.get(next[0]).offset < token.offset)
+                next[0]++;
+            if (next[0] < speculative.size() && speculative.get(next[0]).equals(token))
+                synced[0] = next[0];
+        };
+        while (synced[0] < 0 && lexer.offset() < end)
+            lexer.advance(consumer, Math.min(REPAIR_STEP, end - lexer.offset()));
+        return synced[0];
+    }
+
+    /**
+     * How {@link ParallelLexer} uses threads, for applications that manage their own.
+     */
+    public static final class Config {
+        public static final Config DEFAULT = builder().build();
+
+        public enum Chunking {
+            /**
+             * Split big files into more chunks than there are threads, so a thread that finishes early takes over
+             * chunks from the others
+             */
+            WORK_STEALING,
+            /** One chunk per thread for a single file, and no splitting at all for {@link ParallelLexer#lexAll} */
+            PER_FILE
+        }
+
+        //chunks per thread when work stealing
+        private static final int CHUNKS_PER_THREAD = 4;
+
+        public final int threads;
+        public final Chunking chunking;
+        /** The pool to run on, null for a new one with the given number of threads for each call */
+        public final ForkJoinPool pool;
+
+        private Config(Builder builder) {
+            this.pool = builder.pool;
+            this.threads = builder.threads > 0 ? builder.threads
+                    : builder.pool != null ? builder.pool.getParallelism() : ForkJoinPool.getCommonPoolParallelism();
+            this.chunking = builder.chunking;
+        }
+
+        public static Builder builder() {
+            return new Builder();
+        }
+
+        int chunks() {
+            return chunking == Chunking.WORK_STEALING ? threads * CHUNKS_PER_THREAD : threads;
+        }
+
+        private <T> T run(Callable<T> task) {
+            ForkJoinPool pool = this.pool;
+            boolean ownPool = pool == null && threads != ForkJoinPool.getCommonPoolParallelism();
+            if (pool == null)
+                pool = ownPool ? new ForkJoinPool(threads) : ForkJoinPool.commonPool();
+            try {
+                return pool.submit(task).join();
+            } finally {
+                if (ownPool)
+                    pool.shutdown();
+            }
+        }
+
+        public static final class Builder {
+            private int threads;
+            private Chunking chunking = Chunking.WORK_STEALING;
+            private ForkJoinPool pool;
+
+            private Builder() {
+            }
+
+            /**
+             * How many threads to lex on, by default the pool's parallelism. Without a {@link #pool}, a different
+             * number than the common pool's means a new pool for each call.
+             */
+            public Builder threads(int threads) {
+                if (threads < 1)
+                    throw new IllegalArgumentException("threads must be positive");
+                this.threads = threads;
+                return this;
+            }
+
+            /**
+             * How work is split between threads, {@link Chunking#WORK_STEALING} by default.
+             */
+            public Builder chunking(Chunking chunking) {
+                this.chunking = chunking;
+                return this;
+            }
+
+            /**
+             * Runs on the given pool instead of the common one, it's never shut down.
+             */
+            public Builder pool(ForkJoinPool pool) {
+                this.pool = pool;
+                return this;
+            }
+
+            public Config build() {
+                return new Config(this);
+            }
+        }
+    }
+}
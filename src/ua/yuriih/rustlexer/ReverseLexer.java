@@ -0,0 +1,116 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.UncheckedIOException;
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Finds the tokens before a position without lexing the file from its start, for things like "what's the token
+ * before the cursor" in a big file.
+ * <p>
+ * There's no telling whether a line starts inside a string or a comment without lexing what's before it, so this
+ * lexes from a line start some way back, then from one twice as far back, and so on. Once two of these agree
+ * on a token, the tokens from there on are taken as right. In the worst case it ends up lexing from the start
+ * of the file.
+ * <p>
+ * This is a guess that can be wrong when both starting points are inside the same string or comment, e.g. after
+ * a quote that's never closed or in a raw string longer than the window. Use {@link TokenStream} when that matters.
+ */
+public final class ReverseLexer {
+    private static final int INITIAL_WINDOW = 4096;
+
+    private ReverseLexer() {
+    }
+
+    /**
+     * @return tokens that start before the offset, nearest first; the first one can end after the offset
+     */
+    public static List<Token> tokensBefore(String source, int offset, LexerOptions options) {
+        if (offset < 0 || offset > source.length())
+            throw new IndexOutOfBoundsException("Offset " + offset + " out of bounds for length " + source.length());
+        int linesBeforeOffset = countLines(source, 0, offset);
+        List<Token> later = null;
+        for (long window = INITIAL_WINDOW; ; window *= 2) {
+            int start = lineStart(source, (int) Math.max(0, offset - window));
+            List<Token> tokens = lex(source, start, linesBeforeOffset - countLines(source, start, offset), offset,
+                    options);
+            if (start == 0)
+                return reversed(tokens, 0);
+            if (later != null) {
+                int synced = firstCommon(later, tokens);
+                if (synced >= 0)
+                    return reversed(later, synced);
+            }
+            later = tokens;
+        }
+    }
+
+    /**
+     * @return the token that starts before the offset and is nearest to it, null if there's none
+     */
+    public static Token tokenBefore(String source, int offset, LexerOptions options) {
+        List<Token> tokens = tokensBefore(source, offset, options);
+        return tokens.isEmpty() ? null : tokens.get(0);
+    }
+
+    private static List<Token> lex(String source, int start, int line, int offset, LexerOptions options) {
+        Lexer lexer = start == 0 ? Lexer.fromString(source, options)
+                : Lexer.fromLineStart(source, start, line, options);
+        ArrayList<Token> tokens = new ArrayList<>();
+        boolean[] done = {false};
+        try {
+            while (!done[0] && lexer.advance(token -> {
+                if (token.offset >= offset)
+                    done[0] = true;
+                else if (!done[0])
+                    tokens.add(token);
+            }, INITIAL_WINDOW)) {
+            }
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+        return tokens;
+    }
+
+    /**
+     * Same idea as in {@link ParallelLexer}: once lexers from two starting points produce the same token,
+     * they produce the same tokens after it. Errors don't count, they can come from the middle of a token.
+     *
+     * @return index in later of the first token that's also in earlier, -1 if there's none
+     */
+    private static int firstCommon(List<Token> later, List<Token> earlier) {
+        int next = 0;
+        for (int i = 0; i < later.size(); i++) {
+            Token token = later.get(i);
+            if (token.type == TokenType.ERROR)
+                continue;
+            while (next < earlier.size() && earlier.get(next).offset < token.offset)
+                next++;
+            if (next < earlier.size() && earlier.get(next).equals(token))
+                return i;
+        }
+        return -1;
+    }
+
+    private static List<Token> reversed(List<Token> tokens, int from) {
+        ArrayList<Token> result = new ArrayList<>(tokens.subList(from, tokens.size()));
+        Collections.reverse(result);
+        return Collections.unmodifiableList(result);
+    }
+
+    private static int lineStart(String source, int offset) {
+        return offset == 0 ? 0 : source.lastIndexOf('\n', offset - 1) + 1;
+    }
+
+    private static int countLines(String source, int from, int to) {
+        int lines = 0;
+        for (int i = from; i < to; i++) {
+            if (source.charAt(i) == '\n')
+                lines++;
+        }
+        return lines;
+    }
+}
@@ -0,0 +1,82 @@
+package ua.yuriih.rustlexer;
+
+import java.util.List;
+import java.util.Locale;
+import java.util.Map;
+
+/**
+ * Converts lexical errors to a SARIF 2.1.0 log, with one rule per {@link LexError.Kind}.
+ */
+public final class Sarif {
+    private static final String TOOL_NAME = "rlex";
+
+    private Sarif() {
+    }
+
+    /**
+     * @param uri file location as it should appear in the report
+     * @param tokens lexer output, only the {@link TokenType#ERROR} tokens are used
+     */
+    public static String toSarif(String uri, List<Token> tokens) {
+        return toSarif(Map.of(uri, tokens));
+    }
+
+    /**
+     * @param tokensByUri lexer output for each file, results keep the map's iteration order
+     */
+    public static String toSarif(Map<String, ? extends List<Token>> tokensByUri) {
+        StringBuilder sb = new StringBuilder();
+        sb.append("{\"version\":\"2.1.0\",")
+                .append("\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",")
+                .append("\"runs\":[{\"tool\":{\"driver\":{\"name\":").append(Json.quote(TOOL_NAME))
+                .append(",\"rules\":[");
+        LexError.Kind[] kinds = LexError.Kind.values();
+        for (int i = 0; i < kinds.length; i++) {
+            if (i > 0)
+                sb.append(',');
+            sb.append("{\"id\":").append(Json.quote(kinds[i].code))
+                    .append(",\"name\":").append(Json.quote(ruleName(kinds[i])))
+                    .append(",\"defaultConfiguration\":{\"level\":\"error\"}}");
+        }
+        sb.append("]}},\"columnKind\":\"utf16CodeUnits\",\"results\":[");
+
+        boolean first = true;
+        for (Map.Entry<String, ? extends List<Token>> entry : tokensByUri.entrySet()) {
+            for (Token token : entry.getValue()) {
+                if (token.type != TokenType.ERROR)
+                    continue;
+                if (!first)
+                    sb.append(',');
+                first = false;
+                appendResult(sb, entry.getKey(), token);
+            }
+        }
+        return sb.append("]}]}").toString();
+    }
+
+    private static void appendResult(StringBuilder sb, String uri, Token token) {
+        sb.append('{');
+        if (token instanceof LexError) {
+            LexError.Kind kind = ((LexError) token).kind;
+            sb.append("\"ruleId\":").append(Json.quote(kind.code))
+                    .append(",\"ruleIndex\":").append(kind.ordinal()).append(',');
+        }
+        sb.append("\"level\":\"error\",\"message\":{\"text\":").append(Json.quote(token.value)).append('}')
+                .append(",\"locations\":[{\"physicalLocation\":{\"artifactLocation\":{\"uri\":")
+                .append(Json.quote(uri)).append("},\"region\":{")
+                .append("\"startLine\":").append(token.line + 1)
+                .append(",\"startColumn\":").append(token.column + 1)
+                .append(",\"charOffset\":").append(token.offset)
+                .append(",\"charLength\":").append(token.length)
+                .append("}}}]}");
+    }
+
+    // UNEXPECTED_SYMBOL -> UnexpectedSymbol
+    private static String ruleName(LexError.Kind kind) {
+        StringBuilder sb = new StringBuilder();
+        for (String word : kind.name().split("_")) {
+            sb.append(word.charAt(0)).append(word.substring(1).toLowerCase(Locale.ROOT));
+        }
+        return sb.toString();
+    }
+}
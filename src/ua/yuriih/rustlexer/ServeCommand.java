@@ -0,0 +1,280 @@
+package ua.yuriih.rustlexer;
+
+import java.io.BufferedReader;
+import java.io.IOException;
+import java.io.InputStream;
+import java.io.InputStreamReader;
+import java.io.OutputStream;
+import java.io.PrintStream;
+import java.io.UncheckedIOException;
+import java.net.InetAddress;
+import java.net.ServerSocket;
+import java.net.Socket;
+import java.nio.charset.CharacterCodingException;
+import java.nio.charset.StandardCharsets;
+import java.nio.file.Files;
+import java.nio.file.NoSuchFileException;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.LinkedHashMap;
+import java.util.List;
+import java.util.Map;
+
+/**
+ * rlex serve: a long-lived lexer for editor plugins and tools in other languages, so they don't start a process
+ * for every file. It speaks JSON-RPC 2.0 with one message per line, over stdin and stdout or over TCP connections
+ * to the loopback address (Unix domain sockets need Java 16).
+ * <p>
+ * Methods:
+ * <ul>
+ *     <li>"lex" with params "text" or "path", and optionally "trivia" (boolean) and "edition" (like "2021").
+ *     Tokens and diagnostics are streamed back as "lex/batch" notifications with the request's id,
+ *     then the response has the counts: {"tokens": n, "errors": n, "warnings": n}.</li>
+ *     <li>"shutdown" responds with null and ends the session.</li>
+ * </ul>
+ * Tokens are written like {@code rlex tokenize --format jsonl} writes them, diagnostics have the fields of
+ * {@link Diagnostic}. Positions are 0-based, columns are in chars.
+ */
+final class ServeCommand {
+    //tokens per notification
+    static final int BATCH_SIZE = 1024;
+
+    private static final int PARSE_ERROR = -32700;
+    private static final int INVALID_REQUEST = -32600;
+    private static final int METHOD_NOT_FOUND = -32601;
+    private static final int INVALID_PARAMS = -32602;
+    //the file in "path" couldn't be read
+    private static final int READ_FAILED = -32001;
+
+    private final BufferedReader in;
+    private final OutputStream out;
+
+    ServeCommand(InputStream in, OutputStream out) {
+        this.in = new BufferedReader(new InputStreamReader(in, StandardCharsets.UTF_8));
+        this.out = out;
+    }
+
+    /**
+     * Accepts connections on the loopback address and serves each one on its own thread, until the process
+     * is killed.
+     *
+     * @param port 0 for any free port
+     * @param log  where the port that's listened on and failed connections are reported
+     */
+    static int listen(int port, PrintStream log) throws IOException {
+        try (ServerSocket server = new ServerSocket(port, 0, InetAddress.getLoopbackAddress())) {
+            log.println("Listening on " + server.getInetAddress().getHostAddress() + ":" + server.getLocalPort());
+            while (true) {
+                Socket socket = server.accept();
+                Thread thread = new Thread(() -> {
+                    try (socket) {
+                        new ServeCommand(socket.getInputStream(), socket.getOutputStream()).run();
+                    } catch (IOException | UncheckedIOException e) {
+                        log.println(socket.getRemoteSocketAddress() + ": " + e.getMessage());
+                    }
+                }, "rlex serve " + socket.getRemoteSocketAddress());
+                thread.setDaemon(true);
+                thread.start();
+            }
+        }
+    }
+
+    /**
+     * Serves requests until "shutdown" or the end of the input.
+     *
+     * @return exit status, always 0
+     */
+    int run() throws IOException {
+        String line;
+        while ((line = in.readLine()) != null) {
+            if (line.isBlank())
+                continue;
+            Object parsed;
+            try {
+                parsed = Json.parse(line);
+            } catch (IllegalArgumentException e) {
+                sendError(null, PARSE_ERROR, e.getMessage());
+                continue;
+            }
+            if (!(parsed instanceof Map) || !(((Map<?, ?>) parsed).get("method") instanceof String)) {
+                sendError(parsed instanceof Map ? ((Map<?, ?>) parsed).get("id") : null, INVALID_REQUEST,
+                        "Not a request");
+                continue;
+            }
+            Map<?, ?> request = (Map<?, ?>) parsed;
+            Object id = request.get("id");
+            String method = (String) request.get("method");
+            Object params = request.get("params");
+            try {
+                Object result = switch (method) {
+                    case "lex" -> lex(id, params instanceof Map ? (Map<?, ?>) params : Map.of());
+                    case "shutdown" -> null;
+                    default -> throw new ResponseError(METHOD_NOT_FOUND, "Unknown method: " + method);
+                };
+                if (id != null)
+                    sendResult(id, result);
+                if (method.equals("shutdown"))
+                    return 0;
+            } catch (ResponseError e) {
+                if (id != null)
+                    sendError(id, e.code, e.getMessage());
+            } catch (ClassCastException e) {
+                if (id != null)
+                    sendError(id, INVALID_PARAMS, "Invalid params for " + method);
+            }
+        }
+        return 0;
+    }
+
+    private Map<String, Object> lex(Object id, Map<?, ?> params) throws ResponseError {
+        String source = source(params);
+        Batch batch = new Batch(id);
+        LexerOptions.Builder options = LexerOptions.builder()
+                .emitTrivia(Boolean.TRUE.equals(params.get("trivia")))
+                .warningSink(warning -> batch.diagnostic(Diagnostic.of(warning)));
+        if (params.get("edition") != null) {
+            try {
+                options.edition(LexerOptions.Edition.of((String) params.get("edition")));
+            } catch (IllegalArgumentException e) {
+                throw new ResponseError(INVALID_PARAMS, e.getMessage());
+            }
+        }
+        Lexer.lexInto(source, options.build(), batch);
+
+        LinkedHashMap<String, Object> result = new LinkedHashMap<>();
+        result.put("tokens", batch.tokenCount);
+        result.put("errors", batch.errorCount);
+        result.put("warnings", batch.warningCount);
+        return result;
+    }
+
+    private static String source(Map<?, ?> params) throws ResponseError {
+        Object text = params.get("text");
+        Object path = params.get("path");
+        if (text != null && path != null)
+            throw new ResponseError(INVALID_PARAMS, "Expected either text or path, not both");
+        if (text != null)
+            return (String) text;
+        if (path == null)
+            throw new ResponseError(INVALID_PARAMS, "Expected text or path");
+        try {
+            return Files.readString(Path.of((String) path));
+        } catch (NoSuchFileException e) {
+            throw new ResponseError(READ_FAILED, "No such file: " + path);
+        } catch (CharacterCodingException e) {
+            throw new ResponseError(READ_FAILED, "Not valid UTF-8: " + path);
+        } catch (IOException e) {
+            throw new ResponseError(READ_FAILED, "Can't read " + path + ": " + e.getMessage());
+        }
+    }
+
+    private static Map<String, Object> diagnosticObject(Diagnostic diagnostic) {
+        LinkedHashMap<String, Object> object = new LinkedHashMap<>();
+        object.put("severity", diagnostic.severity == Diagnostic.Severity.ERROR ? "error" : "warning");
+        object.put("code", diagnostic.code);
+        object.put("message", diagnostic.message);
+        if (diagnostic.help != null)
+            object.put("help", diagnostic.help);
+        object.put("line", diagnostic.line);
+        object.put("column", diagnostic.column);
+        object.put("offset", diagnostic.offset);
+        object.put("length", diagnostic.length);
+        return object;
+    }
+
+    private void sendResult(Object id, Object result) {
+        LinkedHashMap<String, Object> response = new LinkedHashMap<>();
+        response.put("jsonrpc", "2.0");
+        response.put("id", id);
+        response.put("result", result);
+        send(response);
+    }
+
+    private void sendError(Object id, int code, String message) {
+        LinkedHashMap<String, Object> response = new LinkedHashMap<>();
+        response.put("jsonrpc", "2.0");
+        response.put("id", id);
+        response.put("error", Map.of("code", code, "message", message));
+        send(response);
+    }
+
+    /**
+     * Strings are written with line breaks escaped, so a message never spans lines.
+     */
+    private void send(Object message) {
+        byte[] line = (Json.write(message) + "\n").getBytes(StandardCharsets.UTF_8);
+        try {
+            out.write(line);
+            out.flush();
+        } catch (IOException e) {
+            throw new UncheckedIOException(e);
+        }
+    }
+
+    /**
+     * Collects tokens and diagnostics of one request and sends them every {@link #BATCH_SIZE} tokens.
+     */
+    private final class Batch implements TokenSink {
+        private final Object id;
+        private final ArrayList<Object> tokens = new ArrayList<>();
+        private final ArrayList<Object> diagnostics = new ArrayList<>();
+        private int tokenCount = 0;
+        private int errorCount = 0;
+        private int warningCount = 0;
+
+        private Batch(Object id) {
+            this.id = id;
+        }
+
+        @Override
+        public void token(Token token) {
+            tokens.add(Json.tokenObject(token));
+            tokenCount++;
+            if (tokens.size() >= BATCH_SIZE)
+                flush();
+        }
+
+        @Override
+        public void error(LexError error) {
+            errorCount++;
+            diagnostics.add(diagnosticObject(Diagnostic.of(error)));
+            token(error);
+        }
+
+        private void diagnostic(Diagnostic diagnostic) {
+            warningCount++;
+            diagnostics.add(diagnosticObject(diagnostic));
+        }
+
+        @Override
+        public void end() {
+            if (!tokens.isEmpty() || !diagnostics.isEmpty())
+                flush();
+        }
+
+        private void flush() {
+            LinkedHashMap<String, Object> params = new LinkedHashMap<>();
+            params.put("id", id);
+            params.put("tokens", List.copyOf(tokens));
+            params.put("diagnostics", List.copyOf(diagnostics));
+            LinkedHashMap<String, Object> notification = new LinkedHashMap<>();
+            notification.put("jsonrpc", "2.0");
+            notification.put("method", "lex/batch");
+            notification.put("params", params);
+            send(notification);
+            tokens.clear();
+            diagnostics.clear();
+        }
+    }
+
+    private static final class ResponseError extends Exception {
+        private static final long serialVersionUID = 1L;
+
+        private final int code;
+
+        private ResponseError(int code, String message) {
+            super(message);
+            this.code = code;
+        }
+    }
+}
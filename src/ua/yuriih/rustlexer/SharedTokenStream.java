@@ -0,0 +1,74 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.UncheckedIOException;
+import java.util.List;
+
+/**
+ * Immutable tokens and source, safe to hand to several threads at once without copying or locking.
+ * Sub-streams made with {@link #slice} share the same tokens and source.
+ */
+public final class SharedTokenStream implements Iterable<Token> {
+    private final List<Token> tokens;
+    private final String source;
+
+    private SharedTokenStream(List<Token> tokens, String source) {
+        this.tokens = tokens;
+        this.source = source;
+    }
+
+    /**
+     * @param source the text the tokens were lexed from, can be null
+     */
+    public static SharedTokenStream of(List<Token> tokens, String source) {
+        return new SharedTokenStream(List.copyOf(tokens), source);
+    }
+
+    public static SharedTokenStream lex(String source, LexerOptions options) {
+        try {
+            return of(Lexer.fromString(source, options).parse(), source);
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+    }
+
+    public int size() {
+        return tokens.size();
+    }
+
+    public Token get(int index) {
+        return tokens.get(index);
+    }
+
+    public List<Token> tokens() {
+        return tokens;
+    }
+
+    public String source() {
+        return source;
+    }
+
+    /**
+     * Same as {@link TokenStream#text}.
+     */
+    public CharSequence text(Token token) {
+        return TokenStream.text(source, token);
+    }
+
+    /**
+     * Tokens [from, to), without copying.
+     */
+    public SharedTokenStream slice(int from, int to) {
+        return new SharedTokenStream(tokens.subList(from, to), source);
+    }
+
+    public TokenStream toTokenStream() {
+        return new TokenStream(tokens, source);
+    }
+
+    @Override
+    public TokenIterator iterator() {
+        return TokenIterator.of(tokens);
+    }
+}
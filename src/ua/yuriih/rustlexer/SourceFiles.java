@@ -0,0 +1,92 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.nio.file.FileSystems;
+import java.nio.file.FileVisitResult;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.nio.file.PathMatcher;
+import java.nio.file.SimpleFileVisitor;
+import java.nio.file.attribute.BasicFileAttributes;
+import java.util.ArrayList;
+import java.util.HashMap;
+import java.util.List;
+
+/**
+ * Finds Rust source files under a directory, skipping whatever .gitignore and .ignore files exclude.
+ */
+public final class SourceFiles {
+    private static final String[] IGNORE_FILES = {".gitignore", ".ignore"};
+
+    private SourceFiles() {
+    }
+
+    public static List<Path> find(Path root) throws IOException {
+        return find(root, List.of());
+    }
+
+    /**
+     * @param excludes extra globs, matched against the path relative to root and against the file name
+     * @return .rs files in a stable (sorted) order; root itself if it's a file
+     */
+    public static List<Path> find(Path root, List<String> excludes) throws IOException {
+        ArrayList<PathMatcher> excludeMatchers = new ArrayList<>();
+        for (String exclude : excludes)
+            excludeMatchers.add(FileSystems.getDefault().getPathMatcher("glob:" + exclude));
+
+        if (!Files.isDirectory(root))
+            return List.of(root);
+
+        HashMap<Path, List<IgnoreRules>> rulesByDir = new HashMap<>();
+        ArrayList<Path> files = new ArrayList<>();
+        Files.walkFileTree(root, new SimpleFileVisitor<>() {
+            @Override
+            public FileVisitResult preVisitDirectory(Path dir, BasicFileAttributes attrs) throws IOException {
+                if (!dir.equals(root)) {
+                    if (dir.getFileName().toString().equals(".git") || isIgnored(dir, true))
+                        return FileVisitResult.SKIP_SUBTREE;
+                }
+                ArrayList<IgnoreRules> rules = new ArrayList<>();
+                for (String name : IGNORE_FILES) {
+                    Path file = dir.resolve(name);
+                    if (Files.isRegularFile(file)) {
+                        IgnoreRules loaded = IgnoreRules.load(file);
+                        if (!loaded.isEmpty())
+                            rules.add(loaded);
+                    }
+                }
+                rulesByDir.put(dir, rules);
+                return FileVisitResult.CONTINUE;
+            }
+
+            @Override
+            public FileVisitResult visitFile(Path file, BasicFileAttributes attrs) {
+                if (file.getFileName().toString().endsWith(".rs") && !isIgnored(file, false))
+                    files.add(file);
+                return FileVisitResult.CONTINUE;
+            }
+
+            private boolean isIgnored(Path path, boolean isDirectory) {
+                Path relative = root.relativize(path);
+                for (PathMatcher matcher : excludeMatchers) {
+                    if (matcher.matches(relative) || matcher.matches(path.getFileName()))
+                        return true;
+                }
+
+                // rules in deeper directories override the ones closer to the root
+                Boolean ignored = null;
+                for (Path dir = path.getParent(); dir != null && dir.startsWith(root); dir = dir.getParent()) {
+                    String relativeToDir = dir.relativize(path).toString().replace('\\', '/');
+                    List<IgnoreRules> rules = rulesByDir.getOrDefault(dir, List.of());
+                    for (int i = rules.size() - 1; i >= 0 && ignored == null; i--)
+                        ignored = rules.get(i).match(relativeToDir, isDirectory);
+                    if (ignored != null)
+                        break;
+                }
+                return ignored != null && ignored;
+            }
+        });
+        files.sort(null);
+        return files;
+    }
+}
@@ -0,0 +1,204 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.UncheckedIOException;
+import java.nio.charset.StandardCharsets;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.List;
+
+/**
+ * Puts many files into one offset space, so a {@link Span} from any of them can be resolved back to a file and position.
+ * <p>
+ * Each file gets a range of global offsets, starting right after the previous file's range (plus one,
+ * so an empty file still has an offset of its own).
+ */
+public final class SourceMap {
+    private final ArrayList<SourceFile> files = new ArrayList<>();
+    private int nextBase = 0;
+
+    public FileId add(String name, String source) {
+        FileId id = new FileId(files.size());
+        files.add(new SourceFile(name, source, nextBase));
+        nextBase += source.length() + 1;
+        return id;
+    }
+
+    /**
+     * Reads the file as UTF-8, its name is the path.
+     */
+    public FileId add(Path path) throws IOException {
+        return add(path.toString(), new String(Files.readAllBytes(path), StandardCharsets.UTF_8));
+    }
+
+    public int size() {
+        return files.size();
+    }
+
+    public String name(FileId file) {
+        return get(file).name;
+    }
+
+    public String source(FileId file) {
+        return get(file).source;
+    }
+
+    /**
+     * Lexes one of the files, token offsets are relative to that file; use {@link #span} for global ones.
+     */
+    public List<Token> lex(FileId file, LexerOptions options) {
+        try {
+            return Lexer.fromString(get(file).source, options).parse();
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+    }
+
+    /**
+     * @param token token lexed from the given file
+     */
+    public Span span(FileId file, Token token) {
+        int base = get(file).base;
+        return new Span(file, base + token.offset, base + token.end());
+    }
+
+    /**
+     * @return file, line and column (0-based, like in {@link Token}) of a global offset, or null if it's outside every file
+     */
+    public Location lookup(int offset) {
+        int low = 0;
+        int high = files.size() - 1;
+        while (low <= high) {
+            int mid = (low + high) >>> 1;
+            SourceFile file = files.get(mid);
+            if (offset < file.base)
+                high = mid - 1;
+            else if (offset > file.base + file.source.length())
+                low = mid + 1;
+            else
+                return file.location(new FileId(mid), offset - file.base);
+        }
+        return null;
+    }
+
+    /**
+     * @return where the span starts
+     */
+    public Location lookup(Span span) {
+        return get(span.file).location(span.file, span.start - get(span.file).base);
+    }
+
+    private SourceFile get(FileId file) {
+        if (file.index >= files.size())
+            throw new IllegalArgumentException("File is not from this source map");
+        return files.get(file.index);
+    }
+
+    private static final class SourceFile {
+        final String name;
+        final String source;
+        final int base;
+        private int[] lineStarts;
+
+        SourceFile(String name, String source, int base) {
+            this.name = name;
+            this.source = source;
+            this.base = base;
+        }
+
+        Location location(FileId id, int offset) {
+            if (lineStarts == null) {
+                int[] starts = new int[16];
+                int count = 1;
+                for (int i = 0; i < source.length(); i++) {
+                    if (source.charAt(i) == '\n') {
+                        if (count == starts.length)
+                            starts = Arrays.copyOf(starts, count * 2);
+                        starts[count++] = i + 1;
+                    }
+                }
+                lineStarts = Arrays.copyOf(starts, count);
+            }
+            int line = Arrays.binarySearch(lineStarts, offset);
+            if (line < 0)
+                line = -line - 2;
+            return new Location(id, line, offset - lineStarts[line]);
+        }
+    }
+
+    public static final class FileId {
+        public final int index;
+
+        private FileId(int index) {
+            this.index = index;
+        }
+
+        @Override
+        public boolean equals(Object o) {
+            return o instanceof FileId && index == ((FileId) o).index;
+        }
+
+        @Override
+        public int hashCode() {
+            return index;
+        }
+
+        @Override
+        public String toString() {
+            return "FileId(" + index + ")";
+        }
+    }
+
+    /**
+     * Global offsets [start, end) of a token in the source map.
+     */
+    public static final class Span {
+        public final FileId file;
+        public final int start;
+        public final int end;
+
+        private Span(FileId file, int start, int end) {
+            this.file = file;
+            this.start = start;
+            this.end = end;
+        }
+
+        @Override
+        public boolean equals(Object o) {
+            if (!(o instanceof Span))
+                return false;
+            Span span = (Span) o;
+            return file.equals(span.file) && start == span.start && end == span.end;
+        }
+
+        @Override
+        public int hashCode() {
+            return 31 * (31 * file.hashCode() + start) + end;
+        }
+
+        @Override
+        public String toString() {
+            return file.index + ":" + start + ".." + end;
+        }
+    }
+
+    public static final class Location {
+        public final FileId file;
+        public final int line;
+        public final int column;
+
+        private Location(FileId file, int line, int column) {
+            this.file = file;
+            this.line = line;
+            this.column = column;
+        }
+
+        @Override
+        public String toString() {
+            return file.index + ":" + line + ":" + column;
+        }
+    }
+}
@@ -0,0 +1,166 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Result of {@link TokenStream#concat}, {@link TokenStream#replace} or {@link TokenStream#delete}:
+ * the new stream and a table mapping its offsets back to the streams it was made from.
+ * <p>
+ * Tokens are laid out as if the new stream's source was the original text of each contiguous run of tokens,
+ * with a single space between runs. Line, column and offset of every token are updated to match, and tokens that
+ * were lexed from a source become {@link Token.Provenance#SPLICED}.
+ */
+public final class Splice {
+    public final TokenStream stream;
+    private final List<Range> ranges;
+
+    private Splice(TokenStream stream, List<Range> ranges) {
+        this.stream = stream;
+        this.ranges = ranges;
+    }
+
+    /**
+     * @return where the char at the new offset came from, or null if it's between runs
+     * or part of a {@link Token#isSynthetic() synthetic} token
+     */
+    public Origin origin(int offset) {
+        int low = 0;
+        int high = ranges.size() - 1;
+        while (low <= high) {
+            int mid = (low + high) >>> 1;
+            Range range = ranges.get(mid);
+            if (offset < range.start)
+                high = mid - 1;
+            else if (offset >= range.end)
+                low = mid + 1;
+            else
+                return new Origin(range.source, range.originalStart + offset - range.start);
+        }
+        return null;
+    }
+
+    /**
+     * The remap table, sorted by new offset.
+     */
+    public List<Range> ranges() {
+        return Collections.unmodifiableList(ranges);
+    }
+
+    static final class Segment {
+        final int source;
+        final List<Token> tokens;
+
+        Segment(int source, List<Token> tokens) {
+            this.source = source;
+            this.tokens = tokens;
+        }
+    }
+
+    static Splice of(List<Segment> segments) {
+        ArrayList<Token> result = new ArrayList<>();
+        ArrayList<Range> ranges = new ArrayList<>();
+        int line = 0;
+        int column = 0;
+        int offset = 0;
+        for (Segment segment : segments) {
+            List<Token> tokens = segment.tokens;
+            int runStart = 0;
+            while (runStart < tokens.size()) {
+                if (!result.isEmpty()) {
+                    //separate runs with a space
+                    column++;
+                    offset++;
+                }
+
+                Token first = tokens.get(runStart);
+                if (first.isSynthetic()) {
+                    Token token = first.withPosition(line, column, offset).withProvenance(Token.Provenance.SYNTHETIC);
+                    result.add(token);
+                    int[] end = endPosition(token);
+                    line = end[0];
+                    column = end[1];
+                    offset = token.end();
+                    runStart++;
+                    continue;
+                }
+
+                //real tokens stay in the same run as long as they follow each other in the original source
+                int runEnd = runStart + 1;
+                while (runEnd < tokens.size() && !tokens.get(runEnd).isSynthetic()
+                        && tokens.get(runEnd).offset >= tokens.get(runEnd - 1).end())
+                    runEnd++;
+
+                Token last = null;
+                for (int i = runStart; i < runEnd; i++) {
+                    Token token = tokens.get(i);
+                    int newColumn = token.line == first.line ? column + token.column - first.column : token.column;
+                    last = token.withPosition(line + token.line - first.line, newColumn,
+                            offset + token.offset - first.offset);
+                    if (last.provenance() == Token.Provenance.SOURCE)
+                        last = last.withProvenance(Token.Provenance.SPLICED);
+                    result.add(last);
+                }
+                ranges.add(new Range(offset, last.end(), segment.source, first.offset));
+                int[] end = endPosition(last);
+                line = end[0];
+                column = end[1];
+                offset = last.end();
+                runStart = runEnd;
+            }
+        }
+        return new Splice(new TokenStream(result), ranges);
+    }
+
+    private static int[] endPosition(Token token) {
+        String text = token.text();
+        int line = token.line;
+        int column = token.column;
+        if (text == null)
+            return new int[] {line, column + token.length};
+        for (int i = 0; i < text.length(); i++) {
+            if (text.charAt(i) == '\n') {
+                line++;
+                column = 0;
+            } else {
+                column++;
+            }
+        }
+        return new int[] {line, column};
+    }
+
+    /**
+     * Chars [start, end) of the new stream's text come from the source stream at originalStart.
+     */
+    public static final class Range {
+        public final int start;
+        public final int end;
+        /** 0 for the stream the operation was called on, 1 for the other one */
+        public final int source;
+        public final int originalStart;
+
+        private Range(int start, int end, int source, int originalStart) {
+            this.start = start;
+            this.end = end;
+            this.source = source;
+            this.originalStart = originalStart;
+        }
+    }
+
+    public static final class Origin {
+        /** 0 for the stream the operation was called on, 1 for the other one */
+        public final int source;
+        public final int offset;
+
+        private Origin(int source, int offset) {
+            this.source = source;
+            this.offset = offset;
+        }
+
+        @Override
+        public String toString() {
+            return source + ":" + offset;
+        }
+    }
+}
@@ -8,6 +8,9 @@ enum State {
     MAYBE_BYTE_OR_BYTE_STRING,
     CHAR_LITERAL_OR_LIFETIME_OR_LABEL,
     LIFETIME_OR_LABEL,
+    RAW_IDENTIFIER,
+    RAW_LIFETIME_START,
+    RAW_LIFETIME_OR_LABEL,
     STRING_LITERAL,
     CHAR_LITERAL_ESCAPED,
     CHAR_LITERAL_END,
@@ -18,6 +21,8 @@ enum State {
     RAW_STRING_LITERAL_START,
     RAW_STRING_LITERAL_MAYBE_END,
     NUMBER_LITERAL,
+    LITERAL_SUFFIX,
+    QUOTED_LITERAL_END,
     NUMBER_LITERAL_START_ZERO,
     INT_LITERAL_HEX,
     INT_LITERAL_OCT,
@@ -25,6 +30,7 @@ enum State {
     INT_LITERAL_HEX_NO_DIGITS,
     INT_LITERAL_OCT_NO_DIGITS,
     INT_LITERAL_BIN_NO_DIGITS,
+    INT_LITERAL_INVALID_DIGIT,
     FLOAT_LITERAL_DOT,
     FLOAT_LITERAL_EXPONENT,
     FLOAT_LITERAL_EXPONENT_START,
@@ -55,7 +61,10 @@ enum State {
     EQ,
     DOT,
     DOT_DOT,
-    COLON;
+    COLON,
+    CUSTOM_TOKEN,
+    SHEBANG,
+    WHITESPACE;
 
     enum StringEscape {
         NONE,
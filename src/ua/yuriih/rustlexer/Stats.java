@@ -0,0 +1,263 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collection;
+import java.util.Collections;
+import java.util.EnumMap;
+import java.util.EnumSet;
+import java.util.HashMap;
+import java.util.List;
+import java.util.Map;
+import java.util.Set;
+
+public final class Stats {
+    private Stats() {
+    }
+
+    public static Histogram histogram(List<Token> tokens) {
+        Histogram histogram = new Histogram();
+        for (Token token : tokens) {
+            histogram.kinds.merge(token.type, 1, Integer::sum);
+            if (token.type == TokenType.IDENTIFIER || token.type == TokenType.RAW_IDENTIFIER)
+                histogram.identifiers.merge(token.value, 1, Integer::sum);
+        }
+        return histogram;
+    }
+
+    /**
+     * Which token types the files have and which they don't, to check that a test corpus has every type
+     * a parser handles. Errors are left out, and so are whitespace and comments unless the files were lexed
+     * with trivia.
+     *
+     * @param files tokens of each file
+     */
+    public static Coverage coverage(Collection<? extends List<Token>> files) {
+        Coverage coverage = new Coverage();
+        for (List<Token> tokens : files) {
+            EnumSet<TokenType> inFile = EnumSet.noneOf(TokenType.class);
+            for (Token token : tokens) {
+                if (token instanceof LexError)
+                    continue;
+                coverage.counts.merge(token.type, 1, Integer::sum);
+                if (inFile.add(token.type))
+                    coverage.files.merge(token.type, 1, Integer::sum);
+            }
+        }
+        return coverage;
+    }
+
+    /**
+     * Finds the extremes that tend to break parsers: deepest delimiter nesting, deepest block comment nesting,
+     * longest token and longest line. Comments are only seen if the tokens were lexed with trivia.
+     *
+     * @param source the text the tokens were lexed from
+     */
+    public static Extremes extremes(String source, List<Token> tokens) {
+        Extreme nestingDepth = Extreme.NONE;
+        Extreme commentNesting = Extreme.NONE;
+        Extreme longestToken = Extreme.NONE;
+        Extreme longestLine = Extreme.NONE;
+        int depth = 0;
+        for (Token token : tokens) {
+            if (token.isSynthetic())
+                continue;
+            if (TokenTree.isOpening(token.type)) {
+                depth++;
+                if (depth > nestingDepth.value)
+                    nestingDepth = Extreme.of(depth, token);
+            } else if (TokenTree.isClosing(token.type)) {
+                depth = Math.max(depth - 1, 0);
+            }
+
+            if (token.length > longestToken.value)
+                longestToken = Extreme.of(token.length, token);
+
+            if ((token.type == TokenType.COMMENT || token.type == TokenType.COMMENT_INNER_DOC
+                    || token.type == TokenType.COMMENT_OUTER_DOC) && source.startsWith("/*", token.offset)) {
+                int commentDepth = commentNesting(source, token.offset, Math.min(token.end(), source.length()));
+                if (commentDepth > commentNesting.value)
+                    commentNesting = Extreme.of(commentDepth, token);
+            }
+        }
+
+        int line = 0;
+        int lineStart = 0;
+        while (lineStart <= source.length()) {
+            int lineEnd = source.indexOf('\n', lineStart);
+            if (lineEnd < 0)
+                lineEnd = source.length();
+            int length = lineEnd - lineStart;
+            if (length > 0 && source.charAt(lineEnd - 1) == '\r')
+                length--;
+            if (length > longestLine.value)
+                longestLine = new Extreme(length, line, 0, lineStart, length);
+            line++;
+            lineStart = lineEnd + 1;
+        }
+        return new Extremes(nestingDepth, commentNesting, longestToken, longestLine);
+    }
+
+    private static int commentNesting(String source, int start, int end) {
+        int depth = 0;
+        int max = 0;
+        for (int i = start; i + 1 < end; i++) {
+            if (source.charAt(i) == '/' && source.charAt(i + 1) == '*') {
+                max = Math.max(max, ++depth);
+                i++;
+            } else if (source.charAt(i) == '*' && source.charAt(i + 1) == '/') {
+                depth--;
+                i++;
+            }
+        }
+        return max;
+    }
+
+    public static final class Extremes {
+        /** Deepest (), [] or {} nesting, at the opening delimiter that reached it */
+        public final Extreme nestingDepth;
+        /** Deepest block comment nesting (1 for a comment without nested ones), at the comment */
+        public final Extreme commentNesting;
+        /** Length of the longest token, in chars */
+        public final Extreme longestToken;
+        /** Length of the longest line, in chars, without the line break */
+        public final Extreme longestLine;
+
+        private Extremes(Extreme nestingDepth, Extreme commentNesting, Extreme longestToken, Extreme longestLine) {
+            this.nestingDepth = nestingDepth;
+            this.commentNesting = commentNesting;
+            this.longestToken = longestToken;
+            this.longestLine = longestLine;
+        }
+
+        @Override
+        public String toString() {
+            return "nesting depth " + nestingDepth + ", comment nesting " + commentNesting
+                    + ", longest token " + longestToken + ", longest line " + longestLine;
+        }
+    }
+
+    /**
+     * A value and where it was found, 0-based.
+     */
+    public static final class Extreme {
+        static final Extreme NONE = new Extreme(0, 0, 0, 0, 0);
+
+        public final int value;
+        public final int line;
+        public final int column;
+        public final int offset;
+        public final int length;
+
+        private Extreme(int value, int line, int column, int offset, int length) {
+            this.value = value;
+            this.line = line;
+            this.column = column;
+            this.offset = offset;
+            this.length = length;
+        }
+
+        private static Extreme of(int value, Token token) {
+            return new Extreme(value, token.line, token.column, token.offset, token.length);
+        }
+
+        @Override
+        public String toString() {
+            return value + " at " + (line + 1) + ":" + (column + 1);
+        }
+    }
+
+    public static final class Histogram {
+        private final EnumMap<TokenType, Integer> kinds = new EnumMap<>(TokenType.class);
+        private final HashMap<String, Integer> identifiers = new HashMap<>();
+
+        private Histogram() {
+        }
+
+        public int count(TokenType type) {
+            return kinds.getOrDefault(type, 0);
+        }
+
+        public Map<TokenType, Integer> kinds() {
+            return Collections.unmodifiableMap(kinds);
+        }
+
+        public Map<TokenType, Integer> keywords() {
+            EnumMap<TokenType, Integer> keywords = new EnumMap<>(TokenType.class);
+            for (Map.Entry<TokenType, Integer> entry : kinds.entrySet()) {
+                if (entry.getKey().isKeyword())
+                    keywords.put(entry.getKey(), entry.getValue());
+            }
+            return keywords;
+        }
+
+        public int identifierCount(String name) {
+            return identifiers.getOrDefault(name, 0);
+        }
+
+        /**
+         * Most frequent identifiers, ties broken alphabetically so the result is stable.
+         */
+        public List<Map.Entry<String, Integer>> topIdentifiers(int n) {
+            if (n < 0)
+                throw new IllegalArgumentException("n can't be negative");
+            ArrayList<Map.Entry<String, Integer>> entries = new ArrayList<>(identifiers.size());
+            identifiers.forEach((name, count) -> entries.add(Map.entry(name, count)));
+            entries.sort(Map.Entry.<String, Integer>comparingByValue().reversed()
+                    .thenComparing(Map.Entry.comparingByKey()));
+            return List.copyOf(entries.subList(0, Math.min(n, entries.size())));
+        }
+    }
+
+    public static final class Coverage {
+        private final EnumMap<TokenType, Integer> counts = new EnumMap<>(TokenType.class);
+        private final EnumMap<TokenType, Integer> files = new EnumMap<>(TokenType.class);
+
+        private Coverage() {
+        }
+
+        public boolean isCovered(TokenType type) {
+            return counts.containsKey(type);
+        }
+
+        /** How many tokens of the type there are in all files */
+        public int count(TokenType type) {
+            return counts.getOrDefault(type, 0);
+        }
+
+        /** How many files have at least one token of the type */
+        public int fileCount(TokenType type) {
+            return files.getOrDefault(type, 0);
+        }
+
+        public Set<TokenType> covered() {
+            EnumSet<TokenType> covered = EnumSet.noneOf(TokenType.class);
+            covered.addAll(counts.keySet());
+            return Collections.unmodifiableSet(covered);
+        }
+
+        /**
+         * Types the files don't have at all.
+         */
+        public Set<TokenType> missing() {
+            return missing(EnumSet.allOf(TokenType.class));
+        }
+
+        /**
+         * @param expected the types that should be covered, like the ones a parser handles
+         * @return the expected types the files don't have
+         */
+        public Set<TokenType> missing(Collection<TokenType> expected) {
+            EnumSet<TokenType> missing = EnumSet.noneOf(TokenType.class);
+            missing.addAll(expected);
+            missing.removeAll(counts.keySet());
+            return Collections.unmodifiableSet(missing);
+        }
+
+        @Override
+        public String toString() {
+            Set<TokenType> missing = missing();
+            return counts.size() + " of " + TokenType.values().length + " token types covered"
+                    + (missing.isEmpty() ? "" : ", missing " + missing);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+package ua.yuriih.rustlexer;
+
+import java.util.EnumMap;
+
+/**
+ * Maps tokens to TextMate scopes, following the names used by the usual Rust grammars
+ * (e.g. {@code keyword.control.rust}, {@code string.quoted.double.rust}), for highlighting with TextMate themes.
+ */
+public final class TextMateScopes {
+    public static final TextMateScopes DEFAULT = new TextMateScopes(new EnumMap<>(TokenType.class));
+
+    private final EnumMap<TokenType, String> overrides;
+
+    private TextMateScopes(EnumMap<TokenType, String> overrides) {
+        this.overrides = overrides;
+    }
+
+    /**
+     * @return a copy that uses the given scope for the token type, or no scope if it's null
+     */
+    public TextMateScopes withOverride(TokenType type, String scope) {
+        EnumMap<TokenType, String> overrides = new EnumMap<>(this.overrides);
+        overrides.put(type, scope);
+        return new TextMateScopes(overrides);
+    }
+
+    /**
+     * Block comments are told from line comments by the token's text, so for tokens lexed without
+     * {@link LexerOptions.Builder#storeText stored text} use {@link #scope(Token, CharSequence)}.
+     *
+     * @return the token's scope, or null if it has none (whitespace)
+     * @throws IllegalStateException if the token is a comment without text
+     */
+    public String scope(Token token) {
+        return scope(token, null);
+    }
+
+    /**
+     * Same as {@link #scope(Token)}, comments without text are looked up in the source.
+     *
+     * @param source what the token was lexed from, only used for comments without text
+     * @throws IllegalStateException if the token is a comment without text and there's no source
+     */
+    public String scope(Token token, CharSequence source) {
+        if (overrides.containsKey(token.type))
+            return overrides.get(token.type);
+        boolean block = false;
+        if (isComment(token.type)) {
+            String text = token.text();
+            if (text != null)
+                block = text.startsWith("/*");
+            else if (source != null && !token.isSynthetic())
+                block = token.length >= 2 && source.charAt(token.offset) == '/'
+                        && source.charAt(token.offset + 1) == '*';
+            else
+                throw new IllegalStateException("Comment token has no text, it was lexed without storing text");
+        }
+        return switch (token.type) {
+            case COMMENT -> block ? "comment.block.rust" : "comment.line.double-slash.rust";
+            case COMMENT_INNER_DOC, COMMENT_OUTER_DOC ->
+                    block ? "comment.block.documentation.rust" : "comment.line.documentation.rust";
+            default -> scope(token.type);
+        };
+    }
+
+    private static boolean isComment(TokenType type) {
+        return type == TokenType.COMMENT || type == TokenType.COMMENT_INNER_DOC || type == TokenType.COMMENT_OUTER_DOC;
+    }
+
+    /**
+     * @return the scope for tokens of this type, or null if they have none (whitespace).
+     * Comments are assumed to be line comments, {@link #scope(Token)} can tell them apart.
+     */
+    public String scope(TokenType type) {
+        if (overrides.containsKey(type))
+            return overrides.get(type);
+        return switch (type) {
+            case BREAK, CONTINUE, ELSE, FOR, IF, IN, LOOP, MATCH, RETURN, WHILE, AWAIT, YIELD, TRY, DO, BECOME ->
+                    "keyword.control.rust";
+            case FN -> "keyword.other.fn.rust";
+            case STRUCT, ENUM, TRAIT, TYPE, UNION, MOD, IMPL, LET, MACRO -> "storage.type.rust";
+            case PUB, MUT, REF, MOVE, UNSAFE, ASYNC, GEN, DYN, EXTERN, CONST, STATIC -> "storage.modifier.rust";
+            case AS, CRATE, SUPER, USE, WHERE, BOX, ABSTRACT, FINAL, OVERRIDE, PRIV, TYPEOF, UNSIZED, VIRTUAL,
+                    CUSTOM_KEYWORD -> "keyword.other.rust";
+            case SELF_VALUE -> "variable.language.self.rust";
+            case SELF_TYPE -> "entity.name.type.self.rust";
+            case TRUE, FALSE -> "constant.language.bool.rust";
+
+            case IDENTIFIER, RAW_IDENTIFIER -> "variable.other.rust";
+            case COMMENT -> "comment.line.double-slash.rust";
+            case COMMENT_INNER_DOC, COMMENT_OUTER_DOC -> "comment.line.documentation.rust";
+            case LABEL, RAW_LABEL -> "entity.name.label.rust";
+            case LIFETIME, RAW_LIFETIME, STATIC_LIFETIME -> "entity.name.type.lifetime.rust";
+
+            case CHAR_LITERAL -> "string.quoted.single.char.rust";
+            case BYTE_LITERAL -> "string.quoted.single.byte.rust";
+            case STRING_LITERAL -> "string.quoted.double.rust";
+            case BYTE_STRING_LITERAL -> "string.quoted.double.byte.rust";
+            case RAW_STRING_LITERAL -> "string.quoted.double.raw.rust";
+            case RAW_BYTE_STRING_LITERAL -> "string.quoted.double.raw.byte.rust";
+            case INT_LITERAL_DEC -> "constant.numeric.decimal.rust";
+            case INT_LITERAL_HEX -> "constant.numeric.hex.rust";
+            case INT_LITERAL_OCTAL -> "constant.numeric.oct.rust";
+            case INT_LITERAL_BIN -> "constant.numeric.bin.rust";
+            case FLOAT_LITERAL -> "constant.numeric.float.rust";
+
+            case PLUS, MINUS, STAR, SLASH, PERCENT -> "keyword.operator.arithmetic.rust";
+            case CARET, AND, OR, SHL, SHR -> "keyword.operator.bitwise.rust";
+            case NOT, AND_AND, OR_OR -> "keyword.operator.logical.rust";
+            case EQ, PLUS_EQ, MINUS_EQ, STAR_EQ, SLASH_EQ, PERCENT_EQ, CARET_EQ, AND_EQ, OR_EQ, SHL_EQ, SHR_EQ ->
+                    "keyword.operator.assignment.rust";
+            case EQ_EQ, NE, GT, LT, GE, LE -> "keyword.operator.comparison.rust";
+            case DOT_DOT, DOT_DOT_DOT, DOT_DOT_EQ -> "keyword.operator.range.rust";
+            case DOT -> "keyword.operator.access.dot.rust";
+            case PATH_SEPARATOR -> "keyword.operator.namespace.rust";
+            case R_ARROW -> "keyword.operator.arrow.skinny.rust";
+            case FAT_ARROW -> "keyword.operator.arrow.fat.rust";
+            case QUESTION -> "keyword.operator.question.rust";
+            case AT -> "keyword.operator.at.rust";
+            case DOLLAR -> "keyword.operator.macro.dollar.rust";
+            case UNDERSCORE -> "variable.language.wildcard.rust";
+            case COMMA -> "punctuation.comma.rust";
+            case SEMICOLON -> "punctuation.semi.rust";
+            case COLON -> "punctuation.colon.rust";
+            case POUND -> "punctuation.definition.attribute.rust";
+            case CURLY_L, CURLY_R -> "punctuation.brackets.curly.rust";
+            case SQUARE_L, SQUARE_R -> "punctuation.brackets.square.rust";
+            case PAREN_L, PAREN_R -> "punctuation.brackets.round.rust";
+
+            case CUSTOM -> "meta.custom.rust";
+            case ERROR -> "invalid.illegal.rust";
+            case SHEBANG -> "comment.line.shebang.rust";
+            case WHITESPACE -> null;
+        };
+    }
+}
@@ -0,0 +1,394 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.nio.charset.StandardCharsets;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.EnumMap;
+import java.util.Locale;
+import java.util.Set;
+
+/**
+ * Colors and text styles for the {@link Highlighter}, per token type. Create it with {@link #builder()},
+ * use one of the built-in themes or load one with {@link #parse}.
+ * <p>
+ * Colors are either hex RGB ("#rrggbb") or one of the 16 terminal colors ("red", "bright-black"...),
+ * which the terminal's own palette decides.
+ */
+public final class Theme {
+    private static final Set<String> NAMED_COLORS = Set.of("black", "red", "green", "yellow", "blue", "magenta",
+            "cyan", "white", "bright-black", "bright-red", "bright-green", "bright-yellow", "bright-blue",
+            "bright-magenta", "bright-cyan", "bright-white");
+
+    /** Uses the terminal's palette, this is what the CLI uses by default */
+    public static final Theme TERMINAL = builder()
+            .name("terminal")
+            .style(Group.KEYWORD, Style.PLAIN.foreground("magenta"))
+            .style(Group.IDENTIFIER, Style.PLAIN.foreground("cyan"))
+            .style(Group.COMMENT, Style.PLAIN.foreground("bright-black").italic())
+            .style(Group.DOC_COMMENT, Style.PLAIN.foreground("bright-black").italic())
+            .style(Group.LIFETIME, Style.PLAIN.foreground("yellow"))
+            .style(Group.CHAR, Style.PLAIN.foreground("blue").italic())
+            .style(Group.STRING, Style.PLAIN.foreground("green"))
+            .style(Group.NUMBER, Style.PLAIN.foreground("blue"))
+            .style(Group.ERROR, Style.PLAIN.background("red"))
+            //'static is highlighted like a keyword
+            .style(TokenType.STATIC_LIFETIME, Style.PLAIN.foreground("magenta"))
+            .build();
+
+    public static final Theme LIGHT = builder()
+            .name("light")
+            .foreground("#383a42").background("#fafafa")
+            .style(Group.KEYWORD, Style.PLAIN.foreground("#a626a4"))
+            .style(Group.IDENTIFIER, Style.PLAIN.foreground("#383a42"))
+            .style(Group.COMMENT, Style.PLAIN.foreground("#a0a1a7").italic())
+            .style(Group.DOC_COMMENT, Style.PLAIN.foreground("#50a14f").italic())
+            .style(Group.LIFETIME, Style.PLAIN.foreground("#c18401"))
+            .style(Group.CHAR, Style.PLAIN.foreground("#0184bc"))
+            .style(Group.STRING, Style.PLAIN.foreground("#50a14f"))
+            .style(Group.NUMBER, Style.PLAIN.foreground("#986801"))
+            .style(Group.OPERATOR, Style.PLAIN.foreground("#0184bc"))
+            .style(Group.ERROR, Style.PLAIN.foreground("#e45649").underline())
+            .build();
+
+    public static final Theme DARK = builder()
+            .name("dark")
+            .foreground("#abb2bf").background("#282c34")
+            .style(Group.KEYWORD, Style.PLAIN.foreground("#c678dd"))
+            .style(Group.IDENTIFIER, Style.PLAIN.foreground("#e06c75"))
+            .style(Group.COMMENT, Style.PLAIN.foreground("#5c6370").italic())
+            .style(Group.DOC_COMMENT, Style.PLAIN.foreground("#7f848e").italic())
+            .style(Group.LIFETIME, Style.PLAIN.foreground("#e5c07b"))
+            .style(Group.CHAR, Style.PLAIN.foreground("#56b6c2"))
+            .style(Group.STRING, Style.PLAIN.foreground("#98c379"))
+            .style(Group.NUMBER, Style.PLAIN.foreground("#d19a66"))
+            .style(Group.OPERATOR, Style.PLAIN.foreground("#56b6c2"))
+            .style(Group.ERROR, Style.PLAIN.foreground("#ffffff").background("#be5046"))
+            .build();
+
+    public static final Theme HIGH_CONTRAST = builder()
+            .name("high-contrast")
+            .foreground("#ffffff").background("#000000")
+            .style(Group.KEYWORD, Style.PLAIN.foreground("#ffff00").bold())
+            .style(Group.IDENTIFIER, Style.PLAIN.foreground("#ffffff"))
+            .style(Group.COMMENT, Style.PLAIN.foreground("#00ff00").italic())
+            .style(Group.DOC_COMMENT, Style.PLAIN.foreground("#00ff00").italic().bold())
+            .style(Group.LIFETIME, Style.PLAIN.foreground("#ff80ff"))
+            .style(Group.CHAR, Style.PLAIN.foreground("#00ffff"))
+            .style(Group.STRING, Style.PLAIN.foreground("#00ffff"))
+            .style(Group.NUMBER, Style.PLAIN.foreground("#ff80ff"))
+            .style(Group.OPERATOR, Style.PLAIN.foreground("#ffffff").bold())
+            .style(Group.ERROR, Style.PLAIN.foreground("#000000").background("#ff0000").bold().underline())
+            .build();
+
+    public final String name;
+    /** Default text color, null to leave it to the terminal or page */
+    public final String foreground;
+    /** Default background color, null to leave it to the terminal or page */
+    public final String background;
+    private final EnumMap<TokenType, Style> styles;
+
+    private Theme(Builder builder) {
+        this.name = builder.name;
+        this.foreground = builder.foreground;
+        this.background = builder.background;
+        this.styles = new EnumMap<>(builder.styles);
+    }
+
+    public Style style(TokenType type) {
+        return styles.getOrDefault(type, Style.PLAIN);
+    }
+
+    /**
+     * @return one of the built-in themes by name ("terminal", "light", "dark" or "high-contrast"), or null
+     */
+    public static Theme named(String name) {
+        for (Theme theme : new Theme[] {TERMINAL, LIGHT, DARK, HIGH_CONTRAST}) {
+            if (theme.name.equals(name))
+                return theme;
+        }
+        return null;
+    }
+
+    public static Theme load(Path path) throws IOException {
+        return parse(new String(Files.readAllBytes(path), StandardCharsets.UTF_8));
+    }
+
+    /**
+     * Reads a theme from a small subset of TOML:
+     * <pre>
+     * name = "solarized"
+     * foreground = "#839496"
+     * background = "#002b36"
+     *
+     * [keyword]
+     * color = "#859900"
+     * bold = true
+     *
+     * [int_literal_hex]
+     * color = "#d33682"
+     * </pre>
+     * Tables are named after a {@link Group} or a {@link TokenType} (in lower case), their keys are color,
+     * background, bold, italic and underline. A token type's table wins over its group's.
+     *
+     * @throws IllegalArgumentException if the description is invalid, the message says on which line
+     */
+    public static Theme parse(String toml) {
+        Builder builder = builder();
+        EnumMap<TokenType, Style> typeStyles = new EnumMap<>(TokenType.class);
+        String table = null;
+        Style style = null;
+        String[] lines = toml.split("\r?\n", -1);
+        for (int i = 0; i <= lines.length; i++) {
+            String line = i < lines.length ? stripComment(lines[i]).strip() : null;
+            if (line != null && line.isEmpty())
+                continue;
+
+            if (line == null || line.startsWith("[")) {
+                if (table != null)
+                    setTableStyle(builder, typeStyles, table, style);
+                if (line == null)
+                    break;
+                if (!line.endsWith("]"))
+                    throw new IllegalArgumentException("Line " + (i + 1) + ": expected ']'");
+                table = line.substring(1, line.length() - 1).strip();
+                style = Style.PLAIN;
+                continue;
+            }
+
+            int eq = line.indexOf('=');
+            if (eq < 0)
+                throw new IllegalArgumentException("Line " + (i + 1) + ": expected 'key = value'");
+            String key = line.substring(0, eq).strip();
+            Object value = parseValue(line.substring(eq + 1).strip(), i);
+            if (table == null) {
+                switch (key) {
+                    case "name" -> builder.name(string(key, value, i));
+                    case "foreground" -> builder.foreground(color(string(key, value, i), i));
+                    case "background" -> builder.background(color(string(key, value, i), i));
+                    default -> throw new IllegalArgumentException("Line " + (i + 1) + ": unknown key " + key);
+                }
+            } else {
+                style = switch (key) {
+                    case "color" -> style.foreground(color(string(key, value, i), i));
+                    case "background" -> style.background(color(string(key, value, i), i));
+                    case "bold" -> bool(key, value, i) ? style.bold() : style;
+                    case "italic" -> bool(key, value, i) ? style.italic() : style;
+                    case "underline" -> bool(key, value, i) ? style.underline() : style;
+                    default -> throw new IllegalArgumentException("Line " + (i + 1) + ": unknown key " + key);
+                };
+            }
+        }
+        //token types go last so they win over their groups
+        typeStyles.forEach(builder::style);
+        return builder.build();
+    }
+
+    private static void setTableStyle(Builder builder, EnumMap<TokenType, Style> typeStyles, String table,
+                                      Style style) {
+        String name = table.toUpperCase(Locale.ROOT).replace('-', '_');
+        for (Group group : Group.values()) {
+            if (group.name().equals(name)) {
+                builder.style(group, style);
+                return;
+            }
+        }
+        for (TokenType type : TokenType.values()) {
+            if (type.name().equals(name)) {
+                typeStyles.put(type, style);
+                return;
+            }
+        }
+        throw new IllegalArgumentException("Unknown token group or type: [" + table + "]");
+    }
+
+    private static String stripComment(String line) {
+        boolean inString = false;
+        for (int i = 0; i < line.length(); i++) {
+            char c = line.charAt(i);
+            if (c == '"')
+                inString = !inString;
+            else if (c == '#' && !inString)
+                return line.substring(0, i);
+        }
+        return line;
+    }
+
+    private static Object parseValue(String value, int line) {
+        if (value.equals("true"))
+            return true;
+        if (value.equals("false"))
+            return false;
+        if (value.length() >= 2 && value.startsWith("\"") && value.endsWith("\"") && value.indexOf('\\') < 0)
+            return value.substring(1, value.length() - 1);
+        throw new IllegalArgumentException("Line " + (line + 1) + ": expected a string or a boolean");
+    }
+
+    private static String string(String key, Object value, int line) {
+        if (!(value instanceof String))
+            throw new IllegalArgumentException("Line " + (line + 1) + ": " + key + " must be a string");
+        return (String) value;
+    }
+
+    private static boolean bool(String key, Object value, int line) {
+        if (!(value instanceof Boolean))
+            throw new IllegalArgumentException("Line " + (line + 1) + ": " + key + " must be true or false");
+        return (Boolean) value;
+    }
+
+    private static String color(String color, int line) {
+        if (!isColor(color))
+            throw new IllegalArgumentException("Line " + (line + 1) + ": invalid color " + color);
+        return color;
+    }
+
+    static boolean isColor(String color) {
+        return NAMED_COLORS.contains(color) || color.matches("#[0-9a-fA-F]{6}");
+    }
+
+    public static Builder builder() {
+        return new Builder();
+    }
+
+    /**
+     * Token types that themes usually color the same way.
+     */
+    public enum Group {
+        KEYWORD,
+        IDENTIFIER,
+        COMMENT,
+        DOC_COMMENT,
+        LIFETIME,
+        CHAR,
+        STRING,
+        NUMBER,
+        OPERATOR,
+        PUNCTUATION,
+        CUSTOM,
+        ERROR;
+
+        /**
+         * @return the group of the token type, or null for whitespace
+         */
+        public static Group of(TokenType type) {
+            if (type.isKeyword() && type != TokenType.STATIC_LIFETIME)
+                return KEYWORD;
+            return switch (type) {
+                case IDENTIFIER, RAW_IDENTIFIER -> IDENTIFIER;
+                case COMMENT, SHEBANG -> COMMENT;
+                case COMMENT_INNER_DOC, COMMENT_OUTER_DOC -> DOC_COMMENT;
+                case LABEL, LIFETIME, RAW_LABEL, RAW_LIFETIME, STATIC_LIFETIME -> LIFETIME;
+                case CHAR_LITERAL, BYTE_LITERAL -> CHAR;
+                case STRING_LITERAL, RAW_STRING_LITERAL, BYTE_STRING_LITERAL, RAW_BYTE_STRING_LITERAL -> STRING;
+                case INT_LITERAL_DEC, INT_LITERAL_HEX, INT_LITERAL_OCTAL, INT_LITERAL_BIN, FLOAT_LITERAL -> NUMBER;
+                case CURLY_L, CURLY_R, SQUARE_L, SQUARE_R, PAREN_L, PAREN_R, COMMA, SEMICOLON, COLON, POUND ->
+                        PUNCTUATION;
+                case CUSTOM -> CUSTOM;
+                case ERROR -> ERROR;
+                case WHITESPACE -> null;
+                default -> OPERATOR;
+            };
+        }
+    }
+
+    /**
+     * Immutable, start from {@link #PLAIN}: {@code Style.PLAIN.foreground("red").bold()}.
+     */
+    public static final class Style {
+        public static final Style PLAIN = new Style(null, null, false, false, false);
+
+        /** Null for the default color */
+        public final String foreground;
+        /** Null for the default color */
+        public final String background;
+        public final boolean bold;
+        public final boolean italic;
+        public final boolean underline;
+
+        private Style(String foreground, String background, boolean bold, boolean italic, boolean underline) {
+            this.foreground = foreground;
+            this.background = background;
+            this.bold = bold;
+            this.italic = italic;
+            this.underline = underline;
+        }
+
+        public Style foreground(String color) {
+            checkColor(color);
+            return new Style(color, background, bold, italic, underline);
+        }
+
+        public Style background(String color) {
+            checkColor(color);
+            return new Style(foreground, color, bold, italic, underline);
+        }
+
+        public Style bold() {
+            return new Style(foreground, background, true, italic, underline);
+        }
+
+        public Style italic() {
+            return new Style(foreground, background, bold, true, underline);
+        }
+
+        public Style underline() {
+            return new Style(foreground, background, bold, italic, true);
+        }
+
+        public boolean isPlain() {
+            return foreground == null && background == null && !bold && !italic && !underline;
+        }
+    }
+
+    private static void checkColor(String color) {
+        if (color != null && !isColor(color))
+            throw new IllegalArgumentException("Invalid color: " + color);
+    }
+
+    public static final class Builder {
+        private String name = "custom";
+        private String foreground;
+        private String background;
+        private final EnumMap<TokenType, Style> styles = new EnumMap<>(TokenType.class);
+
+        private Builder() {
+        }
+
+        public Builder name(String name) {
+            this.name = name;
+            return this;
+        }
+
+        public Builder foreground(String color) {
+            checkColor(color);
+            this.foreground = color;
+            return this;
+        }
+
+        public Builder background(String color) {
+            checkColor(color);
+            this.background = color;
+            return this;
+        }
+
+        public Builder style(TokenType type, Style style) {
+            styles.put(type, style);
+            return this;
+        }
+
+        /**
+         * Sets the style of every token type in the group.
+         */
+        public Builder style(Group group, Style style) {
+            for (TokenType type : TokenType.values()) {
+                if (Group.of(type) == group)
+                    styles.put(type, style);
+            }
+            return this;
+        }
+
+        public Theme build() {
+            return new Theme(this);
+        }
+    }
+}
@@ -1,19 +1,274 @@
 package ua.yuriih.rustlexer;
 
+import java.util.Objects;
+
 public class Token {
+    /**
+     * Line, column and offset of tokens that weren't lexed from a source, see {@link #isSynthetic()}
+     */
+    public static final int SYNTHETIC = -1;
+
+    static final int FLAG_CONTEXTUAL_KEYWORD = 1;
+    static final int FLAG_DOT_NOT_CONSUMED = 2;
+    static final int FLAG_TRAILING_DOT = 4;
+    static final int FLAG_SYNTHESIZED = 8;
+    static final int FLAG_SPLICED = 16;
+    static final int PROVENANCE_FLAGS = FLAG_SYNTHESIZED | FLAG_SPLICED;
+
     public final int line;
     public final int column;
+    /**
+     * Position in the source, counted in chars from the start
+     */
+    public final int offset;
+    /**
+     * Length in chars of the token's source text
+     */
+    public final int length;
     public final TokenType type;
     public final String value;
+    private final int flags;
 
-    public Token(int line, int column, TokenType type, String value) {
+    public Token(int line, int column, int offset, int length, TokenType type, String value) {
+        this(line, column, offset, length, type, value, false);
+    }
+
+    Token(int line, int column, int offset, int length, TokenType type, String value, boolean contextualKeyword) {
+        this(line, column, offset, length, type, value, contextualKeyword ? FLAG_CONTEXTUAL_KEYWORD : 0);
+    }
+
+    Token(int line, int column, int offset, int length, TokenType type, String value, int flags) {
         this.line = line;
         this.column = column;
+        this.offset = offset;
+        this.length = length;
         this.type = type;
         this.value = value;
+        this.flags = flags;
     }
 
+    public Token(int line, int column, int offset, int length, TokenType type) {
+        this(line, column, offset, length, type, null);
+    }
+
+    /**
+     * The offset isn't known, so it's {@link #SYNTHETIC}, and the length is that of the value or the type's text.
+     *
+     * @deprecated use {@link #Token(int, int, int, int, TokenType, String)}
+     */
+    @Deprecated
+    public Token(int line, int column, TokenType type, String value) {
+        this(line, column, SYNTHETIC, textLength(type, value), type, value);
+    }
+
+    /**
+     * @deprecated use {@link #Token(int, int, int, int, TokenType)}
+     */
+    @Deprecated
     public Token(int line, int column, TokenType type) {
         this(line, column, type, null);
     }
+
+    private static int textLength(TokenType type, String value) {
+        String text = value != null ? value : type.text;
+        return type == TokenType.ERROR || text == null ? 0 : text.length();
+    }
+
+    /**
+     * Identifier made up by the program rather than lexed, e.g. for code generation.
+     */
+    public static Token ident(String name) {
+        boolean valid = !name.isEmpty() && !name.equals("_") && !Character.isDigit(name.charAt(0));
+        for (int i = 0; i < name.length() && valid; i++) {
+            char c = name.charAt(i);
+            valid = (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || (c >= '0' && c <= '9') || c == '_';
+        }
+        if (!valid)
+            throw new IllegalArgumentException("Not an identifier: " + name);
+        TokenType keyword = Lexer.KEYWORDS.get(name);
+        if (keyword != null && LexerOptions.DEFAULT.edition.reserves(keyword))
+            throw new IllegalArgumentException(name + " is a keyword");
+        return new Token(SYNTHETIC, SYNTHETIC, SYNTHETIC, name.length(), TokenType.IDENTIFIER, name,
+                (Lexer.CONTEXTUAL_KEYWORDS.contains(name) ? FLAG_CONTEXTUAL_KEYWORD : 0) | FLAG_SYNTHESIZED);
+    }
+
+    public static Token punct(char c) {
+        return punct(String.valueOf(c));
+    }
+
+    /**
+     * Synthetic punctuation token, e.g. "+" or "::".
+     *
+     * @throws IllegalArgumentException if the text isn't the spelling of an operator, a delimiter or other
+     *                                  punctuation, like "fn" or "'static"
+     */
+    public static Token punct(String text) {
+        for (TokenType type : TokenType.values()) {
+            if (type.isPunctuation() && text.equals(type.text))
+                return synthetic(type, null);
+        }
+        throw new IllegalArgumentException("Not a punctuation token: " + text);
+    }
+
+    /**
+     * Token that wasn't lexed from a source, its line, column and offset are {@link #SYNTHETIC}.
+     *
+     * @param value token text, can be null if the type has fixed text
+     */
+    public static Token synthetic(TokenType type, String value) {
+        String text = value != null ? value : type.text;
+        if (text == null)
+            throw new IllegalArgumentException(type + " needs a value");
+        return new Token(SYNTHETIC, SYNTHETIC, SYNTHETIC, type == TokenType.ERROR ? 0 : text.length(), type, value,
+                FLAG_SYNTHESIZED);
+    }
+
+    /**
+     * True for identifiers that are keywords in some contexts, like "union" or "macro_rules".
+     * The lexer can't tell which context it's in, so they're left to the parser.
+     */
+    public boolean isContextualKeyword() {
+        return (flags & FLAG_CONTEXTUAL_KEYWORD) != 0;
+    }
+
+    int flags() {
+        return flags;
+    }
+
+    /**
+     * Which rule decided about a dot after a decimal number. "2." is a float, unless the dot is followed by another
+     * dot, an underscore or an identifier: then the number is an integer and the dot is the next token, as in
+     * 2..5 or 2.max(3).
+     */
+    public NumberDot numberDot() {
+        if ((flags & FLAG_TRAILING_DOT) != 0)
+            return NumberDot.TRAILING;
+        if ((flags & FLAG_DOT_NOT_CONSUMED) != 0)
+            return NumberDot.NOT_CONSUMED;
+        return NumberDot.NONE;
+    }
+
+    /**
+     * True for raw identifiers, lifetimes and labels, like r#async or 'r#async.
+     */
+    public boolean isRaw() {
+        return type == TokenType.RAW_IDENTIFIER || type == TokenType.RAW_LIFETIME || type == TokenType.RAW_LABEL;
+    }
+
+    /**
+     * {@link #text()} without the "r#" of {@link #isRaw() raw} tokens, e.g. "async" for r#async and "'async"
+     * for 'r#async. Null if the text is.
+     */
+    public String name() {
+        String text = text();
+        if (text == null || !isRaw())
+            return text;
+        return type == TokenType.RAW_IDENTIFIER ? text.substring(2) : "'" + text.substring(3);
+    }
+
+    /**
+     * True for tokens without a position, see {@link #SYNTHETIC}. A synthetic token that was given a position,
+     * e.g. by {@link Splice splicing}, still has {@link Provenance#SYNTHETIC} provenance.
+     */
+    public boolean isSynthetic() {
+        return offset == SYNTHETIC;
+    }
+
+    /**
+     * Where the token came from, so diagnostics and span remapping can tell whether its position is in a real file.
+     */
+    public Provenance provenance() {
+        if ((flags & FLAG_SYNTHESIZED) != 0 || isSynthetic())
+            return Provenance.SYNTHETIC;
+        if ((flags & FLAG_SPLICED) != 0)
+            return Provenance.SPLICED;
+        return Provenance.SOURCE;
+    }
+
+    /**
+     * Copy of this token with another provenance, for tools that rewrite token streams. Tokens without a position
+     * stay {@link Provenance#SYNTHETIC}.
+     */
+    public Token withProvenance(Provenance provenance) {
+        int flag = switch (provenance) {
+            case SOURCE -> 0;
+            case SYNTHETIC -> FLAG_SYNTHESIZED;
+            case SPLICED -> FLAG_SPLICED;
+        };
+        return copy(line, column, offset, (flags & ~PROVENANCE_FLAGS) | flag);
+    }
+
+    public int end() {
+        return offset + length;
+    }
+
+    /**
+     * Copy of this token at another position, used when mapping tokens lexed out of context back into a file.
+     */
+    public Token withPosition(int line, int column, int offset) {
+        return copy(line, column, offset, flags);
+    }
+
+    /**
+     * Copy with another position and flags, subclasses return their own type.
+     */
+    Token copy(int line, int column, int offset, int flags) {
+        return new Token(line, column, offset, length, type, value, flags);
+    }
+
+    /**
+     * Source text of the token, or null for errors (their value is the error message).
+     * Also null if the lexer didn't {@link LexerOptions.Builder#storeText store text}, use {@link TokenStream#text} then.
+     */
+    public String text() {
+        if (type == TokenType.ERROR)
+            return null;
+        return value != null ? value : type.text;
+    }
+
+    @Override
+    public boolean equals(Object o) {
+        if (this == o)
+            return true;
+        if (o == null || getClass() != o.getClass())
+            return false;
+        Token token = (Token) o;
+        return line == token.line && column == token.column && offset == token.offset && length == token.length
+                && type == token.type && Objects.equals(value, token.value)
+                && flags == token.flags;
+    }
+
+    @Override
+    public int hashCode() {
+        return Objects.hash(line, column, offset, length, type, value, flags);
+    }
+
+    @Override
+    public String toString() {
+        return line + ":" + column + "\t" + type + (value != null ? "\t" + value : "");
+    }
+
+    public enum Provenance {
+        /** Lexed from a source, its position is where it is in that source */
+        SOURCE,
+        /**
+         * Made up by tooling, e.g. with {@link #synthetic}. If it has a position, it's in a stream it was spliced
+         * into, no source has the token there
+         */
+        SYNTHETIC,
+        /**
+         * Lexed from a source, then moved by splicing or rewriting. Its position is in the new stream,
+         * {@link Splice#origin} tells where it came from
+         */
+        SPLICED
+    }
+
+    public enum NumberDot {
+        /** Not a number, or there's no dot after it, or the dot is followed by digits as in 2.5 */
+        NONE,
+        /** Float that ends with its dot, like "2." in 2. + x */
+        TRAILING,
+        /** Integer followed by a dot that's left for the next token, like "2" in 2.max(3), 2..5 or 2.e5 */
+        NOT_CONSUMED
+    }
 }
@@ -0,0 +1,204 @@
+package ua.yuriih.rustlexer;
+
+import java.util.Arrays;
+import java.util.Iterator;
+import java.util.List;
+import java.util.NoSuchElementException;
+
+/**
+ * Compact token storage for large corpora: parallel arrays of kinds, flags, start offsets, lengths, lines and
+ * columns, 16 bytes per token with no object header. Token text isn't stored, it's resolved against the source
+ * when needed. Error messages and custom token kinds are not kept, and columns past 65534 are clamped.
+ */
+public final class TokenBuffer implements Iterable<TokenBuffer.TokenRef> {
+    public static final int BYTES_PER_TOKEN = Byte.BYTES * 2 + Integer.BYTES * 3 + Character.BYTES;
+
+    //columns are chars, so synthetic ones get the largest one
+    private static final char SYNTHETIC_COLUMN = Character.MAX_VALUE;
+    private static final int MAX_COLUMN = SYNTHETIC_COLUMN - 1;
+
+    static {
+        //kinds and flags are stored in a byte each
+        for (TokenType type : TokenType.values()) {
+            if (type.code > 0xFF)
+                throw new AssertionError("Token type code doesn't fit in a byte: " + type);
+        }
+        if (BYTES_PER_TOKEN != 16)
+            throw new AssertionError("TokenBuffer takes " + BYTES_PER_TOKEN + " bytes per token");
+    }
+
+    //TokenType.code, which unlike the ordinal doesn't change when types are added
+    private byte[] kinds;
+    private byte[] flags;
+    private int[] starts;
+    private int[] lengths;
+    private int[] lines;
+    private char[] columns;
+    private int size = 0;
+
+    public TokenBuffer() {
+        this(16);
+    }
+
+    public TokenBuffer(int capacity) {
+        kinds = new byte[capacity];
+        flags = new byte[capacity];
+        starts = new int[capacity];
+        lengths = new int[capacity];
+        lines = new int[capacity];
+        columns = new char[capacity];
+    }
+
+    public static TokenBuffer of(List<Token> tokens) {
+        TokenBuffer buffer = new TokenBuffer(Math.max(tokens.size(), 1));
+        for (Token token : tokens)
+            buffer.add(token);
+        return buffer;
+    }
+
+    public void add(Token token) {
+        add(token.type, token.offset, token.length, token.line, token.column, token.flags());
+    }
+
+    /**
+     * Adds a token without a position, its line and column are {@link Token#SYNTHETIC}.
+     */
+    public void add(TokenType type, int start, int length) {
+        add(type, start, length, Token.SYNTHETIC, Token.SYNTHETIC, 0);
+    }
+
+    private void add(TokenType type, int start, int length, int line, int column, int tokenFlags) {
+        if (size == kinds.length) {
+            int capacity = Math.max(kinds.length * 2, 16);
+            kinds = Arrays.copyOf(kinds, capacity);
+            flags = Arrays.copyOf(flags, capacity);
+            starts = Arrays.copyOf(starts, capacity);
+            lengths = Arrays.copyOf(lengths, capacity);
+            lines = Arrays.copyOf(lines, capacity);
+            columns = Arrays.copyOf(columns, capacity);
+        }
+        kinds[size] = (byte) type.code;
+        flags[size] = (byte) tokenFlags;
+        starts[size] = start;
+        lengths[size] = length;
+        lines[size] = line;
+        columns[size] = column == Token.SYNTHETIC ? SYNTHETIC_COLUMN : (char) Math.min(column, MAX_COLUMN);
+        size++;
+    }
+
+    public int size() {
+        return size;
+    }
+
+    /**
+     * Memory taken by the token data, including unused capacity
+     */
+    public long sizeInBytes() {
+        return (long) kinds.length * BYTES_PER_TOKEN;
+    }
+
+    public TokenType kind(int index) {
+        return TokenType.fromCode(kinds[checkIndex(index)] & 0xFF);
+    }
+
+    public int start(int index) {
+        return starts[checkIndex(index)];
+    }
+
+    public int length(int index) {
+        return lengths[checkIndex(index)];
+    }
+
+    /**
+     * {@link Token#SYNTHETIC} for synthetic tokens and ones added without a position
+     */
+    public int line(int index) {
+        return lines[checkIndex(index)];
+    }
+
+    /**
+     * {@link Token#SYNTHETIC} for synthetic tokens and ones added without a position, at most 65534
+     */
+    public int column(int index) {
+        char column = columns[checkIndex(index)];
+        return column == SYNTHETIC_COLUMN ? Token.SYNTHETIC : column;
+    }
+
+    public boolean isContextualKeyword(int index) {
+        return (flags[checkIndex(index)] & Token.FLAG_CONTEXTUAL_KEYWORD) != 0;
+    }
+
+    public TokenRef get(int index) {
+        return new TokenRef(this, checkIndex(index));
+    }
+
+    private int checkIndex(int index) {
+        if (index < 0 || index >= size)
+            throw new IndexOutOfBoundsException("Index " + index + " out of bounds for size " + size);
+        return index;
+    }
+
+    @Override
+    public Iterator<TokenRef> iterator() {
+        return new Iterator<>() {
+            private int next = 0;
+
+            @Override
+            public boolean hasNext() {
+                return next < size;
+            }
+
+            @Override
+            public TokenRef next() {
+                if (next >= size)
+                    throw new NoSuchElementException();
+                return new TokenRef(TokenBuffer.this, next++);
+            }
+        };
+    }
+
+    /**
+     * View of a single token in a {@link TokenBuffer}.
+     */
+    public static final class TokenRef {
+        private final TokenBuffer buffer;
+        public final int index;
+
+        private TokenRef(TokenBuffer buffer, int index) {
+            this.buffer = buffer;
+            this.index = index;
+        }
+
+        public TokenType kind() {
+            return buffer.kind(index);
+        }
+
+        public int start() {
+            return buffer.start(index);
+        }
+
+        public int length() {
+            return buffer.length(index);
+        }
+
+        public int end() {
+            return start() + length();
+        }
+
+        public int line() {
+            return buffer.line(index);
+        }
+
+        public int column() {
+            return buffer.column(index);
+        }
+
+        public boolean isContextualKeyword() {
+            return buffer.isContextualKeyword(index);
+        }
+
+        public CharSequence text(CharSequence source) {
+            return source.subSequence(start(), end());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+package ua.yuriih.rustlexer;
+
+import java.io.BufferedInputStream;
+import java.io.ByteArrayInputStream;
+import java.io.ByteArrayOutputStream;
+import java.io.Closeable;
+import java.io.IOException;
+import java.io.InputStream;
+import java.nio.charset.StandardCharsets;
+import java.nio.file.Files;
+import java.nio.file.NoSuchFileException;
+import java.nio.file.Path;
+import java.nio.file.StandardCopyOption;
+import java.nio.file.attribute.FileTime;
+import java.security.MessageDigest;
+import java.security.NoSuchAlgorithmException;
+import java.util.ArrayList;
+import java.util.Comparator;
+import java.util.HashMap;
+import java.util.List;
+import java.util.logging.Level;
+import java.util.logging.Logger;
+import java.util.stream.Stream;
+
+/**
+ * On-disk cache of {@link TokenStream#dump token dumps}, keyed by the SHA-256 of the file contents and
+ * the lexer options, so a cache directory can be shared by caches with different options.
+ * <p>
+ * The cache also remembers the modification time and size of every file it has seen,
+ * so unchanged files are loaded without reading or hashing them. The index is saved by {@link #close()}.
+ */
+public final class TokenCache implements Closeable {
+    private static final Logger LOG = Logger.getLogger(TokenCache.class.getName());
+    private static final String INDEX_FILE = "index";
+    private static final String DUMP_EXTENSION = ".rlex";
+
+    private final Path dir;
+    private final LexerOptions options;
+    //part of every dump's name, so dumps lexed with other options aren't loaded
+    private final String optionsHash;
+    private final HashMap<Path, IndexEntry> index = new HashMap<>();
+    private boolean indexChanged = false;
+
+    private TokenCache(Path dir, LexerOptions options) {
+        this.dir = dir;
+        this.options = options;
+        optionsHash = sha256(options.fingerprint().getBytes(StandardCharsets.UTF_8)).substring(0, 16);
+    }
+
+    public static TokenCache open(Path dir) throws IOException {
+        return open(dir, LexerOptions.DEFAULT);
+    }
+
+    /**
+     * Creates the directory if needed and loads its index.
+     */
+    public static TokenCache open(Path dir, LexerOptions options) throws IOException {
+        Files.createDirectories(dir);
+        TokenCache cache = new TokenCache(dir, options);
+        Path indexFile = dir.resolve(INDEX_FILE);
+        if (Files.isRegularFile(indexFile)) {
+            for (String line : Files.readAllLines(indexFile, StandardCharsets.UTF_8)) {
+                String[] parts = line.split("\t", 4);
+                if (parts.length != 4)
+                    continue;
+                try {
+                    cache.index.put(Path.of(parts[3]),
+                            new IndexEntry(parts[0], Long.parseLong(parts[1]), Long.parseLong(parts[2])));
+                } catch (NumberFormatException e) {
+                    //skip broken lines, the file will just be hashed again
+                }
+            }
+        }
+        return cache;
+    }
+
+    /**
+     * @return the file's tokens, from the cache if its contents were lexed before
+     */
+    public TokenStream getOrLex(Path file) throws IOException {
+        Path key = file.toAbsolutePath().normalize();
+        long modified = Files.getLastModifiedTime(file).toMillis();
+        long size = Files.size(file);
+
+        IndexEntry entry = index.get(key);
+        if (entry != null && entry.modified == modified && entry.size == size) {
+            TokenStream cached = load(entry.hash);
+            if (cached != null) {
+                log("{0}: cache hit", file);
+                return cached;
+            }
+        }
+
+        byte[] content = Files.readAllBytes(file);
+        String hash = sha256(content);
+        index.put(key, new IndexEntry(hash, modified, size));
+        indexChanged = true;
+
+        TokenStream cached = load(hash);
+        if (cached != null) {
+            log("{0}: cache hit after hashing", file);
+            return cached;
+        }
+        log("{0}: cache miss", file);
+
+        TokenStream tokens = new TokenStream(Lexer.withOptions(new ByteArrayInputStream(content), options).parse());
+        ByteArrayOutputStream dump = new ByteArrayOutputStream();
+        tokens.dump(dump);
+        Path temp = Files.createTempFile(dir, hash, ".tmp");
+        Files.write(temp, dump.toByteArray());
+        Files.move(temp, dumpPath(hash), StandardCopyOption.REPLACE_EXISTING, StandardCopyOption.ATOMIC_MOVE);
+        return tokens;
+    }
+
+    private TokenStream load(String hash) throws IOException {
+        Path path = dumpPath(hash);
+        try (InputStream in = new BufferedInputStream(Files.newInputStream(path))) {
+            TokenStream tokens = TokenStream.fromDump(in);
+            //eviction removes the least recently used dumps first
+            Files.setLastModifiedTime(path, FileTime.fromMillis(System.currentTimeMillis()));
+            return tokens;
+        } catch (NoSuchFileException e) {
+            return null;
+        } catch (IOException e) {
+            //corrupt or from an older version, lex again
+            Files.deleteIfExists(path);
+            return null;
+        }
+    }
+
+    /**
+     * Deletes the least recently used dumps until the total size of the cache is at most maxBytes.
+     *
+     * @return how many dumps were deleted
+     */
+    public int evict(long maxBytes) throws IOException {
+        ArrayList<Path> dumps = new ArrayList<>();
+        try (Stream<Path> files = Files.list(dir)) {
+            files.filter(path -> path.getFileName().toString().endsWith(DUMP_EXTENSION)).forEach(dumps::add);
+        }
+        HashMap<Path, Long> lastUsed = new HashMap<>();
+        long total = 0;
+        for (Path dump : dumps) {
+            lastUsed.put(dump, Files.getLastModifiedTime(dump).toMillis());
+            total += Files.size(dump);
+        }
+        dumps.sort(Comparator.comparingLong(lastUsed::get));
+
+        int deleted = 0;
+        for (Path dump : dumps) {
+            if (total <= maxBytes)
+                break;
+            total -= Files.size(dump);
+            Files.delete(dump);
+            deleted++;
+        }
+        return deleted;
+    }
+
+    /**
+     * Saves the index.
+     */
+    @Override
+    public void close() throws IOException {
+        if (!indexChanged)
+            return;
+        List<String> lines = new ArrayList<>();
+        index.forEach((path, entry) -> lines.add(entry.hash + "\t" + entry.modified + "\t" + entry.size + "\t" + path));
+        Path temp = Files.createTempFile(dir, INDEX_FILE, ".tmp");
+        Files.write(temp, lines, StandardCharsets.UTF_8);
+        Files.move(temp, dir.resolve(INDEX_FILE), StandardCopyOption.REPLACE_EXISTING, StandardCopyOption.ATOMIC_MOVE);
+        indexChanged = false;
+    }
+
+    private void log(String message, Path file) {
+        if (options.log)
+            LOG.log(Level.FINE, message, file);
+    }
+
+    private Path dumpPath(String hash) {
+        return dir.resolve(hash + "-" + optionsHash + DUMP_EXTENSION);
+    }
+
+    private static String sha256(byte[] content) {
+        MessageDigest digest;
+        try {
+            digest = MessageDigest.getInstance("SHA-256");
+        } catch (NoSuchAlgorithmException e) {
+            throw new AssertionError("SHA-256 is always available", e);
+        }
+        StringBuilder hex = new StringBuilder();
+        for (byte b : digest.digest(content))
+            hex.append(String.format("%02x", b));
+        return hex.toString();
+    }
+
+    private static final class IndexEntry {
+        final String hash;
+        final long modified;
+        final long size;
+
+        IndexEntry(String hash, long modified, long size) {
+            this.hash = hash;
+            this.modified = modified;
+            this.size = size;
+        }
+    }
+}
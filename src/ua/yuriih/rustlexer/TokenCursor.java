@@ -0,0 +1,165 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Position in a {@link TokenStream} that can move back and forth, for tools that walk around a file,
+ * e.g. to find the comment above a token. Create it with {@link TokenStream#cursor()}.
+ * <p>
+ * The cursor is at a token index, or just outside the stream (-1 or {@link TokenStream#size()}),
+ * where {@link #current()} is null. Leading and trailing trivia are split the same way as in {@link Trivia}.
+ */
+public final class TokenCursor {
+    private final TokenStream stream;
+    private int index = 0;
+
+    TokenCursor(TokenStream stream) {
+        this.stream = stream;
+    }
+
+    public int index() {
+        return index;
+    }
+
+    /**
+     * @return the token under the cursor, or null if it's outside the stream
+     */
+    public Token current() {
+        return index >= 0 && index < stream.size() ? stream.get(index) : null;
+    }
+
+    /**
+     * Moves to the next token.
+     *
+     * @return the new current token, null at the end
+     */
+    public Token next() {
+        index = Math.min(index + 1, stream.size());
+        return current();
+    }
+
+    /**
+     * Moves to the previous token.
+     *
+     * @return the new current token, null at the start
+     */
+    public Token prev() {
+        index = Math.max(index - 1, -1);
+        return current();
+    }
+
+    /**
+     * Moves forward past trivia, staying put if the current token isn't trivia.
+     *
+     * @return the first non-trivia token at or after the cursor, null if there's none
+     */
+    public Token skipTrivia() {
+        if (index < 0)
+            index = 0;
+        while (index < stream.size() && stream.get(index).type.isTrivia())
+            index++;
+        return current();
+    }
+
+    /**
+     * Like {@link #skipTrivia()}, but moves backward.
+     */
+    public Token skipTriviaBack() {
+        if (index >= stream.size())
+            index = stream.size() - 1;
+        while (index >= 0 && stream.get(index).type.isTrivia())
+            index--;
+        return current();
+    }
+
+    public Token seek(int index) {
+        if (index < -1 || index > stream.size())
+            throw new IndexOutOfBoundsException("Index " + index + " out of bounds for size " + stream.size());
+        this.index = index;
+        return current();
+    }
+
+    /**
+     * Moves to the token containing the char at the offset, or the first one after it if it's between tokens.
+     * Synthetic tokens are skipped.
+     *
+     * @return the new current token, null if there are no tokens after the offset
+     */
+    public Token seekOffset(int offset) {
+        for (index = 0; index < stream.size(); index++) {
+            Token token = stream.get(index);
+            if (!token.isSynthetic() && offset < token.end())
+                break;
+        }
+        return current();
+    }
+
+    /**
+     * Moves to the first token starting on the line or after it.
+     *
+     * @param line 0-based
+     */
+    public Token seekLine(int line) {
+        for (index = 0; index < stream.size(); index++) {
+            Token token = stream.get(index);
+            if (!token.isSynthetic() && token.line >= line)
+                break;
+        }
+        return current();
+    }
+
+    /**
+     * Trivia right before the current token: comments and whitespace on the lines above it and before it on its line,
+     * without trivia that trails the token before it.
+     */
+    public List<Token> leadingTrivia() {
+        if (current() == null)
+            return Collections.emptyList();
+        int start = index;
+        while (start > 0 && stream.get(start - 1).type.isTrivia())
+            start--;
+        if (start > 0) {
+            //skip what trails the previous token
+            int previousEndLine = stream.endLine(stream.get(start - 1));
+            while (start < index && stream.get(start).line == previousEndLine) {
+                boolean lineBreak = isLineBreak(stream.get(start));
+                previousEndLine = stream.endLine(stream.get(start));
+                start++;
+                if (lineBreak)
+                    break;
+            }
+        }
+        return new ArrayList<>(stream.tokens().subList(start, index));
+    }
+
+    /**
+     * Trivia after the current token on the line where it ends, e.g. {@code // note} in {@code x = 1; // note},
+     * up to and including the whitespace with the line break.
+     */
+    public List<Token> trailingTrivia() {
+        Token token = current();
+        if (token == null || token.type.isTrivia())
+            return Collections.emptyList();
+        ArrayList<Token> trailing = new ArrayList<>();
+        int endLine = stream.endLine(token);
+        for (int i = index + 1; i < stream.size(); i++) {
+            Token trivia = stream.get(i);
+            if (!trivia.type.isTrivia() || trivia.line != endLine)
+                break;
+            trailing.add(trivia);
+            if (isLineBreak(trivia))
+                break;
+            endLine = stream.endLine(trivia);
+        }
+        return trailing;
+    }
+
+    private boolean isLineBreak(Token token) {
+        if (token.type != TokenType.WHITESPACE)
+            return false;
+        CharSequence text = stream.text(token);
+        return text != null && text.toString().indexOf('\n') >= 0;
+    }
+}
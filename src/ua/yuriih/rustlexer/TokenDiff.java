@@ -0,0 +1,224 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.Collections;
+import java.util.HashMap;
+import java.util.List;
+
+/**
+ * Differences between two versions of a file, token by token: a change that only moves tokens to other lines
+ * or reindents them shows up as a trivia change, not as changed tokens.
+ */
+public final class TokenDiff {
+    public enum Status {
+        SAME,
+        /** Deleted from the old version or inserted into the new one */
+        CHANGED,
+        /** Whitespace or a comment that differs between tokens that are the same */
+        TRIVIA_CHANGED
+    }
+
+    public final List<Token> before;
+    public final List<Token> after;
+    private final Status[] beforeStatus;
+    private final Status[] afterStatus;
+    private final List<Edit> edits;
+
+    private TokenDiff(List<Token> before, List<Token> after, Status[] beforeStatus, Status[] afterStatus,
+                      List<Edit> edits) {
+        this.before = Collections.unmodifiableList(before);
+        this.after = Collections.unmodifiableList(after);
+        this.beforeStatus = beforeStatus;
+        this.afterStatus = afterStatus;
+        this.edits = Collections.unmodifiableList(edits);
+    }
+
+    /**
+     * Tokens other than trivia are compared by type and text, with a shortest edit script, so they need their text
+     * (the lexer {@link LexerOptions.Builder#storeText stores it} by default). Trivia between two pairs of tokens
+     * that are the same is compared by text.
+     */
+    public static TokenDiff diff(List<Token> before, List<Token> after) {
+        HashMap<String, Integer> ids = new HashMap<>();
+        ArrayList<Integer> beforeIndexes = new ArrayList<>();
+        ArrayList<Integer> afterIndexes = new ArrayList<>();
+        int[] a = significant(before, beforeIndexes, ids);
+        int[] b = significant(after, afterIndexes, ids);
+
+        Status[] beforeStatus = new Status[before.size()];
+        Status[] afterStatus = new Status[after.size()];
+        Arrays.fill(beforeStatus, Status.SAME);
+        Arrays.fill(afterStatus, Status.SAME);
+        ArrayList<Edit> edits = new ArrayList<>();
+        //matched pairs of significant indexes, with a sentinel pair on each end
+        ArrayList<int[]> matches = new ArrayList<>();
+        matches.add(new int[] {-1, -1});
+        for (int[] match : match(a, b))
+            matches.add(match);
+        matches.add(new int[] {a.length, b.length});
+
+        for (int m = 1; m < matches.size(); m++) {
+            int[] from = matches.get(m - 1);
+            int[] to = matches.get(m);
+            //token indexes of the gap between the two pairs
+            int beforeStart = from[0] < 0 ? 0 : beforeIndexes.get(from[0]) + 1;
+            int beforeEnd = to[0] < a.length ? beforeIndexes.get(to[0]) : before.size();
+            int afterStart = from[1] < 0 ? 0 : afterIndexes.get(from[1]) + 1;
+            int afterEnd = to[1] < b.length ? afterIndexes.get(to[1]) : after.size();
+            if (to[0] - from[0] > 1 || to[1] - from[1] > 1) {
+                edits.add(new Edit(beforeStart, beforeEnd, afterStart, afterEnd));
+                for (int i = from[0] + 1; i < to[0]; i++)
+                    beforeStatus[beforeIndexes.get(i)] = Status.CHANGED;
+                for (int i = from[1] + 1; i < to[1]; i++)
+                    afterStatus[afterIndexes.get(i)] = Status.CHANGED;
+            }
+            markTrivia(before, beforeStart, beforeEnd, beforeStatus, after, afterStart, afterEnd);
+            markTrivia(after, afterStart, afterEnd, afterStatus, before, beforeStart, beforeEnd);
+        }
+        return new TokenDiff(before, after, beforeStatus, afterStatus, edits);
+    }
+
+    /**
+     * Marks trivia of the gap that the other version's gap doesn't have.
+     */
+    private static void markTrivia(List<Token> tokens, int start, int end, Status[] status,
+                                   List<Token> other, int otherStart, int otherEnd) {
+        HashMap<String, Integer> otherTrivia = new HashMap<>();
+        for (int i = otherStart; i < otherEnd; i++) {
+            if (other.get(i).type.isTrivia())
+                otherTrivia.merge(other.get(i).text(), 1, Integer::sum);
+        }
+        for (int i = start; i < end; i++) {
+            Token token = tokens.get(i);
+            if (!token.type.isTrivia())
+                continue;
+            Integer count = otherTrivia.get(token.text());
+            if (count == null)
+                status[i] = Status.TRIVIA_CHANGED;
+            else if (count == 1)
+                otherTrivia.remove(token.text());
+            else
+                otherTrivia.put(token.text(), count - 1);
+        }
+    }
+
+    private static int[] significant(List<Token> tokens, List<Integer> indexes, HashMap<String, Integer> ids) {
+        ArrayList<Integer> keys = new ArrayList<>();
+        for (int i = 0; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (token.type.isTrivia() || token.isSynthetic())
+                continue;
+            String text = token.type == TokenType.ERROR ? token.value : token.text();
+            indexes.add(i);
+            keys.add(ids.computeIfAbsent(token.type.name() + ' ' + text, key -> ids.size()));
+        }
+        return keys.stream().mapToInt(Integer::intValue).toArray();
+    }
+
+    /**
+     * Myers' algorithm, keeping the part of V each step can reach to walk back from the end.
+     *
+     * @return indexes of equal elements, in order
+     */
+    private static List<int[]> match(int[] a, int[] b) {
+        int n = a.length;
+        int m = b.length;
+        int offset = n + m + 1;
+        int[] v = new int[2 * offset + 1];
+        ArrayList<int[]> trace = new ArrayList<>();
+        int steps = 0;
+        search:
+        for (int d = 0; d <= n + m; d++) {
+            trace.add(Arrays.copyOfRange(v, offset - d, offset + d + 1));
+            for (int k = -d; k <= d; k += 2) {
+                int x = k == -d || (k != d && v[offset + k - 1] < v[offset + k + 1])
+                        ? v[offset + k + 1] : v[offset + k - 1] + 1;
+                int y = x - k;
+                while (x < n && y < m && a[x] == b[y]) {
+                    x++;
+                    y++;
+                }
+                v[offset + k] = x;
+                if (x >= n && y >= m) {
+                    steps = d;
+                    break search;
+                }
+            }
+        }
+
+        ArrayList<int[]> matches = new ArrayList<>();
+        int x = n;
+        int y = m;
+        for (int d = steps; d >= 0; d--) {
+            int[] previous = trace.get(d);
+            int k = x - y;
+            int previousK;
+            int previousX;
+            if (d == 0) {
+                previousX = 0;
+                previousK = 0;
+            } else {
+                //previous holds V for k from -d to d, before step d
+                boolean down = k == -d || (k != d && previous[k - 1 + d] < previous[k + 1 + d]);
+                previousK = down ? k + 1 : k - 1;
+                previousX = previous[previousK + d];
+            }
+            int previousY = previousX - previousK;
+            //the snake after the edit
+            while (x > previousX && y > previousY) {
+                x--;
+                y--;
+                matches.add(new int[] {x, y});
+            }
+            x = previousX;
+            y = previousY;
+        }
+        Collections.reverse(matches);
+        return matches;
+    }
+
+    public Status before(int index) {
+        return beforeStatus[index];
+    }
+
+    public Status after(int index) {
+        return afterStatus[index];
+    }
+
+    /**
+     * Runs of changed tokens, in order. A run can be empty on one side, for pure insertions and deletions.
+     */
+    public List<Edit> edits() {
+        return edits;
+    }
+
+    /**
+     * Whether the versions only differ in whitespace and comments, or not at all.
+     */
+    public boolean isTriviaOnly() {
+        return edits.isEmpty();
+    }
+
+    /**
+     * Token index ranges, end-exclusive, that replace each other. They include the trivia around changed tokens.
+     */
+    public static final class Edit {
+        public final int beforeStart;
+        public final int beforeEnd;
+        public final int afterStart;
+        public final int afterEnd;
+
+        private Edit(int beforeStart, int beforeEnd, int afterStart, int afterEnd) {
+            this.beforeStart = beforeStart;
+            this.beforeEnd = beforeEnd;
+            this.afterStart = afterStart;
+            this.afterEnd = afterEnd;
+        }
+
+        @Override
+        public String toString() {
+            return beforeStart + ".." + beforeEnd + " -> " + afterStart + ".." + afterEnd;
+        }
+    }
+}
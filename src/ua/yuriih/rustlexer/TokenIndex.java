@@ -0,0 +1,100 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.List;
+
+/**
+ * Positional lookups over a token list in O(log n), for when {@link TokenStream#tokenAt} is called many times.
+ * Synthetic tokens and errors aren't indexed. The list must not change after the index is built.
+ */
+public final class TokenIndex {
+    private final List<Token> tokens;
+    /** Indexes into tokens, sorted by offset */
+    private final int[] indexes;
+    private final int[] starts;
+    /** maxEnds[i] is the largest end among the first i + 1 tokens, for finding tokens that overlap a range */
+    private final int[] maxEnds;
+
+    public TokenIndex(List<Token> tokens) {
+        this.tokens = tokens;
+        int[] indexes = new int[tokens.size()];
+        int count = 0;
+        boolean sorted = true;
+        for (int i = 0; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (token.isSynthetic() || token.type == TokenType.ERROR)
+                continue;
+            if (count > 0 && token.offset < tokens.get(indexes[count - 1]).offset)
+                sorted = false;
+            indexes[count++] = i;
+        }
+        indexes = Arrays.copyOf(indexes, count);
+        if (!sorted) {
+            indexes = Arrays.stream(indexes).boxed()
+                    .sorted((a, b) -> Integer.compare(tokens.get(a).offset, tokens.get(b).offset))
+                    .mapToInt(Integer::intValue).toArray();
+        }
+
+        this.indexes = indexes;
+        this.starts = new int[count];
+        this.maxEnds = new int[count];
+        for (int i = 0; i < count; i++) {
+            Token token = tokens.get(indexes[i]);
+            starts[i] = token.offset;
+            maxEnds[i] = Math.max(token.end(), i > 0 ? maxEnds[i - 1] : 0);
+        }
+    }
+
+    /**
+     * @return indexes of the tokens overlapping chars [start, end), in source order
+     */
+    public List<Integer> tokensInRange(int start, int end) {
+        ArrayList<Integer> result = new ArrayList<>();
+        //first token that could end after start
+        int from = upperBound(maxEnds, start);
+        for (int i = from; i < starts.length && starts[i] < end; i++) {
+            Token token = tokens.get(indexes[i]);
+            if (token.end() > start || (token.length == 0 && token.offset >= start))
+                result.add(indexes[i]);
+        }
+        return result;
+    }
+
+    /**
+     * @return index of the token containing the offset, or else the closest one (preferring the earlier one
+     * on a tie); -1 if there are no tokens
+     */
+    public int nearestToken(int offset) {
+        if (starts.length == 0)
+            return -1;
+        //last token starting at or before the offset
+        int i = upperBound(starts, offset) - 1;
+        if (i >= 0 && offset < tokens.get(indexes[i]).end())
+            return indexes[i];
+
+        int best = -1;
+        int bestDistance = Integer.MAX_VALUE;
+        if (i >= 0) {
+            best = indexes[i];
+            bestDistance = offset - tokens.get(best).end() + 1;
+        }
+        if (i + 1 < starts.length && starts[i + 1] - offset < bestDistance)
+            best = indexes[i + 1];
+        return best;
+    }
+
+    // first index where array[index] > value
+    private static int upperBound(int[] array, int value) {
+        int low = 0;
+        int high = array.length;
+        while (low < high) {
+            int mid = (low + high) >>> 1;
+            if (array[mid] <= value)
+                low = mid + 1;
+            else
+                high = mid;
+        }
+        return low;
+    }
+}
@@ -0,0 +1,155 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.EnumSet;
+import java.util.Iterator;
+import java.util.List;
+import java.util.NoSuchElementException;
+import java.util.function.Predicate;
+
+/**
+ * An iterator over tokens with the usual manipulations built in, so consumers don't need a loop for each one.
+ * Filters are lazy and return a new iterator over this one, {@link #splitOn}, {@link #spans} and {@link #toList}
+ * use up the rest of the tokens.
+ */
+public final class TokenIterator implements Iterator<Token> {
+    private final Iterator<? extends Token> source;
+    private final Predicate<? super Token> filter;
+    private Token next;
+
+    private TokenIterator(Iterator<? extends Token> source, Predicate<? super Token> filter) {
+        this.source = source;
+        this.filter = filter;
+    }
+
+    public static TokenIterator of(Iterator<? extends Token> tokens) {
+        return tokens instanceof TokenIterator ? (TokenIterator) tokens : new TokenIterator(tokens, token -> true);
+    }
+
+    public static TokenIterator of(Iterable<? extends Token> tokens) {
+        return of(tokens.iterator());
+    }
+
+    @Override
+    public boolean hasNext() {
+        while (next == null && source.hasNext()) {
+            Token token = source.next();
+            if (filter.test(token))
+                next = token;
+        }
+        return next != null;
+    }
+
+    @Override
+    public Token next() {
+        if (!hasNext())
+            throw new NoSuchElementException();
+        Token token = next;
+        next = null;
+        return token;
+    }
+
+    public TokenIterator filter(Predicate<? super Token> predicate) {
+        return new TokenIterator(this, predicate);
+    }
+
+    /**
+     * Skips whitespace, comments and the shebang, see {@link TokenType#isTrivia()}. Doc comments are kept.
+     */
+    public TokenIterator significant() {
+        return filter(token -> !token.type.isTrivia());
+    }
+
+    /**
+     * Only tokens of these types.
+     */
+    public TokenIterator ofType(TokenType type, TokenType... types) {
+        EnumSet<TokenType> set = EnumSet.of(type, types);
+        return filter(token -> set.contains(token.type));
+    }
+
+    /**
+     * Splits the rest of the tokens on separators outside (), [] and {}, like the commas between the arguments
+     * of a call when iterating over the contents of its parentheses. Separators aren't included in the pieces,
+     * and a separator at the end doesn't start another piece, so a trailing comma gives no empty argument.
+     * Unmatched closing delimiters are kept without changing the depth.
+     */
+    public List<List<Token>> splitOn(TokenType separator) {
+        ArrayList<List<Token>> pieces = new ArrayList<>();
+        ArrayList<Token> piece = new ArrayList<>();
+        boolean started = false;
+        int depth = 0;
+        while (hasNext()) {
+            Token token = next();
+            started = true;
+            if (depth == 0 && token.type == separator) {
+                pieces.add(piece);
+                piece = new ArrayList<>();
+                started = false;
+                continue;
+            }
+            if (TokenTree.isOpening(token.type))
+                depth++;
+            else if (TokenTree.isClosing(token.type) && depth > 0)
+                depth--;
+            piece.add(token);
+        }
+        if (started)
+            pieces.add(piece);
+        return pieces;
+    }
+
+    /**
+     * Source ranges of the rest of the tokens, without the synthetic ones.
+     */
+    public List<Span> spans() {
+        ArrayList<Span> spans = new ArrayList<>();
+        while (hasNext()) {
+            Token token = next();
+            if (!token.isSynthetic())
+                spans.add(new Span(token.offset, token.end()));
+        }
+        return spans;
+    }
+
+    public List<Token> toList() {
+        ArrayList<Token> tokens = new ArrayList<>();
+        forEachRemaining(tokens::add);
+        return tokens;
+    }
+
+    /**
+     * Offsets [start, end) of a token in its source.
+     */
+    public static final class Span {
+        public final int start;
+        public final int end;
+
+        private Span(int start, int end) {
+            this.start = start;
+            this.end = end;
+        }
+
+        public int length() {
+            return end - start;
+        }
+
+        @Override
+        public boolean equals(Object o) {
+            if (!(o instanceof Span))
+                return false;
+            Span span = (Span) o;
+            return start == span.start && end == span.end;
+        }
+
+        @Override
+        public int hashCode() {
+            return 31 * start + end;
+        }
+
+        @Override
+        public String toString() {
+            return start + ".." + end;
+        }
+    }
+}
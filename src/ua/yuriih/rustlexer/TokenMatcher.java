@@ -0,0 +1,17 @@
+package ua.yuriih.rustlexer;
+
+public interface TokenMatcher {
+    boolean matches(Token token);
+
+    static TokenMatcher kind(TokenType type) {
+        return token -> token.type == type;
+    }
+
+    static TokenMatcher text(String text) {
+        return token -> text.equals(token.text());
+    }
+
+    static TokenMatcher any() {
+        return token -> true;
+    }
+}
@@ -0,0 +1,161 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.HashMap;
+import java.util.HashSet;
+import java.util.List;
+import java.util.Map;
+
+/**
+ * Token-level pattern, compiled from a whitespace-separated description:
+ * <pre>
+ * fn            - token with this exact text
+ * $name         - any single token, captured as "name"
+ * $name:KIND    - single token of the given {@link TokenType}, captured as "name"
+ * $_, $_:KIND   - same, but not captured
+ * $name*        - zero or more tokens (also + for one or more, ? for optional)
+ * $$            - literal $ token
+ * </pre>
+ * Repetitions are lazy, e.g. {@code "fn $name:IDENTIFIER ( $args* )"}. Each capture name can only be used once.
+ */
+public final class TokenPattern {
+    private static final int UNBOUNDED = Integer.MAX_VALUE;
+
+    private final List<Element> elements;
+
+    private TokenPattern(List<Element> elements) {
+        this.elements = elements;
+    }
+
+    public static TokenPattern compile(String pattern) {
+        ArrayList<Element> elements = new ArrayList<>();
+        HashSet<String> captureNames = new HashSet<>();
+        for (String part : pattern.trim().split("\\s+")) {
+            if (part.isEmpty())
+                continue;
+            Element element = parseElement(part);
+            if (element.captureName != null && !captureNames.add(element.captureName))
+                throw new IllegalArgumentException("Duplicate capture name in pattern: " + element.captureName);
+            elements.add(element);
+        }
+        if (elements.isEmpty())
+            throw new IllegalArgumentException("Empty token pattern");
+        return new TokenPattern(elements);
+    }
+
+    private static Element parseElement(String part) {
+        if (!part.startsWith("$") || part.equals("$"))
+            return new Element(TokenMatcher.text(part), null, 1, 1);
+        if (part.equals("$$"))
+            return new Element(TokenMatcher.text("$"), null, 1, 1);
+
+        String s = part.substring(1);
+        int min = 1;
+        int max = 1;
+        char last = s.charAt(s.length() - 1);
+        if (last == '*' || last == '+' || last == '?') {
+            s = s.substring(0, s.length() - 1);
+            min = last == '+' ? 1 : 0;
+            max = last == '?' ? 1 : UNBOUNDED;
+        }
+
+        TokenMatcher matcher = TokenMatcher.any();
+        int colon = s.indexOf(':');
+        if (colon >= 0) {
+            String kind = s.substring(colon + 1);
+            try {
+                matcher = TokenMatcher.kind(TokenType.valueOf(kind));
+            } catch (IllegalArgumentException e) {
+                throw new IllegalArgumentException("Unknown token kind in pattern: " + kind);
+            }
+            s = s.substring(0, colon);
+        }
+
+        if (s.isEmpty())
+            throw new IllegalArgumentException("Missing capture name in pattern element: " + part);
+        return new Element(matcher, s.equals("_") ? null : s, min, max);
+    }
+
+    /**
+     * Finds a match starting at every token index where one exists. Matches may overlap.
+     */
+    public List<PatternMatch> find(List<Token> tokens) {
+        ArrayList<PatternMatch> matches = new ArrayList<>();
+        for (int start = 0; start < tokens.size(); start++) {
+            PatternMatch match = matchAt(tokens, start);
+            if (match != null)
+                matches.add(match);
+        }
+        return matches;
+    }
+
+    /**
+     * @return the match starting exactly at the given index, or null
+     */
+    public PatternMatch matchAt(List<Token> tokens, int start) {
+        HashMap<String, TokenSearch.Match> captures = new HashMap<>();
+        int end = match(tokens, 0, start, captures);
+        if (end < 0)
+            return null;
+        return new PatternMatch(new TokenSearch.Match(start, end), captures);
+    }
+
+    private int match(List<Token> tokens, int elementIndex, int pos, HashMap<String, TokenSearch.Match> captures) {
+        if (elementIndex == elements.size())
+            return pos;
+
+        Element element = elements.get(elementIndex);
+        int count = 0;
+        while (true) {
+            if (count >= element.min) {
+                if (element.captureName != null)
+                    captures.put(element.captureName, new TokenSearch.Match(pos - count, pos));
+                int end = match(tokens, elementIndex + 1, pos, captures);
+                if (end >= 0)
+                    return end;
+                if (element.captureName != null)
+                    captures.remove(element.captureName);
+            }
+            if (count == element.max || pos >= tokens.size() || !element.matcher.matches(tokens.get(pos)))
+                return -1;
+            pos++;
+            count++;
+        }
+    }
+
+    private static final class Element {
+        final TokenMatcher matcher;
+        final String captureName;
+        final int min;
+        final int max;
+
+        Element(TokenMatcher matcher, String captureName, int min, int max) {
+            this.matcher = matcher;
+            this.captureName = captureName;
+            this.min = min;
+            this.max = max;
+        }
+    }
+
+    public static final class PatternMatch {
+        public final TokenSearch.Match span;
+        private final Map<String, TokenSearch.Match> captures;
+
+        private PatternMatch(TokenSearch.Match span, Map<String, TokenSearch.Match> captures) {
+            this.span = span;
+            this.captures = captures;
+        }
+
+        /**
+         * @return the captured range of tokens, or null if there's no such capture
+         */
+        public TokenSearch.Match capture(String name) {
+            return captures.get(name);
+        }
+
+        public Map<String, TokenSearch.Match> captures() {
+            return Collections.unmodifiableMap(captures);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.List;
+
+public final class TokenSearch {
+    private TokenSearch() {
+    }
+
+    /**
+     * Finds every position where the pattern matches consecutive tokens.
+     * Matches may overlap.
+     */
+    public static List<Match> find(List<Token> tokens, List<TokenMatcher> pattern) {
+        ArrayList<Match> matches = new ArrayList<>();
+        if (pattern.isEmpty())
+            return matches;
+
+        for (int start = 0; start + pattern.size() <= tokens.size(); start++) {
+            if (matchesAt(tokens, start, pattern))
+                matches.add(new Match(start, start + pattern.size()));
+        }
+        return matches;
+    }
+
+    public static List<Match> find(List<Token> tokens, TokenMatcher... pattern) {
+        return find(tokens, List.of(pattern));
+    }
+
+    private static boolean matchesAt(List<Token> tokens, int start, List<TokenMatcher> pattern) {
+        for (int i = 0; i < pattern.size(); i++) {
+            if (!pattern.get(i).matches(tokens.get(start + i)))
+                return false;
+        }
+        return true;
+    }
+
+    /**
+     * Range of token indices, end is exclusive.
+     */
+    public static final class Match {
+        public final int start;
+        public final int end;
+
+        public Match(int start, int end) {
+            this.start = start;
+            this.end = end;
+        }
+
+        public List<Token> tokens(List<Token> tokens) {
+            return tokens.subList(start, end);
+        }
+
+        @Override
+        public String toString() {
+            return start + ".." + end;
+        }
+    }
+}
@@ -0,0 +1,23 @@
+package ua.yuriih.rustlexer;
+
+/**
+ * Receives tokens as the lexer finds them, see {@link Lexer#lexInto(TokenSink)}. Nothing is collected on the way,
+ * so counting tokens or a single-pass analysis costs no more memory than the sink itself uses.
+ */
+public interface TokenSink {
+    void token(Token token);
+
+    /**
+     * Lexical errors, in source order along with the other tokens. By default they go to {@link #token} too.
+     * With {@link LexerOptions.Builder#collectErrors} on they're kept in {@link Lexer#errors()} instead.
+     */
+    default void error(LexError error) {
+        token(error);
+    }
+
+    /**
+     * Called once after the last token.
+     */
+    default void end() {
+    }
+}
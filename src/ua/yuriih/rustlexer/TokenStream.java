@@ -0,0 +1,590 @@
+package ua.yuriih.rustlexer;
+
+import java.io.DataInputStream;
+import java.io.DataOutputStream;
+import java.io.IOException;
+import java.io.InputStream;
+import java.io.OutputStream;
+import java.io.UncheckedIOException;
+import java.nio.ByteBuffer;
+import java.nio.CharBuffer;
+import java.nio.charset.StandardCharsets;
+import java.security.MessageDigest;
+import java.security.NoSuchAlgorithmException;
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Sequence of lexed tokens that can be dumped to a compact binary format and loaded back
+ * without the original source file.
+ */
+public final class TokenStream implements Iterable<Token> {
+    private static final int DUMP_MAGIC = 0x524C4558; //"RLEX"
+    private static final int DUMP_VERSION = 1;
+
+    private final ArrayList<Token> tokens;
+    private final String source;
+
+    public TokenStream(List<Token> tokens) {
+        this(tokens, null);
+    }
+
+    /**
+     * @param source the text the tokens were lexed from, kept for {@link #text}
+     */
+    public TokenStream(List<Token> tokens, String source) {
+        this.tokens = new ArrayList<>(tokens);
+        this.source = source;
+    }
+
+    /**
+     * Lexes the source and keeps it around, so tokens can be lexed without {@link LexerOptions.Builder#storeText text}.
+     */
+    public static TokenStream lex(String source, LexerOptions options) throws IOException {
+        return new TokenStream(Lexer.fromString(source, options).parse(), source);
+    }
+
+    public List<Token> tokens() {
+        return Collections.unmodifiableList(tokens);
+    }
+
+    public int size() {
+        return tokens.size();
+    }
+
+    public Token get(int index) {
+        return tokens.get(index);
+    }
+
+    /**
+     * Tokens on lines [fromLine, toLine), 0-based. Tokens spanning several lines, like block comments,
+     * raw strings or whitespace, are included if any of their lines is in the range.
+     */
+    public TokenStream lines(int fromLine, int toLine) {
+        ArrayList<Token> result = new ArrayList<>();
+        for (Token token : tokens) {
+            if (token.isSynthetic() || token.line >= toLine)
+                continue;
+            if (token.line >= fromLine || endLine(token) >= fromLine)
+                result.add(token);
+        }
+        return new TokenStream(result, source);
+    }
+
+    /**
+     * Replaces one line of the source and re-lexes only that line, for small edits in an editor.
+     *
+     * @param line 0-based line number
+     * @param newText the line's new text, without a line break
+     * @param options must be the same options the stream was lexed with
+     * @return the updated stream, or null if the edit can affect other lines (e.g. the line is part of
+     * a block comment or raw string, or now opens one) and the whole source has to be lexed again
+     */
+    public TokenStream relexLine(int line, String newText, LexerOptions options) {
+        if (source == null)
+            throw new IllegalStateException("Re-lexing needs the source");
+        if (newText.indexOf('\n') >= 0)
+            throw new IllegalArgumentException("New text has a line break");
+
+        int lineStart = 0;
+        for (int i = 0; i < line; i++) {
+            lineStart = source.indexOf('\n', lineStart) + 1;
+            if (lineStart == 0)
+                throw new IllegalArgumentException("No line " + line + " in the source");
+        }
+        int lineEnd = source.indexOf('\n', lineStart);
+        if (lineEnd < 0)
+            lineEnd = source.length();
+
+        //re-lex whole tokens, whitespace can reach into the neighbouring lines
+        int first = -1;
+        int last = -1;
+        int regionStart = lineStart;
+        int regionEnd = lineEnd;
+        int regionLine = line;
+        int regionColumn = 0;
+        for (int i = 0; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (token.isSynthetic() || token.line > line)
+                continue;
+            int endLine = endLine(token);
+            if (endLine < line)
+                continue;
+            if (token.type != TokenType.WHITESPACE && (token.line != line || endLine != line))
+                return null;
+            if (first < 0)
+                first = i;
+            last = i;
+            if (token.offset < regionStart) {
+                regionStart = token.offset;
+                regionLine = token.line;
+                regionColumn = token.column;
+            }
+            regionEnd = Math.max(regionEnd, token.end());
+        }
+
+        String newSource = source.substring(0, lineStart) + newText + source.substring(lineEnd);
+        int delta = newText.length() - (lineEnd - lineStart);
+        String region = newSource.substring(regionStart, regionEnd + delta);
+
+        Lexer lexer = Lexer.fromRegion(region, regionStart == 0, options);
+        ArrayList<Token> relexed;
+        try {
+            relexed = lexer.parse();
+        } catch (IOException e) {
+            //StringReader doesn't throw
+            throw new UncheckedIOException(e);
+        }
+        if (lexer.endedInsideToken())
+            return null;
+
+        int from = first >= 0 ? first : insertionIndex(lineStart);
+        int to = first >= 0 ? last + 1 : from;
+        ArrayList<Token> result = new ArrayList<>(tokens.size() - (to - from) + relexed.size());
+        result.addAll(tokens.subList(0, from));
+        for (Token token : relexed) {
+            int column = token.line == 0 ? regionColumn + token.column : token.column;
+            result.add(token.withPosition(regionLine + token.line, column, regionStart + token.offset));
+        }
+        for (Token token : tokens.subList(to, tokens.size())) {
+            if (token.isSynthetic())
+                result.add(token);
+            else
+                result.add(token.withPosition(token.line, token.column, token.offset + delta));
+        }
+        return new TokenStream(result, newSource);
+    }
+
+    /**
+     * Finds the tokens that an edit could change, for incremental re-lexing. The edit replaces chars [start, end)
+     * of the old source with newLength chars.
+     * <p>
+     * The damage covers every token overlapping or touching the edit (so a block comment or raw string around it is
+     * included whole), the token before those, so the edit isn't re-lexed without context,
+     * and the rest of the last line, because the edit can start a line comment or join two lines (lines are found
+     * from the source if the stream has it, otherwise from the tokens, which misses edits inside comments when trivia
+     * isn't emitted). If re-lexing
+     * the damaged text ends inside a token, the edit opened a block comment or string and the caller has to keep going
+     * past the damage.
+     */
+    public Damage damage(int start, int end, int newLength) {
+        if (start < 0 || end < start || (source != null && end > source.length()))
+            throw new IllegalArgumentException("Invalid edit range " + start + ".." + end);
+
+        int from = -1;
+        int to = -1;
+        for (int i = 0; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (token.isSynthetic() || token.offset > end || token.end() < start)
+                continue;
+            if (from < 0)
+                from = i;
+            to = i + 1;
+        }
+        if (from < 0) {
+            from = insertionIndex(start);
+            to = from;
+        }
+        int previous = from - 1;
+        while (previous >= 0 && (tokens.get(previous).isSynthetic() || tokens.get(previous).type.isTrivia()))
+            previous--;
+        if (previous >= 0)
+            from = previous;
+
+        int damageStart = start;
+        int damageEnd = end;
+        for (int i = from; i < to; i++) {
+            Token token = tokens.get(i);
+            if (token.isSynthetic())
+                continue;
+            damageStart = Math.min(damageStart, token.offset);
+            damageEnd = Math.max(damageEnd, token.end());
+        }
+
+        //the rest of the line, the edit can start a line comment or join two lines
+        int lastLine = -1;
+        for (int i = from; i < to; i++) {
+            if (!tokens.get(i).isSynthetic())
+                lastLine = Math.max(lastLine, endLine(tokens.get(i)));
+        }
+        int lineEnd = source != null ? lineEnd(damageEnd) : Integer.MAX_VALUE;
+        for (; to < tokens.size(); to++) {
+            Token token = tokens.get(to);
+            if (token.isSynthetic())
+                continue;
+            if (source != null ? token.offset > lineEnd : token.line > lastLine)
+                break;
+            damageEnd = Math.max(damageEnd, token.end());
+            //a block comment or string reaching into the next lines
+            if (source != null && token.type != TokenType.WHITESPACE && token.end() > lineEnd)
+                lineEnd = lineEnd(token.end());
+        }
+        return new Damage(damageStart, damageEnd, damageEnd + newLength - (end - start), from, to);
+    }
+
+    private int lineEnd(int offset) {
+        int lineEnd = source.indexOf('\n', offset);
+        return lineEnd < 0 ? source.length() : lineEnd;
+    }
+
+    // index of the first token starting at or after the offset
+    private int insertionIndex(int offset) {
+        for (int i = 0; i < tokens.size(); i++) {
+            if (!tokens.get(i).isSynthetic() && tokens.get(i).offset >= offset)
+                return i;
+        }
+        return tokens.size();
+    }
+
+    int endLine(Token token) {
+        CharSequence text = text(token);
+        int line = token.line;
+        if (text != null) {
+            for (int i = 0; i < text.length(); i++) {
+                if (text.charAt(i) == '\n')
+                    line++;
+            }
+        }
+        return line;
+    }
+
+    /**
+     * Finds the token containing the char at the offset, e.g. under the editor cursor.
+     * Trivia, errors and synthetic tokens are skipped, so an offset in a comment or whitespace
+     * gives only the tokens before and after it.
+     */
+    public TokenAt tokenAt(int offset) {
+        int before = -1;
+        for (int i = 0; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (token.isSynthetic() || token.type == TokenType.ERROR || token.type.isTrivia())
+                continue;
+            if (offset < token.offset)
+                return new TokenAt(-1, before, i);
+            if (offset < token.end())
+                return new TokenAt(i, before, nextIndex(i));
+            before = i;
+        }
+        return new TokenAt(-1, before, -1);
+    }
+
+    private int nextIndex(int index) {
+        for (int i = index + 1; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (!token.isSynthetic() && token.type != TokenType.ERROR && !token.type.isTrivia())
+                return i;
+        }
+        return -1;
+    }
+
+    /**
+     * @return the source, or null if the stream was made without it
+     */
+    public String source() {
+        return source;
+    }
+
+    /**
+     * Source text of the token as a view into the retained source, without copying it.
+     * Falls back to {@link Token#text()} for synthetic tokens or if there's no source.
+     */
+    public CharSequence text(Token token) {
+        return text(source, token);
+    }
+
+    /**
+     * @param source null if the tokens were lexed without retaining it
+     */
+    static CharSequence text(String source, Token token) {
+        if (source == null || token.isSynthetic() || token.type == TokenType.ERROR)
+            return token.text();
+        return CharBuffer.wrap(source, token.offset, token.end());
+    }
+
+    /**
+     * Copy of the stream with floats after a dot split up as tuple indexes, so x.0.1 is x . 0 . 1
+     * instead of x . 0.1, see {@link Literal#splitTupleIndex()}.
+     *
+     * @throws IllegalStateException if such a float was lexed without storing its text and there's no source
+     */
+    public TokenStream splitTupleIndexes() {
+        ArrayList<Token> result = new ArrayList<>(tokens.size());
+        Token previous = null;
+        for (Token token : tokens) {
+            List<Token> parts = null;
+            if (token.type == TokenType.FLOAT_LITERAL && previous != null && previous.type == TokenType.DOT) {
+                CharSequence text = text(token);
+                if (text == null)
+                    throw new IllegalStateException("Float at " + token.line + ":" + token.column
+                            + " has no text: it was lexed without storing text, and the stream has no source");
+                Token withText = token.value != null ? token : new Token(token.line, token.column, token.offset,
+                        token.length, token.type, text.toString());
+                parts = new Literal(withText).splitTupleIndex();
+            }
+            if (parts != null)
+                result.addAll(parts);
+            else
+                result.add(token);
+            if (!token.type.isTrivia())
+                previous = token;
+        }
+        return new TokenStream(result, source);
+    }
+
+    /**
+     * @return a cursor at the first token
+     */
+    public TokenCursor cursor() {
+        return new TokenCursor(this);
+    }
+
+    /**
+     * Immutable copy that can be used from several threads.
+     */
+    public SharedTokenStream share() {
+        return SharedTokenStream.of(tokens, source);
+    }
+
+    /**
+     * Copy of the stream that doesn't depend on the source: text of tokens lexed without
+     * {@link LexerOptions.Builder#storeText storing it} is copied into their values, and the source is dropped.
+     */
+    public TokenStream intoOwned() {
+        if (source == null)
+            return this;
+        ArrayList<Token> owned = new ArrayList<>(tokens.size());
+        for (Token token : tokens) {
+            if (token.value != null || token.isSynthetic() || token.type == TokenType.ERROR) {
+                owned.add(token);
+                continue;
+            }
+            String text = source.substring(token.offset, token.end());
+            if (text.equals(token.type.text))
+                owned.add(token);
+            else
+                owned.add(new Token(token.line, token.column, token.offset, token.length, token.type, text,
+                        token.flags()));
+        }
+        return new TokenStream(owned);
+    }
+
+    /**
+     * @return a new stream with the tokens inserted before the given index, usually {@link Token#isSynthetic() synthetic} ones
+     */
+    public TokenStream insert(int index, List<Token> inserted) {
+        ArrayList<Token> result = new ArrayList<>(tokens.size() + inserted.size());
+        result.addAll(tokens.subList(0, index));
+        result.addAll(inserted);
+        result.addAll(tokens.subList(index, tokens.size()));
+        return new TokenStream(result, source);
+    }
+
+    public Splice concat(TokenStream other) {
+        return Splice.of(List.of(new Splice.Segment(0, tokens), new Splice.Segment(1, other.tokens)));
+    }
+
+    /**
+     * Replaces tokens [from, to) with the other stream's tokens.
+     */
+    public Splice replace(int from, int to, TokenStream replacement) {
+        return Splice.of(List.of(
+                new Splice.Segment(0, tokens.subList(0, from)),
+                new Splice.Segment(1, replacement.tokens),
+                new Splice.Segment(0, tokens.subList(to, tokens.size()))));
+    }
+
+    /**
+     * Removes tokens [from, to).
+     */
+    public Splice delete(int from, int to) {
+        return Splice.of(List.of(
+                new Splice.Segment(0, tokens.subList(0, from)),
+                new Splice.Segment(0, tokens.subList(to, tokens.size()))));
+    }
+
+    @Override
+    public TokenIterator iterator() {
+        return TokenIterator.of(tokens());
+    }
+
+    public void dump(OutputStream out) throws IOException {
+        DataOutputStream data = new DataOutputStream(out);
+        data.writeInt(DUMP_MAGIC);
+        data.writeInt(DUMP_VERSION);
+        data.writeInt(tokens.size());
+        for (Token token : tokens) {
+            data.writeInt(token.type.code);
+            data.writeInt(token.line);
+            data.writeInt(token.column);
+            data.writeInt(token.offset);
+            data.writeInt(token.length);
+            data.writeInt(token.flags());
+            writeString(data, token.value);
+            writeString(data, kindOf(token));
+        }
+        data.flush();
+    }
+
+    public static TokenStream fromDump(InputStream in) throws IOException {
+        DataInputStream data = new DataInputStream(in);
+        if (data.readInt() != DUMP_MAGIC)
+            throw new IOException("Not a token dump");
+        int version = data.readInt();
+        if (version != DUMP_VERSION)
+            throw new IOException("Unsupported token dump version: " + version);
+
+        int count = data.readInt();
+        ArrayList<Token> tokens = new ArrayList<>(count);
+        for (int i = 0; i < count; i++) {
+            TokenType type;
+            try {
+                type = TokenType.fromCode(data.readInt());
+            } catch (IllegalArgumentException e) {
+                throw new IOException("Unknown token type in dump", e);
+            }
+            int line = data.readInt();
+            int column = data.readInt();
+            int offset = data.readInt();
+            int length = data.readInt();
+            int flags = data.readInt();
+            String value = readString(data);
+            String kind = readString(data);
+            if (kind != null && type == TokenType.CUSTOM) {
+                tokens.add(new CustomToken(line, column, offset, kind, value, flags));
+            } else if (kind != null && type == TokenType.ERROR) {
+                try {
+                    int colon = kind.indexOf(':');
+                    int radix = colon >= 0 ? Integer.parseInt(kind.substring(colon + 1)) : 0;
+                    LexError.Kind errorKind = LexError.Kind.valueOf(colon >= 0 ? kind.substring(0, colon) : kind);
+                    tokens.add(new LexError(line, column, offset, length, errorKind, value, radix, flags));
+                } catch (IllegalArgumentException e) {
+                    throw new IOException("Unknown error kind in dump", e);
+                }
+            } else {
+                tokens.add(new Token(line, column, offset, length, type, value, flags));
+            }
+        }
+        return new TokenStream(tokens);
+    }
+
+    private static String kindOf(Token token) {
+        if (token instanceof CustomToken)
+            return ((CustomToken) token).kind;
+        if (token instanceof LexError) {
+            LexError error = (LexError) token;
+            return error.radix != 0 ? error.kind.name() + ":" + error.radix : error.kind.name();
+        }
+        return null;
+    }
+
+    private static void writeString(DataOutputStream data, String s) throws IOException {
+        if (s == null) {
+            data.writeInt(-1);
+            return;
+        }
+        byte[] bytes = s.getBytes(StandardCharsets.UTF_8);
+        data.writeInt(bytes.length);
+        data.write(bytes);
+    }
+
+    private static String readString(DataInputStream data) throws IOException {
+        int length = data.readInt();
+        if (length < 0)
+            return null;
+        byte[] bytes = new byte[length];
+        data.readFully(bytes);
+        return new String(bytes, StandardCharsets.UTF_8);
+    }
+
+    /**
+     * SHA-256 of the significant tokens, in hex: their types and texts, without whitespace, comments and shebangs.
+     * Build tools can use it to skip work when only formatting or comments changed.
+     * <p>
+     * The hash is computed from the {@link TokenType#code type codes} and the token texts, so it stays the same across
+     * versions of this library unless the same source is lexed differently (e.g. a bug fix splits a token). Options
+     * affect it too: the same file lexed with a different edition can hash differently.
+     */
+    public String semanticHash() {
+        MessageDigest digest;
+        try {
+            digest = MessageDigest.getInstance("SHA-256");
+        } catch (NoSuchAlgorithmException e) {
+            throw new AssertionError("SHA-256 is always available", e);
+        }
+        for (Token token : tokens) {
+            if (token.type.isTrivia())
+                continue;
+            CharSequence text = token.type == TokenType.ERROR ? token.value : text(token);
+            //length-prefixed, so token boundaries count
+            byte[] bytes = text != null ? text.toString().getBytes(StandardCharsets.UTF_8) : new byte[0];
+            digest.update(ByteBuffer.allocate(8).putInt(token.type.code).putInt(bytes.length).array());
+            digest.update(bytes);
+        }
+        StringBuilder hex = new StringBuilder();
+        for (byte b : digest.digest())
+            hex.append(String.format("%02x", b));
+        return hex.toString();
+    }
+
+    @Override
+    public boolean equals(Object o) {
+        return o instanceof TokenStream && tokens.equals(((TokenStream) o).tokens);
+    }
+
+    @Override
+    public int hashCode() {
+        return tokens.hashCode();
+    }
+
+    /**
+     * Result of {@link #tokenAt}, indexes are -1 if there's no such token.
+     */
+    public static final class TokenAt {
+        /** The token containing the offset */
+        public final int index;
+        /** The last token before the offset, not counting the one containing it */
+        public final int before;
+        /** The first token after the offset */
+        public final int after;
+
+        private TokenAt(int index, int before, int after) {
+            this.index = index;
+            this.before = before;
+            this.after = after;
+        }
+
+        public boolean found() {
+            return index >= 0;
+        }
+    }
+
+    /**
+     * Part of a stream that has to be lexed again after an edit, see {@link #damage}.
+     */
+    public static final class Damage {
+        /** Start of the damaged text, the same in the old and new source */
+        public final int start;
+        /** End of the damaged text in the old source */
+        public final int end;
+        /** End of the damaged text in the new source */
+        public final int newEnd;
+        /** Index of the first token to replace */
+        public final int from;
+        /** Index after the last token to replace */
+        public final int to;
+
+        private Damage(int start, int end, int newEnd, int from, int to) {
+            this.start = start;
+            this.end = end;
+            this.newEnd = newEnd;
+            this.from = from;
+            this.to = to;
+        }
+
+        @Override
+        public String toString() {
+            return "Damage(" + start + ".." + end + " -> " + start + ".." + newEnd + ", tokens " + from + ".." + to + ")";
+        }
+    }
+}
@@ -0,0 +1,211 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Tokens grouped by their delimiters: (), [] and {}, like the token trees macros work with.
+ * <p>
+ * Mismatched input still gives a tree: a group that's never closed has a null {@link Group#close}, and a closing
+ * delimiter that doesn't match any open group is kept as a leaf and listed in {@link #unmatched}.
+ * If it does match a group further out, the groups in between are left unclosed.
+ */
+public final class TokenTree {
+    /** The whole file, its delimiters are null */
+    public final Group root;
+    /** Closing delimiters without an opening one */
+    public final List<Token> unmatched;
+
+    private TokenTree(Group root, List<Token> unmatched) {
+        this.root = root;
+        this.unmatched = Collections.unmodifiableList(unmatched);
+    }
+
+    public static TokenTree build(List<Token> tokens) {
+        Group root = new Group(null, 0);
+        ArrayList<Group> stack = new ArrayList<>();
+        stack.add(root);
+        ArrayList<Token> unmatched = new ArrayList<>();
+
+        for (Token token : tokens) {
+            Group current = stack.get(stack.size() - 1);
+            if (isOpening(token.type)) {
+                Group group = new Group(token, stack.size());
+                current.children.add(group);
+                stack.add(group);
+                continue;
+            }
+            if (!isClosing(token.type)) {
+                current.children.add(new Leaf(token));
+                continue;
+            }
+
+            int match = stack.size() - 1;
+            while (match > 0 && stack.get(match).open.type != opening(token.type))
+                match--;
+            if (match == 0) {
+                current.children.add(new Leaf(token));
+                unmatched.add(token);
+                continue;
+            }
+            stack.get(match).close = token;
+            while (stack.size() > match)
+                stack.remove(stack.size() - 1);
+        }
+        return new TokenTree(root, unmatched);
+    }
+
+    /**
+     * Finds the delimiter that matches the one at the offset, for jumping to the matching brace. Delimiters in
+     * strings and comments aren't tokens, so they're never matched.
+     *
+     * @param offset of an opening or closing delimiter
+     * @return the delimiter and its counterpart, which is null if it's unmatched; null if there's no delimiter
+     * at the offset
+     */
+    public Match matching(int offset) {
+        for (Token token : unmatched) {
+            if (token.offset == offset)
+                return new Match(token, null);
+        }
+        Group group = root;
+        search:
+        while (true) {
+            for (Node child : group.children) {
+                if (!(child instanceof Group))
+                    continue;
+                Group candidate = (Group) child;
+                if (candidate.open.offset == offset)
+                    return new Match(candidate.open, candidate.close);
+                if (candidate.close != null && candidate.close.offset == offset)
+                    return new Match(candidate.close, candidate.open);
+                if (candidate.start() < offset && offset < candidate.end()) {
+                    group = candidate;
+                    continue search;
+                }
+            }
+            return null;
+        }
+    }
+
+    public static boolean isOpening(TokenType type) {
+        return type == TokenType.PAREN_L || type == TokenType.SQUARE_L || type == TokenType.CURLY_L;
+    }
+
+    public static boolean isClosing(TokenType type) {
+        return type == TokenType.PAREN_R || type == TokenType.SQUARE_R || type == TokenType.CURLY_R;
+    }
+
+    private static TokenType opening(TokenType closing) {
+        return switch (closing) {
+            case PAREN_R -> TokenType.PAREN_L;
+            case SQUARE_R -> TokenType.SQUARE_L;
+            case CURLY_R -> TokenType.CURLY_L;
+            default -> throw new IllegalArgumentException(closing + " is not a closing delimiter");
+        };
+    }
+
+    /**
+     * A {@link Leaf} or a {@link Group}.
+     */
+    public abstract static class Node {
+        private Node() {
+        }
+    }
+
+    public static final class Leaf extends Node {
+        public final Token token;
+
+        private Leaf(Token token) {
+            this.token = token;
+        }
+
+        @Override
+        public String toString() {
+            return token.toString();
+        }
+    }
+
+    public static final class Group extends Node {
+        /** Null for the root */
+        public final Token open;
+        /** Null for the root and for groups that are never closed */
+        private Token close;
+        /** 0 for the root, 1 for top-level groups... */
+        public final int depth;
+        private final ArrayList<Node> children = new ArrayList<>();
+
+        private Group(Token open, int depth) {
+            this.open = open;
+            this.depth = depth;
+        }
+
+        public Token close() {
+            return close;
+        }
+
+        public List<Node> children() {
+            return Collections.unmodifiableList(children);
+        }
+
+        /**
+         * @return the delimiters, like "()", with a missing closing one left out; empty for the root
+         */
+        public String delimiters() {
+            if (open == null)
+                return "";
+            return open.type.text + (close != null ? close.type.text : "");
+        }
+
+        /**
+         * @return the offset of the opening delimiter, 0 for the root
+         */
+        public int start() {
+            return open != null ? open.offset : 0;
+        }
+
+        /**
+         * @return the end offset of the closing delimiter, or of the last token inside if it's missing
+         */
+        public int end() {
+            if (close != null)
+                return close.end();
+            int end = open != null ? open.end() : 0;
+            for (Node child : children) {
+                int childEnd = child instanceof Group ? ((Group) child).end() : ((Leaf) child).token.end();
+                end = Math.max(end, childEnd);
+            }
+            return end;
+        }
+
+        @Override
+        public String toString() {
+            return open == null ? "root" : delimiters() + " at " + open.line + ":" + open.column;
+        }
+    }
+
+    public static final class Match {
+        /** The delimiter at the offset that was asked about */
+        public final Token delimiter;
+        /** The delimiter it's paired with, null if it's unmatched */
+        public final Token counterpart;
+
+        private Match(Token delimiter, Token counterpart) {
+            this.delimiter = delimiter;
+            this.counterpart = counterpart;
+        }
+
+        public boolean isMatched() {
+            return counterpart != null;
+        }
+
+        @Override
+        public String toString() {
+            if (counterpart == null)
+                return delimiter.type.text + " at " + delimiter.offset + " unmatched";
+            return delimiter.type.text + " at " + delimiter.offset + " matches " + counterpart.type.text + " at "
+                    + counterpart.offset;
+        }
+    }
+}
@@ -1,142 +1,299 @@
 package ua.yuriih.rustlexer;
 
+import java.util.Arrays;
+
+/**
+ * Kinds of tokens. Each one's {@link #code} is written next to it, codes don't depend on the order of the constants.
+ */
 public enum TokenType {
     //
     // Keywords
     //
     // Strict:
-    AS,
-    BREAK,
-    CONST,
-    CONTINUE,
-    CRATE,
-    ELSE,
-    ENUM,
-    EXTERN,
-    FALSE,
-    FN,
-    FOR,
-    IF,
-    IMPL,
-    IN,
-    LET,
-    LOOP,
-    MATCH,
-    MOD,
-    MOVE,
-    MUT,
-    PUB,
-    REF,
-    RETURN,
-    SELF_VALUE,
-    SELF_TYPE,
-    STATIC,
-    STRUCT,
-    SUPER,
-    TRAIT,
-    TRUE,
-    TYPE,
-    UNSAFE,
-    USE,
-    WHERE,
-    WHILE,
+    AS("as", 0),
+    BREAK("break", 1),
+    CONST("const", 2),
+    CONTINUE("continue", 3),
+    CRATE("crate", 4),
+    ELSE("else", 5),
+    ENUM("enum", 6),
+    EXTERN("extern", 7),
+    FALSE("false", 8),
+    FN("fn", 9),
+    FOR("for", 10),
+    IF("if", 11),
+    IMPL("impl", 12),
+    IN("in", 13),
+    LET("let", 14),
+    LOOP("loop", 15),
+    MATCH("match", 16),
+    MOD("mod", 17),
+    MOVE("move", 18),
+    MUT("mut", 19),
+    PUB("pub", 20),
+    REF("ref", 21),
+    RETURN("return", 22),
+    SELF_VALUE("self", 23),
+    SELF_TYPE("Self", 24),
+    STATIC("static", 25),
+    STRUCT("struct", 26),
+    SUPER("super", 27),
+    TRAIT("trait", 28),
+    TRUE("true", 29),
+    TYPE("type", 30),
+    UNSAFE("unsafe", 31),
+    USE("use", 32),
+    WHERE("where", 33),
+    WHILE("while", 34),
     // Strict (since 2018)
-    ASYNC,
-    AWAIT,
-    DYN, //weak since 2015
+    ASYNC("async", 35),
+    AWAIT("await", 36),
+    DYN("dyn", 37), //weak since 2015
     // Reserved
-    ABSTRACT,
-    BECOME,
-    BOX,
-    DO,
-    FINAL,
-    MACRO,
-    OVERRIDE,
-    PRIV,
-    TYPEOF,
-    UNSIZED,
-    VIRTUAL,
-    YIELD,
+    ABSTRACT("abstract", 38),
+    BECOME("become", 39),
+    BOX("box", 40),
+    DO("do", 41),
+    FINAL("final", 42),
+    MACRO("macro", 43),
+    OVERRIDE("override", 44),
+    PRIV("priv", 45),
+    TYPEOF("typeof", 46),
+    UNSIZED("unsized", 47),
+    VIRTUAL("virtual", 48),
+    YIELD("yield", 49),
     // Reserved (since 2018)
-    TRY,
-    // Weak
-    UNION,
-    STATIC_LIFETIME,
-
-    IDENTIFIER,
-    RAW_IDENTIFIER,
-
-    COMMENT,
-    COMMENT_INNER_DOC,
-    COMMENT_OUTER_DOC,
-
-    LABEL,
-    LIFETIME,
-
-    CHAR_LITERAL,
-    STRING_LITERAL,
-    RAW_STRING_LITERAL,
-    BYTE_LITERAL,
-    BYTE_STRING_LITERAL,
-    RAW_BYTE_STRING_LITERAL,
-    INT_LITERAL_DEC,
-    INT_LITERAL_HEX,
-    INT_LITERAL_OCTAL,
-    INT_LITERAL_BIN,
-    FLOAT_LITERAL,
-
-    PLUS,
-    MINUS,
-    STAR,
-    SLASH,
-    PERCENT,
-    CARET,
-    NOT,
-    AND,
-    OR,
-    AND_AND,
-    OR_OR,
-    SHL,
-    SHR,
-    PLUS_EQ,
-    MINUS_EQ,
-    STAR_EQ,
-    SLASH_EQ,
-    PERCENT_EQ,
-    CARET_EQ,
-    AND_EQ,
-    OR_EQ,
-    SHL_EQ,
-    SHR_EQ,
-    EQ,
-    EQ_EQ,
-    NE,
-    GT,
-    LT,
-    GE,
-    LE,
-    AT,
-    UNDERSCORE,
-    DOT,
-    DOT_DOT,
-    DOT_DOT_DOT,
-    DOT_DOT_EQ,
-    COMMA,
-    SEMICOLON,
-    COLON,
-    PATH_SEPARATOR,
-    R_ARROW,
-    FAT_ARROW,
-    POUND,
-    DOLLAR,
-    QUESTION,
-
-    CURLY_L,
-    CURLY_R,
-    SQUARE_L,
-    SQUARE_R,
-    PAREN_L,
-    PAREN_R,
-
-    ERROR
+    TRY("try", 50),
+    // Reserved (since 2024)
+    GEN("gen", 51),
+    // Added through LexerOptions
+    CUSTOM_KEYWORD(52),
+    // Weak, not produced anymore: "union" is an identifier, see Token#isContextualKeyword()
+    UNION("union", 53),
+    STATIC_LIFETIME("'static", 54),
+
+    IDENTIFIER(55),
+    RAW_IDENTIFIER(56),
+
+    WHITESPACE(57),
+    COMMENT(58),
+    COMMENT_INNER_DOC(59),
+    COMMENT_OUTER_DOC(60),
+    /** "#!/usr/bin/env ..." on the first line */
+    SHEBANG(61),
+
+    LABEL(62),
+    LIFETIME(63),
+    RAW_LABEL(64),
+    RAW_LIFETIME(65),
+
+    CHAR_LITERAL(66),
+    STRING_LITERAL(67),
+    RAW_STRING_LITERAL(68),
+    BYTE_LITERAL(69),
+    BYTE_STRING_LITERAL(70),
+    RAW_BYTE_STRING_LITERAL(71),
+    INT_LITERAL_DEC(72),
+    INT_LITERAL_HEX(73),
+    INT_LITERAL_OCTAL(74),
+    INT_LITERAL_BIN(75),
+    FLOAT_LITERAL(76),
+
+    PLUS("+", 77),
+    MINUS("-", 78),
+    STAR("*", 79),
+    SLASH("/", 80),
+    PERCENT("%", 81),
+    CARET("^", 82),
+    NOT("!", 83),
+    AND("&", 84),
+    OR("|", 85),
+    AND_AND("&&", 86),
+    OR_OR("||", 87),
+    SHL("<<", 88),
+    SHR(">>", 89),
+    PLUS_EQ("+=", 90),
+    MINUS_EQ("-=", 91),
+    STAR_EQ("*=", 92),
+    SLASH_EQ("/=", 93),
+    PERCENT_EQ("%=", 94),
+    CARET_EQ("^=", 95),
+    AND_EQ("&=", 96),
+    OR_EQ("|=", 97),
+    SHL_EQ("<<=", 98),
+    SHR_EQ(">>=", 99),
+    EQ("=", 100),
+    EQ_EQ("==", 101),
+    NE("!=", 102),
+    GT(">", 103),
+    LT("<", 104),
+    GE(">=", 105),
+    LE("<=", 106),
+    AT("@", 107),
+    UNDERSCORE("_", 108),
+    DOT(".", 109),
+    DOT_DOT("..", 110),
+    DOT_DOT_DOT("...", 111),
+    DOT_DOT_EQ("..=", 112),
+    COMMA(",", 113),
+    SEMICOLON(";", 114),
+    COLON(":", 115),
+    PATH_SEPARATOR("::", 116),
+    R_ARROW("->", 117),
+    FAT_ARROW("=>", 118),
+    POUND("#", 119),
+    DOLLAR("$", 120),
+    QUESTION("?", 121),
+
+    CURLY_L("{", 122),
+    CURLY_R("}", 123),
+    SQUARE_L("[", 124),
+    SQUARE_R("]", 125),
+    PAREN_L("(", 126),
+    PAREN_R(")", 127),
+
+    CUSTOM(128),
+
+    ERROR(129);
+
+    /**
+     * Fixed spelling of the token in source code, or null if it varies (identifiers, literals, comments...)
+     */
+    public final String text;
+    /**
+     * Number that stays the same across versions, for binary dumps and anything else that stores token types
+     * outside the process. New types get new codes, codes of removed ones aren't reused.
+     */
+    public final int code;
+
+    TokenType(String text, int code) {
+        this.text = text;
+        this.code = code;
+    }
+
+    TokenType(int code) {
+        this(null, code);
+    }
+
+    /**
+     * @throws IllegalArgumentException if no token type has the code
+     */
+    public static TokenType fromCode(int code) {
+        TokenType type = code >= 0 && code < ByCode.TYPES.length ? ByCode.TYPES[code] : null;
+        if (type == null)
+            throw new IllegalArgumentException("Unknown token type code: " + code);
+        return type;
+    }
+
+    public boolean isKeyword() {
+        return ordinal() <= UNION.ordinal();
+    }
+
+    /**
+     * Operators, delimiters and other punctuation, from {@link #PLUS} to {@link #PAREN_R}.
+     */
+    public boolean isPunctuation() {
+        return ordinal() >= PLUS.ordinal() && ordinal() <= PAREN_R.ordinal();
+    }
+
+    /**
+     * Looks up a Rust keyword (strict or reserved, in any edition) by its spelling, e.g. "fn" gives {@link #FN}.
+     *
+     * @return null if the text isn't a keyword, including weak ones like "union"
+     */
+    public static TokenType fromKeyword(CharSequence text) {
+        return KeywordTable.get(text);
+    }
+
+    private static final class ByCode {
+        private static final TokenType[] TYPES;
+
+        static {
+            int maxCode = 0;
+            for (TokenType type : values())
+                maxCode = Math.max(maxCode, type.code);
+            TYPES = new TokenType[maxCode + 1];
+            for (TokenType type : values()) {
+                if (TYPES[type.code] != null)
+                    throw new AssertionError("Duplicate token type code: " + type.code);
+                TYPES[type.code] = type;
+            }
+        }
+    }
+
+    /**
+     * Perfect hash of the keywords: the first two chars, the middle and last ones and the length are mixed with
+     * a multiplier that's searched for when the class loads, so every keyword gets its own slot and a lookup is
+     * one comparison.
+     */
+    private static final class KeywordTable {
+        private static final int BITS = 8;
+        private static final TokenType[] TABLE = new TokenType[1 << BITS];
+        private static final int SEED;
+        private static final int MAX_LENGTH;
+
+        static {
+            int maxLength = 0;
+            for (TokenType type : values()) {
+                if (isTableKeyword(type))
+                    maxLength = Math.max(maxLength, type.text.length());
+            }
+            MAX_LENGTH = maxLength;
+
+            int seed = 1;
+            while (!fill(seed)) {
+                seed += 2;
+                if (seed < 0)
+                    throw new AssertionError("No perfect hash for the keywords");
+            }
+            SEED = seed;
+        }
+
+        private KeywordTable() {
+        }
+
+        private static boolean isTableKeyword(TokenType type) {
+            return type.isKeyword() && type.text != null && type != UNION && type != STATIC_LIFETIME;
+        }
+
+        private static boolean fill(int seed) {
+            Arrays.fill(TABLE, null);
+            for (TokenType type : values()) {
+                if (!isTableKeyword(type))
+                    continue;
+                int slot = hash(type.text, seed);
+                if (TABLE[slot] != null)
+                    return false;
+                TABLE[slot] = type;
+            }
+            return true;
+        }
+
+        private static int hash(CharSequence text, int seed) {
+            int length = text.length();
+            int key = (text.charAt(0) << 24) ^ (text.charAt(1) << 16) ^ (text.charAt(length / 2) << 12)
+                    ^ (text.charAt(length - 1) << 6) ^ length;
+            return (key * seed) >>> (32 - BITS);
+        }
+
+        static TokenType get(CharSequence text) {
+            if (text.length() < 2 || text.length() > MAX_LENGTH)
+                return null;
+            TokenType type = TABLE[hash(text, SEED)];
+            if (type == null || type.text.length() != text.length())
+                return null;
+            for (int i = 0; i < text.length(); i++) {
+                if (type.text.charAt(i) != text.charAt(i))
+                    return null;
+            }
+            return type;
+        }
+    }
+
+    /**
+     * Whitespace and plain comments, which don't affect the meaning of the code (unlike doc comments)
+     */
+    public boolean isTrivia() {
+        return this == WHITESPACE || this == COMMENT || this == SHEBANG;
+    }
 }
@@ -0,0 +1,80 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.InputStream;
+import java.io.PrintStream;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.util.ArrayList;
+
+/**
+ * rlex tokenize: prints the token sequence of a file, or of stdin when the path is "-".
+ * Tokens are written as they are lexed, so the output can be consumed before the input ends.
+ */
+final class TokenizeCommand {
+    enum Format {
+        TEXT,
+        /** one JSON object per line, see {@link Json#token} */
+        JSONL,
+        /** only the errors, as a SARIF log printed at the end */
+        SARIF
+    }
+
+    private final PrintStream out;
+    private final PrintStream err;
+    private final Format format;
+
+    private String fileName;
+    private final ArrayList<Token> errors = new ArrayList<>();
+
+    TokenizeCommand(PrintStream out, PrintStream err, Format format) {
+        this.out = out;
+        this.err = err;
+        this.format = format;
+    }
+
+    /**
+     * @param fileName name shown in diagnostics, defaults to the path or "&lt;stdin&gt;"
+     * @return exit status: 0 if there were no lexical errors, 1 otherwise
+     */
+    int run(String path, String fileName, InputStream stdin) throws IOException {
+        errors.clear();
+        if (path.equals("-")) {
+            this.fileName = fileName != null ? fileName : "<stdin>";
+            new Lexer(stdin).parse(this::print);
+        } else {
+            this.fileName = fileName != null ? fileName : path;
+            try (InputStream in = Files.newInputStream(Path.of(path))) {
+                new Lexer(in).parse(this::print);
+            }
+        }
+        if (format == Format.SARIF)
+            out.println(Sarif.toSarif(this.fileName, errors));
+        out.flush();
+        return errors.isEmpty() ? 0 : 1;
+    }
+
+    private void print(Token token) {
+        switch (format) {
+            case TEXT -> {
+                out.printf("%d:%d\t%s", token.line + 1, token.column + 1, token.type);
+                if (token.value != null)
+                    out.print("\t" + escape(token.value));
+                out.println();
+            }
+            case JSONL -> out.println(Json.token(token));
+            case SARIF -> {
+            }
+        }
+
+        if (token.type == TokenType.ERROR) {
+            err.printf("%s:%d:%d: %s%n", fileName, token.line + 1, token.column + 1, token.value);
+            errors.add(token);
+        }
+    }
+
+    // keeps one token per line
+    private static String escape(String value) {
+        return value.replace("\\", "\\\\").replace("\n", "\\n").replace("\r", "\\r").replace("\t", "\\t");
+    }
+}
@@ -0,0 +1,162 @@
+package ua.yuriih.rustlexer;
+
+import java.util.EnumSet;
+import java.util.IdentityHashMap;
+import java.util.List;
+import java.util.Locale;
+
+/**
+ * A standalone HTML page for looking at how a file was lexed: the {@link TokenTree} on one side, as collapsible
+ * groups, and the highlighted source on the other. Hovering over a group or token highlights its text in the source,
+ * and checkboxes filter the tree by token type.
+ */
+public final class TreeExplorer {
+    private static final String SCRIPT = String.join("\n",
+            "document.querySelectorAll('[data-from]').forEach(function (node) {",
+            "  function mark(on) {",
+            "    for (var i = +node.dataset.from; i <= +node.dataset.to; i++) {",
+            "      var token = document.getElementById('t' + i);",
+            "      if (token) token.classList.toggle('hl', on);",
+            "    }",
+            "  }",
+            "  node.addEventListener('mouseover', function (e) { e.stopPropagation(); mark(true); });",
+            "  node.addEventListener('mouseout', function (e) { e.stopPropagation(); mark(false); });",
+            "});",
+            "document.querySelectorAll('#filters input').forEach(function (box) {",
+            "  function apply() {",
+            "    document.querySelectorAll('.leaf.k-' + box.value).forEach(function (leaf) {",
+            "      leaf.hidden = !box.checked;",
+            "    });",
+            "  }",
+            "  box.addEventListener('change', apply);",
+            "  apply();",
+            "});"
+    );
+
+    private static final String STYLE = String.join("\n",
+            "body { margin: 0; display: flex; height: 100vh; font-family: sans-serif; }",
+            "#tree, #source { overflow: auto; padding: 8px; margin: 0; }",
+            "#tree { width: 40%; border-right: 1px solid #ccc; font-size: 13px; }",
+            "#source { flex: 1; }",
+            "details { margin-left: 12px; }",
+            ".leaf { margin-left: 24px; font-family: monospace; white-space: pre; cursor: default; }",
+            ".leaf .type { color: #888; }",
+            "summary { font-family: monospace; cursor: pointer; }",
+            ".unclosed { color: #c00; }",
+            ".hl { outline: 2px solid #f90; background: #ffe8b0; }",
+            "#filters { margin-bottom: 8px; }",
+            "#filters label { display: inline-block; margin-right: 8px; }"
+    );
+
+    private TreeExplorer() {
+    }
+
+    public static String toHtml(String title, String source, List<Token> tokens) {
+        return toHtml(title, source, tokens, Theme.LIGHT);
+    }
+
+    /**
+     * @param title page title, usually the file name
+     * @param tokens tokens lexed from the source, preferably with trivia
+     * @param theme colors of the source pane
+     */
+    public static String toHtml(String title, String source, List<Token> tokens, Theme theme) {
+        IdentityHashMap<Token, Integer> indexes = new IdentityHashMap<>();
+        EnumSet<TokenType> types = EnumSet.noneOf(TokenType.class);
+        for (int i = 0; i < tokens.size(); i++) {
+            indexes.put(tokens.get(i), i);
+            if (!tokens.get(i).isSynthetic())
+                types.add(tokens.get(i).type);
+        }
+
+        StringBuilder html = new StringBuilder();
+        html.append("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>");
+        Highlighter.escapeHtml(html, title, 0, title.length());
+        html.append("</title>\n<style>\n").append(STYLE).append('\n');
+        for (TokenType type : types) {
+            Theme.Style style = theme.style(type);
+            if (!style.isPlain())
+                html.append(".k-").append(type.name()).append(" { ").append(Highlighter.css(style)).append(" }\n");
+        }
+        html.append("</style>\n</head>\n<body>\n<div id=\"tree\">\n<div id=\"filters\">");
+        for (TokenType type : types) {
+            html.append("<label><input type=\"checkbox\" value=\"").append(type.name()).append('"')
+                    .append(type == TokenType.WHITESPACE ? "" : " checked").append("> ")
+                    .append(type.name().toLowerCase(Locale.ROOT)).append("</label>");
+        }
+        html.append("</div>\n");
+        TokenTree tree = TokenTree.build(tokens);
+        for (TokenTree.Node child : tree.root.children())
+            writeNode(html, child, indexes);
+        html.append("</div>\n");
+
+        html.append("<pre id=\"source\"");
+        if (theme.foreground != null || theme.background != null) {
+            html.append(" style=\"");
+            if (theme.foreground != null)
+                html.append("color:").append(Highlighter.cssColor(theme.foreground)).append(';');
+            if (theme.background != null)
+                html.append("background:").append(Highlighter.cssColor(theme.background)).append(';');
+            html.append('"');
+        }
+        html.append('>');
+        int position = 0;
+        for (int i = 0; i < tokens.size(); i++) {
+            Token token = tokens.get(i);
+            if (token.isSynthetic() || token.offset < position)
+                continue;
+            int end = Math.min(token.end(), source.length());
+            Highlighter.escapeHtml(html, source, position, token.offset);
+            html.append("<span id=\"t").append(i).append("\" class=\"k-").append(token.type.name())
+                    .append("\" title=\"").append(token.type.name()).append("\">");
+            Highlighter.escapeHtml(html, source, token.offset, end);
+            html.append("</span>");
+            position = end;
+        }
+        Highlighter.escapeHtml(html, source, position, source.length());
+        html.append("</pre>\n<script>\n").append(SCRIPT).append("\n</script>\n</body>\n</html>\n");
+        return html.toString();
+    }
+
+    private static void writeNode(StringBuilder html, TokenTree.Node node, IdentityHashMap<Token, Integer> indexes) {
+        if (node instanceof TokenTree.Leaf) {
+            Token token = ((TokenTree.Leaf) node).token;
+            int index = indexes.get(token);
+            html.append("<div class=\"leaf k-").append(token.type.name()).append("\" data-from=\"").append(index)
+                    .append("\" data-to=\"").append(index).append("\"><span class=\"type\">")
+                    .append(token.type.name().toLowerCase(Locale.ROOT)).append("</span> ");
+            String text = token.text();
+            if (text != null)
+                Highlighter.escapeHtml(html, text, 0, text.length());
+            else if (token.type == TokenType.ERROR)
+                Highlighter.escapeHtml(html, token.value, 0, token.value.length());
+            html.append("</div>\n");
+            return;
+        }
+
+        TokenTree.Group group = (TokenTree.Group) node;
+        int from = indexes.get(group.open);
+        int to = group.close() != null ? indexes.get(group.close()) : lastIndex(group, indexes);
+        html.append("<details open><summary data-from=\"").append(from).append("\" data-to=\"").append(to)
+                .append('"').append(group.close() == null ? " class=\"unclosed\"" : "").append('>');
+        Highlighter.escapeHtml(html, group.delimiters(), 0, group.delimiters().length());
+        html.append(' ').append(group.open.line + 1).append(':').append(group.open.column + 1);
+        if (group.close() == null)
+            html.append(" (unclosed)");
+        html.append("</summary>\n");
+        for (TokenTree.Node child : group.children())
+            writeNode(html, child, indexes);
+        html.append("</details>\n");
+    }
+
+    private static int lastIndex(TokenTree.Group group, IdentityHashMap<Token, Integer> indexes) {
+        List<TokenTree.Node> children = group.children();
+        if (children.isEmpty())
+            return indexes.get(group.open);
+        TokenTree.Node last = children.get(children.size() - 1);
+        if (last instanceof TokenTree.Leaf)
+            return indexes.get(((TokenTree.Leaf) last).token);
+        TokenTree.Group lastGroup = (TokenTree.Group) last;
+        return lastGroup.close() != null ? indexes.get(lastGroup.close()) : lastIndex(lastGroup, indexes);
+    }
+}
@@ -0,0 +1,150 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.List;
+
+/**
+ * Exposes tokens the way tree-sitter-rust names its leaf nodes, so highlight queries written for it can run on this
+ * lexer's output. Positions follow tree-sitter: UTF-8 byte offsets, and points with a 0-based row and a column
+ * in bytes.
+ * <p>
+ * Keywords and punctuation become anonymous nodes named after their text (like "fn" or "::"), everything else
+ * becomes a named node (like identifier or string_literal). Whitespace and synthetic tokens are left out.
+ */
+public final class TreeSitter {
+    private TreeSitter() {
+    }
+
+    /**
+     * @param source the text the tokens were lexed from, needed for byte offsets
+     */
+    public static List<Node> nodes(String source, List<Token> tokens) {
+        ArrayList<Node> nodes = new ArrayList<>();
+        Cursor cursor = new Cursor(source);
+        for (Token token : tokens) {
+            if (token.isSynthetic() || token.type == TokenType.WHITESPACE)
+                continue;
+            if (token.offset < cursor.offset)
+                cursor = new Cursor(source);
+            cursor.advanceTo(token.offset);
+            Point start = cursor.point();
+            int startByte = cursor.byteOffset;
+            cursor.advanceTo(token.end());
+            nodes.add(new Node(kind(token, source), isNamed(token), startByte, cursor.byteOffset,
+                    start, cursor.point()));
+        }
+        return nodes;
+    }
+
+    private static String kind(Token token, String source) {
+        return switch (token.type) {
+            case IDENTIFIER, RAW_IDENTIFIER -> "identifier";
+            case SELF_VALUE -> "self";
+            case SUPER -> "super";
+            case CRATE -> "crate";
+            case MUT -> "mutable_specifier";
+            case TRUE, FALSE -> "boolean_literal";
+            case COMMENT, COMMENT_INNER_DOC, COMMENT_OUTER_DOC ->
+                    source.startsWith("/*", token.offset) ? "block_comment" : "line_comment";
+            case SHEBANG -> "shebang";
+            case LABEL, RAW_LABEL -> "label";
+            case LIFETIME, RAW_LIFETIME, STATIC_LIFETIME -> "lifetime";
+            case CHAR_LITERAL, BYTE_LITERAL -> "char_literal";
+            case STRING_LITERAL, BYTE_STRING_LITERAL -> "string_literal";
+            case RAW_STRING_LITERAL, RAW_BYTE_STRING_LITERAL -> "raw_string_literal";
+            case INT_LITERAL_DEC, INT_LITERAL_HEX, INT_LITERAL_OCTAL, INT_LITERAL_BIN -> "integer_literal";
+            case FLOAT_LITERAL -> "float_literal";
+            case CUSTOM -> token instanceof CustomToken ? ((CustomToken) token).kind : "identifier";
+            case ERROR -> "ERROR";
+            //keywords (including custom ones) and punctuation
+            default -> token.type.text != null ? token.type.text : source.substring(token.offset, token.end());
+        };
+    }
+
+    private static boolean isNamed(Token token) {
+        return switch (token.type) {
+            case SELF_VALUE, SUPER, CRATE, MUT, TRUE, FALSE -> true;
+            default -> token.type.text == null && token.type != TokenType.CUSTOM_KEYWORD;
+        };
+    }
+
+    // walks the source keeping track of UTF-8 byte offsets and rows
+    private static final class Cursor {
+        final String source;
+        int offset = 0;
+        int byteOffset = 0;
+        int row = 0;
+        int rowStartByte = 0;
+
+        Cursor(String source) {
+            this.source = source;
+        }
+
+        void advanceTo(int target) {
+            while (offset < target && offset < source.length()) {
+                char c = source.charAt(offset);
+                if (Character.isHighSurrogate(c) && offset + 1 < source.length()
+                        && Character.isLowSurrogate(source.charAt(offset + 1))) {
+                    byteOffset += 4;
+                    offset += 2;
+                    continue;
+                }
+                byteOffset += c < 0x80 ? 1 : c < 0x800 ? 2 : 3;
+                offset++;
+                if (c == '\n') {
+                    row++;
+                    rowStartByte = byteOffset;
+                }
+            }
+        }
+
+        Point point() {
+            return new Point(row, byteOffset - rowStartByte);
+        }
+    }
+
+    public static final class Node {
+        /** Node type, as used in tree-sitter queries */
+        public final String kind;
+        /** False for anonymous nodes (keywords and punctuation) */
+        public final boolean named;
+        public final int startByte;
+        public final int endByte;
+        public final Point startPoint;
+        public final Point endPoint;
+
+        private Node(String kind, boolean named, int startByte, int endByte, Point startPoint, Point endPoint) {
+            this.kind = kind;
+            this.named = named;
+            this.startByte = startByte;
+            this.endByte = endByte;
+            this.startPoint = startPoint;
+            this.endPoint = endPoint;
+        }
+
+        /**
+         * Same format as tree-sitter's own output, e.g. {@code (identifier [0, 3] - [0, 7])} or {@code "fn" [0, 0] - [0, 2]}.
+         */
+        @Override
+        public String toString() {
+            String range = startPoint + " - " + endPoint;
+            return named ? "(" + kind + " " + range + ")" : Json.quote(kind) + " " + range;
+        }
+    }
+
+    public static final class Point {
+        public final int row;
+        /** In bytes */
+        public final int column;
+
+        private Point(int row, int column) {
+            this.row = row;
+            this.column = column;
+        }
+
+        @Override
+        public String toString() {
+            return "[" + row + ", " + column + "]";
+        }
+    }
+}
@@ -0,0 +1,89 @@
+package ua.yuriih.rustlexer;
+
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+
+/**
+ * Attaches trivia ({@link TokenType#isTrivia() comments and whitespace}) to the tokens around it, like a formatter sees it.
+ * <p>
+ * Trivia that starts on the line where the previous token ends is trailing trivia of that token,
+ * e.g. {@code x = 1; // note}. Anything else is leading trivia of the next token.
+ */
+public final class Trivia {
+    /** Non-trivia tokens with their trivia, in order */
+    public final List<Attached> tokens;
+    /** Leading trivia with no token after it, at the end of the file */
+    public final List<Token> endOfFile;
+
+    private Trivia(List<Attached> tokens, List<Token> endOfFile) {
+        this.tokens = Collections.unmodifiableList(tokens);
+        this.endOfFile = Collections.unmodifiableList(endOfFile);
+    }
+
+    /**
+     * @param tokens lexer output, whitespace is only attached if the lexer was set to {@link LexerOptions.Builder#emitTrivia emit it}
+     */
+    public static Trivia attach(List<Token> tokens) {
+        ArrayList<Attached> attached = new ArrayList<>();
+        ArrayList<Token> pending = new ArrayList<>();
+        Attached previous = null;
+        int previousEndLine = -1;
+        for (Token token : tokens) {
+            if (token.type.isTrivia()) {
+                if (previous != null && pending.isEmpty() && token.line == previousEndLine) {
+                    previous.trailing.add(token);
+                    //a line break ends the trailing trivia
+                    previousEndLine = token.type == TokenType.WHITESPACE && token.value.indexOf('\n') >= 0
+                            ? -1 : endLine(token);
+                } else {
+                    pending.add(token);
+                }
+                continue;
+            }
+
+            previous = new Attached(token, pending);
+            attached.add(previous);
+            pending = new ArrayList<>();
+            previousEndLine = endLine(token);
+        }
+        return new Trivia(attached, pending);
+    }
+
+    private static int endLine(Token token) {
+        String text = token.text();
+        int line = token.line;
+        if (text != null) {
+            for (int i = 0; i < text.length(); i++) {
+                if (text.charAt(i) == '\n')
+                    line++;
+            }
+        }
+        return line;
+    }
+
+    public static final class Attached {
+        public final Token token;
+        private final List<Token> leading;
+        private final ArrayList<Token> trailing = new ArrayList<>();
+
+        private Attached(Token token, List<Token> leading) {
+            this.token = token;
+            this.leading = leading;
+        }
+
+        /**
+         * Trivia on the lines above the token, and before it on the same line.
+         */
+        public List<Token> leading() {
+            return Collections.unmodifiableList(leading);
+        }
+
+        /**
+         * Trivia after the token on the line where it ends.
+         */
+        public List<Token> trailing() {
+            return Collections.unmodifiableList(trailing);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+package ua.yuriih.rustlexer;
+
+import java.io.IOException;
+import java.io.InputStream;
+import java.io.PrintStream;
+import java.nio.file.ClosedWatchServiceException;
+import java.nio.file.FileSystems;
+import java.nio.file.Files;
+import java.nio.file.NoSuchFileException;
+import java.nio.file.Path;
+import java.nio.file.StandardWatchEventKinds;
+import java.nio.file.WatchEvent;
+import java.nio.file.WatchKey;
+import java.nio.file.WatchService;
+import java.util.ArrayList;
+import java.util.HashMap;
+import java.util.HashSet;
+import java.util.List;
+import java.util.stream.Stream;
+
+/**
+ * rlex watch: re-lexes .rs files when they change and reports new errors.
+ */
+final class WatchCommand {
+    private final PrintStream out;
+    private final HashMap<Path, FileResult> results = new HashMap<>();
+    private final HashMap<WatchKey, Path> watchedDirs = new HashMap<>();
+
+    WatchCommand(PrintStream out) {
+        this.out = out;
+    }
+
+    int run(Path root) throws IOException, InterruptedException {
+        try (WatchService watchService = FileSystems.getDefault().newWatchService()) {
+            Path dir = Files.isDirectory(root) ? root : root.toAbsolutePath().getParent();
+            registerRecursively(watchService, dir);
+
+            if (Files.isDirectory(root)) {
+                try (Stream<Path> files = Files.walk(root)) {
+                    files.filter(WatchCommand::isRustFile).sorted().forEach(this::initialLex);
+                }
+            } else {
+                initialLex(root);
+            }
+            printTotals();
+            out.println("Watching " + root + " for changes...");
+
+            while (true) {
+                WatchKey key;
+                try {
+                    key = watchService.take();
+                } catch (ClosedWatchServiceException e) {
+                    return 0;
+                }
+                Path keyDir = watchedDirs.get(key);
+                for (WatchEvent<?> event : key.pollEvents()) {
+                    if (event.kind() == StandardWatchEventKinds.OVERFLOW || keyDir == null)
+                        continue;
+                    Path path = keyDir.resolve((Path) event.context());
+                    if (event.kind() == StandardWatchEventKinds.ENTRY_CREATE && Files.isDirectory(path))
+                        registerRecursively(watchService, path);
+                    else if (Files.isDirectory(root) ? isRustFile(path) : path.equals(root.toAbsolutePath()))
+                        onChange(path, event.kind() == StandardWatchEventKinds.ENTRY_DELETE);
+                }
+                if (!key.reset())
+                    watchedDirs.remove(key);
+            }
+        }
+    }
+
+    private static boolean isRustFile(Path path) {
+        return path.toString().endsWith(".rs");
+    }
+
+    private void registerRecursively(WatchService watchService, Path dir) throws IOException {
+        try (Stream<Path> dirs = Files.walk(dir)) {
+            for (Path d : (Iterable<Path>) dirs.filter(Files::isDirectory)::iterator) {
+                WatchKey key = d.toAbsolutePath().register(watchService, StandardWatchEventKinds.ENTRY_CREATE,
+                        StandardWatchEventKinds.ENTRY_MODIFY, StandardWatchEventKinds.ENTRY_DELETE);
+                watchedDirs.put(key, d.toAbsolutePath());
+            }
+        }
+    }
+
+    private void initialLex(Path path) {
+        FileResult result = lex(path);
+        if (result == null)
+            return;
+        results.put(path.toAbsolutePath(), result);
+        for (Token error : result.errors)
+            printError(path, error);
+    }
+
+    private void onChange(Path path, boolean deleted) {
+        FileResult old = results.remove(path);
+        FileResult result = deleted ? null : lex(path);
+        if (result == null) {
+            if (old != null)
+                out.println(path + ": removed");
+            return;
+        }
+        results.put(path, result);
+
+        HashSet<String> oldErrors = new HashSet<>();
+        if (old != null) {
+            for (Token error : old.errors)
+                oldErrors.add(errorKey(error));
+        }
+        for (Token error : result.errors) {
+            if (!oldErrors.contains(errorKey(error)))
+                printError(path, error);
+        }
+
+        int oldErrorCount = old == null ? 0 : old.errors.size();
+        int oldTokenCount = old == null ? 0 : old.tokenCount;
+        out.printf("%s: %d errors (%+d), %d tokens (%+d)%n", path,
+                result.errors.size(), result.errors.size() - oldErrorCount,
+                result.tokenCount, result.tokenCount - oldTokenCount);
+        printTotals();
+    }
+
+    private void printTotals() {
+        int errors = 0;
+        int filesWithErrors = 0;
+        for (FileResult result : results.values()) {
+            errors += result.errors.size();
+            if (!result.errors.isEmpty())
+                filesWithErrors++;
+        }
+        out.printf("%d files, %d errors in %d files%n", results.size(), errors, filesWithErrors);
+    }
+
+    private void printError(Path path, Token error) {
+        out.printf("%s:%d:%d: %s%n", path, error.line + 1, error.column + 1, error.value);
+    }
+
+    // position is part of the key, so an error that moved is reported again
+    private static String errorKey(Token error) {
+        return error.line + ":" + error.column + ":" + error.value;
+    }
+
+    private FileResult lex(Path path) {
+        List<Token> tokens;
+        try (InputStream in = Files.newInputStream(path)) {
+            tokens = new Lexer(in).parse();
+        } catch (NoSuchFileException e) {
+            return null;
+        } catch (IOException e) {
+            out.println(path + ": " + e.getMessage());
+            return null;
+        }
+
+        ArrayList<Token> errors = new ArrayList<>();
+        for (Token token : tokens) {
+            if (token.type == TokenType.ERROR)
+                errors.add(token);
+        }
+        return new FileResult(tokens.size(), errors);
+    }
+
+    private static final class FileResult {
+        final int tokenCount;
+        final List<Token> errors;
+
+        FileResult(int tokenCount, List<Token> errors) {
+            this.tokenCount = tokenCount;
+            this.errors = errors;
+        }
+    }
+}